@@ -11,14 +11,27 @@ pub struct AppConfig {
     #[clap(long, env)]
     pub database_url: String,
 
-    /// How often (in seconds) a new block index is built
-    #[clap(long, env)]
-    pub interval: Option<NonZeroU64>,
+    #[command(flatten)]
+    pub indexer: IndexerConfig,
 
     #[command(flatten)]
     pub verbosity: Verbosity<InfoLevel>,
 }
 
+/// Configuration of the block index builder, independent of the db it
+/// writes to.
+#[derive(clap::Args)]
+pub struct IndexerConfig {
+    /// How often (in seconds) a new block index is built
+    #[clap(
+        id = "block_index_interval",
+        long = "interval",
+        env = "INTERVAL",
+        value_name = "INTERVAL"
+    )]
+    pub interval: Option<NonZeroU64>,
+}
+
 pub fn install_tracing_subscriber(
     verbosity: Verbosity<InfoLevel>,
     non_blocking_logger: NonBlocking,
@@ -0,0 +1,269 @@
+pub mod appstate;
+pub mod config;
+
+use std::future::Future;
+use std::num::NonZeroU64;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+use deadpool_diesel::postgres::Object;
+use orm::block_index::BlockIndex;
+use orm::notification::Notification;
+use orm::schema;
+use shared::error::{ContextDbInteractError, IntoMainError, MainError};
+use shared::exit_handle;
+use tokio::time::sleep;
+use xorf::{BinaryFuse16, Filter};
+
+use crate::appstate::AppState;
+use crate::config::IndexerConfig;
+
+macro_rules! exit {
+    () => {{
+        tracing::info!("Exiting...");
+        return Ok(());
+    }};
+}
+
+/// Periodically build the index of the block heights with MASP txs in
+/// the db of `app_state`, until the process is requested to exit.
+pub async fn run(
+    config: IndexerConfig,
+    app_state: AppState,
+) -> Result<(), MainError> {
+    let IndexerConfig { interval } = config;
+
+    let mut exit_handle = Box::pin(exit_handle::exited());
+
+    if wait_for_migrations(&mut exit_handle, &app_state)
+        .await
+        .is_break()
+    {
+        exit!();
+    }
+    build_block_indexes(&mut exit_handle, interval, &app_state).await;
+
+    exit!();
+}
+
+async fn wait_for_migrations<F>(
+    mut exit_handle: F,
+    app_state: &AppState,
+) -> ControlFlow<()>
+where
+    F: Future<Output = ()> + Unpin,
+{
+    while run_migrations(app_state).await.is_err() {
+        const SLEEP_AMOUNT: Duration = Duration::from_secs(5);
+
+        tracing::info!(after = ?SLEEP_AMOUNT, "Retrying migrations");
+
+        tokio::select! {
+            _ = &mut exit_handle => {
+                return ControlFlow::Break(());
+            }
+            _ = sleep(SLEEP_AMOUNT) => {}
+        }
+    }
+
+    ControlFlow::Continue(())
+}
+
+async fn build_block_indexes<F>(
+    mut exit_handle: F,
+    interval: Option<NonZeroU64>,
+    app_state: &AppState,
+) where
+    F: Future<Output = ()> + Unpin,
+{
+    const DEFAULT_SLEEP_AMOUNT: Duration = Duration::from_secs(30 * 60);
+    let sleep_amount = interval
+        .map(|interval| Duration::from_secs(interval.get()))
+        .unwrap_or(DEFAULT_SLEEP_AMOUNT);
+
+    loop {
+        tracing::debug!(after = ?sleep_amount, "Building new block index");
+
+        tokio::select! {
+            _ = &mut exit_handle => {
+                return;
+            }
+            _ = sleep(sleep_amount) => {
+                _ = build_new_block_index(app_state).await;
+            }
+        }
+    }
+}
+
+async fn run_migrations(app_state: &AppState) -> Result<(), MainError> {
+    use diesel_migrations::{
+        EmbeddedMigrations, MigrationHarness, embed_migrations,
+    };
+
+    const MIGRATIONS: EmbeddedMigrations =
+        embed_migrations!("../orm/migrations/");
+
+    async fn run_migrations_inner(conn: Object) -> anyhow::Result<()> {
+        tracing::debug!("Running db migrations...");
+
+        conn.interact(|transaction_conn| {
+            transaction_conn
+                .run_pending_migrations(MIGRATIONS)
+                .map_err(|_| anyhow!("Failed to run db migrations"))?;
+            anyhow::Ok(())
+        })
+        .await
+        .context_db_interact_error()??;
+
+        tracing::debug!("Finished running db migrations");
+
+        anyhow::Ok(())
+    }
+
+    run_migrations_inner(app_state.get_db_connection().await.into_db_error()?)
+        .await
+        .into_db_error()
+}
+
+async fn build_new_block_index(app_state: &AppState) -> Result<(), MainError> {
+    use diesel::connection::DefaultLoadingMode as DbDefaultLoadingMode;
+    use diesel::prelude::*;
+
+    tracing::info!("Starting new masp txs block index");
+
+    tracing::debug!("Reading all block heights with masp transactions from db");
+
+    let conn = app_state.get_db_connection().await.into_db_error()?;
+
+    let (last_height, block_heights_with_txs) = conn
+        .interact(|conn| {
+            conn.build_transaction().read_only().run(|conn| {
+                let last_height = {
+                    use diesel::prelude::OptionalExtension;
+                    use schema::chain_state::dsl::*;
+
+                    chain_state
+                        .select(block_height)
+                        .first(conn)
+                        .optional()
+                        .context("Failed to query last block height")
+                }?;
+
+                let block_heights_with_txs = {
+                    use schema::tx::dsl::*;
+
+                    tx.select(block_height)
+                        .distinct()
+                        .load_iter::<_, DbDefaultLoadingMode>(conn)
+                        .context("Failed to query block heights with masp txs")?
+                        .try_fold(
+                            Vec::new(),
+                            |mut accum, maybe_block_height| {
+                                tracing::debug!(
+                                    "Reading block height entry from db"
+                                );
+                                let height: i32 = maybe_block_height.context(
+                                    "Failed to get tx block height row data \
+                                     from db",
+                                )?;
+                                tracing::debug!(
+                                    "Read block height entry from db"
+                                );
+                                accum.push(u64::try_from(height).context(
+                                    "Failed to convert block height from i32 \
+                                     to u64",
+                                )?);
+                                anyhow::Ok(accum)
+                            },
+                        )
+                }?;
+
+                anyhow::Ok((last_height, block_heights_with_txs))
+            })
+        })
+        .await
+        .context_db_interact_error()
+        .into_db_error()?
+        .into_db_error()?;
+
+    let block_heights_len = block_heights_with_txs.len();
+    tracing::debug!(
+        num_blocks_with_masp_txs = block_heights_len,
+        "Read all block heights with masp transactions from db"
+    );
+
+    let serialized_filter = tokio::task::block_in_place(|| {
+        tracing::debug!(
+            "Building binary fuse xor filter of all heights with masp \
+             transactions"
+        );
+
+        let filter: BinaryFuse16 = block_heights_with_txs
+            .try_into()
+            .map_err(|err| {
+                anyhow!(
+                    "Failed to convert queried block heights into binary fuse \
+                     xor filter: {err}",
+                )
+            })
+            .into_conversion_error()?;
+
+        let serialized = bincode::serialize(&filter)
+            .context(
+                "Failed to serialze binary fuse xor filter of block heights",
+            )
+            .into_serialization_error()?;
+
+        tracing::debug!(
+            index_len = filter.len(),
+            "Binary fuse xor filter built"
+        );
+
+        Ok(serialized)
+    })?;
+
+    tracing::debug!("Storing binary fuse xor filter in db");
+
+    conn.interact(move |conn| {
+        use schema::block_index::dsl::*;
+
+        let db_filter = BlockIndex {
+            id: 0,
+            serialized_data: serialized_filter,
+            block_height: last_height.unwrap_or_default(),
+        };
+
+        diesel::insert_into(block_index)
+            .values(&db_filter)
+            .on_conflict(id)
+            .do_update()
+            .set((
+                block_height.eq(&db_filter.block_height),
+                serialized_data.eq(&db_filter.serialized_data),
+            ))
+            .execute(conn)
+            .context("Failed to insert masp txs block index into db")?;
+
+        Notification::BlockIndex {
+            block_height: db_filter.block_height as u64,
+        }
+        .send(conn)
+        .context("Failed to notify the new masp txs block index")?;
+
+        anyhow::Ok(())
+    })
+    .await
+    .context_db_interact_error()
+    .into_db_error()?
+    .into_db_error()?;
+
+    tracing::debug!("Stored binary fuse xor filter in db");
+
+    tracing::info!(
+        num_blocks_with_masp_txs = block_heights_len,
+        "Built and stored new masp txs block index"
+    );
+
+    Ok(())
+}
@@ -1,3 +1,6 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
 use clap_verbosity_flag::{InfoLevel, LevelFilter, Verbosity};
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
@@ -5,10 +8,31 @@ use tracing_subscriber::FmtSubscriber;
 #[derive(clap::Parser)]
 pub struct AppConfig {
     #[clap(long, env)]
-    pub cometbft_url: String,
+    pub database_url: String,
+
+    #[command(flatten)]
+    pub crawler: CrawlerConfig,
 
+    #[command(flatten)]
+    pub verbosity: Verbosity<InfoLevel>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Configuration of the crawler, independent of the db it writes to.
+#[derive(clap::Args)]
+pub struct CrawlerConfig {
+    /// URLs of the CometBFT RPC endpoints to query, separated by
+    /// commas, in order of preference
+    #[clap(long, env, required = true, value_delimiter = ',')]
+    pub cometbft_url: Vec<String>,
+
+    /// URL of a CometBFT WebSocket endpoint (e.g.
+    /// `ws://127.0.0.1:26657/websocket`), to be notified of new blocks
+    /// rather than polling for them
     #[clap(long, env)]
-    pub database_url: String,
+    pub cometbft_websocket_url: Option<String>,
 
     #[clap(long, env)]
     pub interval: Option<u64>,
@@ -19,11 +43,79 @@ pub struct AppConfig {
     #[clap(long, env, default_value_t = 0)]
     pub max_concurrent_fetches: usize,
 
+    /// Maximum number of blocks to fetch ahead of the last processed
+    /// block
+    #[clap(long, env, default_value_t = 1000)]
+    pub max_blocks_ahead: usize,
+
+    /// Maximum size, in bytes, of the fetched blocks waiting to be
+    /// processed, past which no new blocks are fetched
+    #[clap(long, env, default_value_t = 256 * 1024 * 1024)]
+    pub max_bytes_ahead: usize,
+
+    /// Check the root of the commitment tree against the anchors of the
+    /// chain after each block with new notes, if non-zero
     #[clap(long, env, default_value_t = 0)]
     pub number_of_witness_map_roots_to_check: usize,
 
-    #[command(flatten)]
-    pub verbosity: Verbosity<InfoLevel>,
+    /// Record the raw data of every block fetched from CometBFT into
+    /// the block archive at the given path
+    #[clap(long, env, conflicts_with = "replay_blocks")]
+    pub record_blocks: Option<PathBuf>,
+
+    /// Index the blocks in the block archive at the given path, rather
+    /// than fetching them from CometBFT, and exit after the last one
+    #[clap(long, env)]
+    pub replay_blocks: Option<PathBuf>,
+
+    /// Address to serve Prometheus metrics from, at `/metrics` (e.g.
+    /// `0.0.0.0:9090`)
+    #[clap(long, env)]
+    pub metrics_address: Option<SocketAddr>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Remove all indexed MASP data above the given block height, and
+    /// exit
+    Rollback {
+        /// Last block height to keep in the index
+        #[clap(long)]
+        to_height: u64,
+    },
+    /// Rebuild the commitment trees, witness maps and notes index from
+    /// the stored MASP txs, and exit
+    Rebuild {
+        /// First block height whose derived MASP data is rebuilt, or the
+        /// first indexed block height if omitted
+        #[clap(long)]
+        from_height: Option<u64>,
+    },
+    /// Write a snapshot of the indexed MASP state to a file, and exit
+    ExportSnapshot {
+        /// Path of the snapshot file to create
+        #[clap(long)]
+        path: PathBuf,
+    },
+    /// Load a snapshot of the indexed MASP state into an empty
+    /// database, and exit
+    ImportSnapshot {
+        /// Path of the snapshot file to load
+        #[clap(long)]
+        path: PathBuf,
+    },
+    /// Check the consistency of all the indexed MASP data, report the
+    /// first inconsistency found as JSON, and exit
+    Verify {
+        /// Also check that the root of every stored commitment tree is
+        /// an anchor on chain
+        #[clap(long)]
+        check_anchors: bool,
+        /// Path of the file to write the report to, rather than
+        /// standard output
+        #[clap(long)]
+        report: Option<PathBuf>,
+    },
 }
 
 pub fn install_tracing_subscriber(verbosity: Verbosity<InfoLevel>) {
@@ -0,0 +1,47 @@
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime};
+use orm::block::BlockInsertDb;
+use shared::block::Block;
+use shared::height::BlockHeight;
+
+/// Header of a committed block, from which timestamps are resolved to
+/// block heights.
+#[derive(Clone, Debug)]
+pub struct CommittedBlock {
+    pub block_height: BlockHeight,
+    pub hash: String,
+    pub timestamp: NaiveDateTime,
+    pub proposer_address: String,
+    pub app_hash: String,
+}
+
+impl CommittedBlock {
+    pub fn from_block(block: &Block) -> anyhow::Result<Self> {
+        let timestamp = DateTime::parse_from_rfc3339(&block.header.timestamp)
+            .with_context(|| {
+                format!(
+                    "Invalid timestamp {:?} in the header of block {}",
+                    block.header.timestamp, block.header.height
+                )
+            })?
+            .naive_utc();
+
+        Ok(Self {
+            block_height: block.header.height,
+            hash: block.hash.to_string(),
+            timestamp,
+            proposer_address: block.header.proposer_address.to_string(),
+            app_hash: block.header.app_hash.to_string(),
+        })
+    }
+
+    pub fn into_db(&self) -> BlockInsertDb {
+        BlockInsertDb {
+            block_height: self.block_height.0 as i32,
+            hash: self.hash.clone(),
+            timestamp: self.timestamp,
+            proposer_address: self.proposer_address.clone(),
+            app_hash: self.app_hash.clone(),
+        }
+    }
+}
@@ -1,20 +1,71 @@
-use orm::chain_state::ChainStateteInsertDb;
+use orm::chain_state::{ChainStateteInsertDb, NetworkIdentityDb};
+use shared::block::Block;
 use shared::height::BlockHeight;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct ChainState {
     pub block_height: BlockHeight,
+    /// Network of the block at `block_height`, if the chain state was
+    /// built from a fetched block.
+    pub network: Option<NetworkIdentity>,
+}
+
+/// Network the indexed blocks belong to, identified by its chain id and
+/// the hash of the first block committed to the db.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkIdentity {
+    pub chain_id: String,
+    pub first_block_height: BlockHeight,
+    pub first_block_hash: String,
 }
 
 impl ChainState {
     pub fn new(block_height: BlockHeight) -> Self {
-        Self { block_height }
+        Self {
+            block_height,
+            network: None,
+        }
+    }
+
+    /// Build the chain state after committing the given block.
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            block_height: block.header.height,
+            network: Some(NetworkIdentity {
+                chain_id: block.header.chain_id.clone(),
+                first_block_height: block.header.height,
+                first_block_hash: block.hash.to_string(),
+            }),
+        }
     }
 
     pub fn into_db(&self) -> ChainStateteInsertDb {
         ChainStateteInsertDb {
             id: 0, // NB: overwrite old row
             block_height: self.block_height.0 as i32,
+            chain_id: self
+                .network
+                .as_ref()
+                .map(|network| network.chain_id.clone()),
+            first_block_height: self
+                .network
+                .as_ref()
+                .map(|network| network.first_block_height.0 as i32),
+            first_block_hash: self
+                .network
+                .as_ref()
+                .map(|network| network.first_block_hash.clone()),
         }
     }
 }
+
+impl NetworkIdentity {
+    /// Read the network identity stored in the db, if it was recorded.
+    pub fn from_db(network: NetworkIdentityDb) -> Option<Self> {
+        Some(Self {
+            chain_id: network.chain_id?,
+            first_block_height: BlockHeight::from(network.first_block_height?),
+            first_block_hash: network.first_block_hash?,
+        })
+    }
+}
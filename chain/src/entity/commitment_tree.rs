@@ -1,79 +1,67 @@
-use anyhow::Context;
-use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
-use namada_sdk::masp_primitives::merkle_tree::CommitmentTree as MaspCommitmentTree;
+use namada_sdk::borsh::BorshSerializeExt;
 use namada_sdk::masp_primitives::sapling::Node;
-use orm::tree::{TreeDb, TreeInsertDb};
+use orm::tree::TreeInsertDb;
+use orm::witness_delta::WitnessDeltaInsertDb;
+use shared::bridge_tree::BridgeTree;
 use shared::height::BlockHeight;
-use shared::transactional::Transactional;
+use shared::witness_delta;
 
+/// The MASP note commitment tree, from which the witnesses of all of
+/// its notes are computed.
 #[derive(Debug)]
 pub struct CommitmentTree {
-    transactional: Transactional<MaspCommitmentTree<Node>>,
-}
-
-impl Default for CommitmentTree {
-    fn default() -> Self {
-        Self {
-            transactional: Transactional::new(MaspCommitmentTree::empty()),
-        }
-    }
+    tree: BridgeTree,
 }
 
 impl CommitmentTree {
-    pub const fn new(tree: MaspCommitmentTree<Node>) -> Self {
-        Self {
-            transactional: Transactional::new(tree),
-        }
+    pub fn new(mut tree: BridgeTree) -> Self {
+        tree.checkpoint();
+        Self { tree }
     }
 
     pub fn is_dirty(&self) -> bool {
-        self.transactional.is_dirty()
+        self.tree.last_checkpoint() != Some(self.tree.size())
     }
 
     pub fn rollback(&mut self) {
-        self.transactional.rollback();
+        self.tree.rewind();
     }
 
     pub fn append(&mut self, node: Node) -> bool {
-        self.transactional.as_mut().append(node).is_ok()
+        self.tree.append(node).is_ok()
     }
 
     pub fn size(&self) -> usize {
-        self.transactional.as_ref().size()
+        self.tree.size()
     }
 
     pub fn root(&self) -> Node {
-        self.transactional.as_ref().root()
-    }
-
-    pub fn get_tree(&self) -> MaspCommitmentTree<Node> {
-        self.transactional.as_ref().clone()
+        self.tree.root()
     }
 
     #[allow(clippy::wrong_self_convention)]
     pub fn into_db(
         &mut self,
         block_height: BlockHeight,
-    ) -> Option<TreeInsertDb> {
-        if !self.transactional.commit() {
+    ) -> Option<(TreeInsertDb, WitnessDeltaInsertDb)> {
+        if !self.is_dirty() {
             return None;
         }
-        Some(TreeInsertDb {
-            tree: self.transactional.as_ref().serialize_to_vec(),
-            block_height: block_height.0 as i32,
-        })
-    }
-}
 
-impl TryFrom<TreeDb> for CommitmentTree {
-    type Error = anyhow::Error;
+        let first_new_note = self.tree.last_checkpoint().unwrap_or(0);
+        self.tree.checkpoint();
 
-    fn try_from(value: TreeDb) -> Result<Self, Self::Error> {
-        let commitment_tree =
-            MaspCommitmentTree::<Node>::try_from_slice(&value.tree).context(
-                "Failed to deserialize commitment tree from db borsh encoded \
-                 bytes",
-            )?;
-        Ok(Self::new(commitment_tree))
+        Some((
+            TreeInsertDb {
+                tree: self.tree.commitment_tree().serialize_to_vec(),
+                block_height: block_height.0 as i32,
+            },
+            WitnessDeltaInsertDb {
+                block_height: block_height.0 as i32,
+                leaves: Some(witness_delta::serialize_leaves(
+                    self.tree.leaves_from(first_new_note),
+                )),
+            },
+        ))
     }
 }
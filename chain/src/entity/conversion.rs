@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context;
+use namada_core::masp::{MaspEpoch, encode_asset_type};
+use namada_sdk::address::Address;
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::masp_primitives::asset_type::AssetType;
+use namada_sdk::masp_primitives::transaction::components::I128Sum;
+use namada_sdk::token::{Denomination, MaspDigitPos};
+use orm::asset::AssetInsertDb;
+use orm::conversion::ConversionInsertDb;
+
+/// Conversion of each MASP asset type, as returned by
+/// [`namada_sdk::rpc::query_conversions`].
+pub type Conversions = BTreeMap<
+    AssetType,
+    (Address, Denomination, MaspDigitPos, MaspEpoch, I128Sum),
+>;
+
+/// Conversion state of the MASP as of some MASP epoch.
+#[derive(Clone, Debug)]
+pub struct ConversionState {
+    pub masp_epoch: MaspEpoch,
+    pub conversions: Conversions,
+}
+
+impl ConversionState {
+    pub fn into_db(&self) -> anyhow::Result<Vec<ConversionInsertDb>> {
+        let masp_epoch = masp_epoch_to_db(self.masp_epoch)?;
+
+        self.conversions
+            .iter()
+            .map(
+                |(asset_type, (token, denom, digit_pos, asset_epoch, conv))| {
+                    Ok(ConversionInsertDb {
+                        masp_epoch,
+                        asset_type: asset_type.to_string(),
+                        token: token.to_string(),
+                        denom: denom.0 as i16,
+                        digit_pos: *digit_pos as u8 as i16,
+                        asset_epoch: masp_epoch_to_db(*asset_epoch)?,
+                        conversion_bytes: conv.serialize_to_vec(),
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Get the pre-images of the asset types in the conversion state,
+    /// along with the undated asset types of its tokens.
+    pub fn assets(&self) -> anyhow::Result<Vec<AssetInsertDb>> {
+        let mut assets = self
+            .conversions
+            .iter()
+            .map(|(asset_type, (token, denom, digit_pos, asset_epoch, _))| {
+                Ok(AssetInsertDb {
+                    asset_type: asset_type.to_string(),
+                    token: token.to_string(),
+                    denom: denom.0 as i16,
+                    digit_pos: *digit_pos as u8 as i16,
+                    epoch: Some(masp_epoch_to_db(*asset_epoch)?),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let tokens: BTreeSet<_> = self
+            .conversions
+            .values()
+            .map(|(token, denom, ..)| (token, *denom))
+            .collect();
+
+        for (token, denom) in tokens {
+            assets.extend(undated_assets(token, denom)?);
+        }
+
+        Ok(assets)
+    }
+}
+
+/// Get the pre-images of the undated asset types of the given token.
+pub fn undated_assets(
+    token: &Address,
+    denom: Denomination,
+) -> anyhow::Result<Vec<AssetInsertDb>> {
+    MaspDigitPos::iter()
+        .map(|digit_pos| {
+            let asset_type =
+                encode_asset_type(token.clone(), denom, digit_pos, None)
+                    .with_context(|| {
+                        format!(
+                            "Failed to encode the undated asset type of token \
+                             {token}"
+                        )
+                    })?;
+
+            Ok(AssetInsertDb {
+                asset_type: asset_type.to_string(),
+                token: token.to_string(),
+                denom: denom.0 as i16,
+                digit_pos: digit_pos as u8 as i16,
+                epoch: None,
+            })
+        })
+        .collect()
+}
+
+/// Get the numeric value of a MASP epoch, as stored in the database.
+pub fn masp_epoch_to_db(masp_epoch: MaspEpoch) -> anyhow::Result<i32> {
+    // NB: the inner epoch of a `MaspEpoch` is only exposed through its
+    // string representation
+    masp_epoch
+        .to_string()
+        .parse()
+        .with_context(|| format!("MASP epoch {masp_epoch} is out of range"))
+}
@@ -1,4 +1,8 @@
+pub mod block;
 pub mod chain_state;
 pub mod commitment_tree;
+pub mod conversion;
+pub mod snapshot;
+pub mod tx_flow;
 pub mod tx_notes_index;
-pub mod witness_map;
+pub mod verification;
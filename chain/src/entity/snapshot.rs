@@ -0,0 +1,238 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::DateTime;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
+use orm::block::{BlockDb, BlockInsertDb};
+use orm::notes_index::{NotesIndexDb, NotesIndexInsertDb};
+use orm::tx::{TxDb, TxInsertDb};
+use sha2::{Digest, Sha256};
+use shared::height::BlockHeight;
+
+use crate::entity::chain_state::NetworkIdentity;
+
+/// Magic bytes at the start of every snapshot file.
+const MAGIC: &[u8; 8] = b"MASPSNAP";
+
+/// Version of the snapshot file format.
+const FORMAT_VERSION: u32 = 4;
+
+/// Length of the checksum at the end of every snapshot file.
+const CHECKSUM_LEN: usize = 32;
+
+/// Full state of the indexer at some block height, from which a new
+/// instance can resume crawling.
+///
+/// Snapshot files are laid out as the [`MAGIC`] bytes, the format
+/// version, the borsh encoded snapshot, and a SHA-256 checksum of
+/// all the preceding bytes.
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "namada_sdk::borsh")]
+pub struct Snapshot {
+    /// Identity of the network the snapshot was taken from, as recorded
+    /// in the db it was exported from.
+    pub chain_id: String,
+    pub first_block_height: u64,
+    pub first_block_hash: String,
+    pub block_height: u64,
+    /// Borsh encoded MASP commitment tree at the snapshot height.
+    pub commitment_tree: Vec<u8>,
+    /// Serialized leaves of the commitment tree. The witness map is
+    /// rebuilt from these, upon importing the snapshot.
+    pub leaves: Vec<u8>,
+    pub notes_index: Vec<SnapshotNote>,
+    pub txs: Vec<SnapshotTx>,
+    pub blocks: Vec<SnapshotBlock>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "namada_sdk::borsh")]
+pub struct SnapshotNote {
+    pub block_index: i32,
+    pub note_position: i32,
+    pub block_height: i32,
+    pub masp_tx_index: i32,
+    pub is_masp_fee_payment: bool,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "namada_sdk::borsh")]
+pub struct SnapshotTx {
+    pub block_index: i32,
+    pub tx_bytes: Vec<u8>,
+    pub block_height: i32,
+    pub masp_tx_index: i32,
+    pub is_masp_fee_payment: bool,
+    pub tx_hash: Option<String>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "namada_sdk::borsh")]
+pub struct SnapshotBlock {
+    pub block_height: i32,
+    pub hash: String,
+    /// Microseconds elapsed since the Unix epoch.
+    pub timestamp: i64,
+    pub proposer_address: String,
+    pub app_hash: String,
+}
+
+impl Snapshot {
+    pub fn block_height(&self) -> BlockHeight {
+        BlockHeight::from(self.block_height)
+    }
+
+    pub fn network(&self) -> NetworkIdentity {
+        NetworkIdentity {
+            chain_id: self.chain_id.clone(),
+            first_block_height: BlockHeight::from(self.first_block_height),
+            first_block_hash: self.first_block_hash.clone(),
+        }
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.serialize_to_vec());
+
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum);
+
+        let mut file = std::fs::File::create_new(path).with_context(|| {
+            format!("Failed to create snapshot file {}", path.display())
+        })?;
+        file.write_all(&bytes)
+            .and_then(|()| file.sync_all())
+            .with_context(|| {
+                format!("Failed to write snapshot file {}", path.display())
+            })?;
+
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &Path) -> anyhow::Result<Self> {
+        let mut bytes = Vec::new();
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .with_context(|| {
+                format!("Failed to read snapshot file {}", path.display())
+            })?;
+
+        let header_len = MAGIC.len() + size_of::<u32>();
+        if bytes.len() < header_len + CHECKSUM_LEN {
+            anyhow::bail!("Snapshot file {} is truncated", path.display());
+        }
+
+        let (contents, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if Sha256::digest(contents).as_slice() != checksum {
+            anyhow::bail!(
+                "Checksum mismatch in snapshot file {}",
+                path.display()
+            );
+        }
+
+        let (header, payload) = contents.split_at(header_len);
+        let (magic, version) = header.split_at(MAGIC.len());
+        if magic != MAGIC {
+            anyhow::bail!("{} is not a snapshot file", path.display());
+        }
+        let version = u32::from_le_bytes(
+            version.try_into().expect("Version should be 4 bytes long"),
+        );
+        if version != FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported snapshot format version {version}, expected \
+                 {FORMAT_VERSION}"
+            );
+        }
+
+        Self::try_from_slice(payload).context("Failed to decode snapshot")
+    }
+}
+
+impl From<NotesIndexDb> for SnapshotNote {
+    fn from(note: NotesIndexDb) -> Self {
+        Self {
+            block_index: note.block_index,
+            note_position: note.note_position,
+            block_height: note.block_height,
+            masp_tx_index: note.masp_tx_index,
+            is_masp_fee_payment: note.is_masp_fee_payment,
+        }
+    }
+}
+
+impl From<SnapshotNote> for NotesIndexInsertDb {
+    fn from(note: SnapshotNote) -> Self {
+        Self {
+            block_index: note.block_index,
+            note_position: note.note_position,
+            block_height: note.block_height,
+            masp_tx_index: note.masp_tx_index,
+            is_masp_fee_payment: note.is_masp_fee_payment,
+        }
+    }
+}
+
+impl From<TxDb> for SnapshotTx {
+    fn from(tx: TxDb) -> Self {
+        Self {
+            block_index: tx.block_index,
+            tx_bytes: tx.tx_bytes,
+            block_height: tx.block_height,
+            masp_tx_index: tx.masp_tx_index,
+            is_masp_fee_payment: tx.is_masp_fee_payment,
+            tx_hash: tx.tx_hash,
+        }
+    }
+}
+
+impl From<SnapshotTx> for TxInsertDb {
+    fn from(tx: SnapshotTx) -> Self {
+        Self {
+            block_index: tx.block_index,
+            tx_bytes: tx.tx_bytes,
+            block_height: tx.block_height,
+            masp_tx_index: tx.masp_tx_index,
+            is_masp_fee_payment: tx.is_masp_fee_payment,
+            tx_hash: tx.tx_hash,
+        }
+    }
+}
+
+impl From<BlockDb> for SnapshotBlock {
+    fn from(block: BlockDb) -> Self {
+        Self {
+            block_height: block.block_height,
+            hash: block.hash,
+            timestamp: block.timestamp.and_utc().timestamp_micros(),
+            proposer_address: block.proposer_address,
+            app_hash: block.app_hash,
+        }
+    }
+}
+
+impl TryFrom<SnapshotBlock> for BlockInsertDb {
+    type Error = anyhow::Error;
+
+    fn try_from(block: SnapshotBlock) -> anyhow::Result<Self> {
+        let timestamp = DateTime::from_timestamp_micros(block.timestamp)
+            .with_context(|| {
+                format!(
+                    "Invalid timestamp of snapshot block {}",
+                    block.block_height
+                )
+            })?
+            .naive_utc();
+
+        Ok(Self {
+            block_height: block.block_height,
+            hash: block.hash,
+            timestamp,
+            proposer_address: block.proposer_address,
+            app_hash: block.app_hash,
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use namada_sdk::masp_primitives::asset_type::AssetType;
+use namada_sdk::masp_primitives::transaction::Transaction;
+use orm::numeric::numeric_from_i128;
+use orm::tx_flow::TxFlowInsertDb;
+use shared::indexed_tx::{IndexedTx, MaspIndexedTx, MaspTxKind};
+
+/// Value moved in and out of the shielded pool by a MASP tx, in some
+/// asset type.
+#[derive(Default, Clone, Copy, Debug)]
+struct Flow {
+    /// Value of the transparent inputs of the tx.
+    transparent_in: i128,
+    /// Value of the transparent outputs of the tx.
+    transparent_out: i128,
+    /// Value balance of the sapling bundle of the tx.
+    value_balance: i128,
+}
+
+/// Flows of a MASP tx, per asset type.
+#[derive(Default, Clone, Debug)]
+pub struct TxFlows(BTreeMap<AssetType, Flow>);
+
+impl TxFlows {
+    pub fn new(masp_tx: &Transaction) -> Self {
+        let mut flows = BTreeMap::<_, Flow>::new();
+
+        if let Some(bundle) = masp_tx.transparent_bundle() {
+            for tx_in in &bundle.vin {
+                flows.entry(tx_in.asset_type).or_default().transparent_in +=
+                    i128::from(tx_in.value);
+            }
+            for tx_out in &bundle.vout {
+                flows.entry(tx_out.asset_type).or_default().transparent_out +=
+                    i128::from(tx_out.value);
+            }
+        }
+
+        if let Some(bundle) = masp_tx.sapling_bundle() {
+            for (asset_type, value) in bundle.value_balance.components() {
+                flows.entry(*asset_type).or_default().value_balance += *value;
+            }
+        }
+
+        Self(flows)
+    }
+
+    pub fn into_db(
+        self,
+        MaspIndexedTx {
+            indexed_tx:
+                IndexedTx {
+                    block_height,
+                    block_index,
+                    masp_tx_index,
+                },
+            kind,
+        }: MaspIndexedTx,
+    ) -> impl Iterator<Item = TxFlowInsertDb> {
+        self.0
+            .into_iter()
+            .map(move |(asset_type, flow)| TxFlowInsertDb {
+                block_height: block_height.0 as i32,
+                block_index: block_index.0 as i32,
+                masp_tx_index: masp_tx_index.0 as i32,
+                is_masp_fee_payment: matches!(kind, MaspTxKind::FeePayment),
+                asset_type: asset_type.to_string(),
+                transparent_in: numeric_from_i128(flow.transparent_in),
+                transparent_out: numeric_from_i128(flow.transparent_out),
+                value_balance: numeric_from_i128(flow.value_balance),
+            })
+    }
+}
@@ -0,0 +1,71 @@
+use serde::Serialize;
+use shared::height::BlockHeight;
+
+/// Outcome of the verification of the whole index, meant to be
+/// consumed by other programs.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VerificationReport {
+    /// No inconsistency was found.
+    Consistent(VerificationSummary),
+    /// The first inconsistency found, in the order the checks are run.
+    Inconsistent(Inconsistency),
+}
+
+/// Amount of data covered by the verification.
+#[derive(Debug, Default, Serialize)]
+pub struct VerificationSummary {
+    pub last_block_height: Option<u64>,
+    pub num_txs: usize,
+    pub num_notes: usize,
+    pub num_notes_index_entries: usize,
+    pub num_witness_map_deltas: usize,
+    pub num_commitment_trees: usize,
+    pub num_anchors: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Inconsistency {
+    pub check: Check,
+    pub block_height: Option<u64>,
+    pub note_position: Option<usize>,
+    pub message: String,
+}
+
+/// Checks run by the verification, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The notes index refers to the position of the first note of each
+    /// MASP tx, in the order they were applied to the commitment tree.
+    NotesIndex,
+    /// The leaves of each witness map delta are the notes created by the
+    /// MASP txs since the previous delta.
+    WitnessMapDelta,
+    /// The commitment tree stored at each height holds the notes created
+    /// by the MASP txs up to that height.
+    CommitmentTree,
+    /// The root of each stored commitment tree is an anchor on chain.
+    Anchor,
+}
+
+impl Inconsistency {
+    pub fn new(check: Check, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            block_height: None,
+            note_position: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at_height(mut self, block_height: BlockHeight) -> Self {
+        self.block_height = Some(block_height.0);
+        self
+    }
+
+    pub fn at_note(mut self, note_position: usize) -> Self {
+        self.note_position = Some(note_position);
+        self
+    }
+}
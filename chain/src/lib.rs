@@ -0,0 +1,1203 @@
+pub mod appstate;
+pub mod config;
+pub mod entity;
+pub mod services;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+use std::env;
+use std::future::poll_fn;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use anyhow::Context;
+use namada_sdk::address::Address;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_sdk::masp_primitives::merkle_tree::CommitmentTree as MaspCommitmentTree;
+use namada_sdk::masp_primitives::sapling::Node;
+use namada_sdk::token::Denomination;
+use shared::block::Block;
+use shared::block_source::{
+    BlockSource, RecordingBlockSource, ReplayBlockSource,
+};
+use shared::bridge_tree::BridgeTree;
+use shared::client::Client;
+use shared::error::{IntoMainError, MainError};
+use shared::fetch_window::FetchWindow;
+use shared::height::{BlockHeight, FollowingHeights, UnprocessedBlocks};
+use shared::indexed_tx::MaspIndexedTx;
+use shared::metrics::{
+    FETCH_DURATION_HISTOGRAM, VALIDATION_DURATION_HISTOGRAM,
+};
+use shared::subscription::NewBlockSubscription;
+use shared::transaction::Transaction;
+use shared::{exit_handle, retry, witness_delta};
+use tokio::sync::{Semaphore, mpsc};
+use tokio::time::{Instant, sleep};
+
+use crate::appstate::AppState;
+use crate::config::{Command, CrawlerConfig};
+use crate::entity::block::CommittedBlock;
+use crate::entity::chain_state::{ChainState, NetworkIdentity};
+use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::conversion::{masp_epoch_to_db, undated_assets};
+use crate::entity::snapshot::Snapshot;
+use crate::entity::tx_notes_index::TxNoteMap;
+use crate::entity::verification::{Check, Inconsistency, VerificationReport};
+use crate::services::db::{
+    NetworkMismatch, SnapshotRows, UnregisteredAssetTypes, Verification,
+};
+use crate::services::{
+    cometbft as cometbft_service, db as db_service, masp as masp_service,
+};
+
+const DEFAULT_INTERVAL: u64 = 5;
+const DEFAULT_MAX_CONCURRENT_FETCHES: usize = 100;
+
+/// Index the MASP data of the chain into the db of `app_state`, or run
+/// the given maintenance `command` against it, until the process is
+/// requested to exit.
+pub async fn run(
+    config: CrawlerConfig,
+    command: Option<Command>,
+    app_state: AppState,
+) -> Result<(), MainError> {
+    let CrawlerConfig {
+        cometbft_url,
+        interval,
+        starting_block_height,
+        number_of_witness_map_roots_to_check,
+        max_concurrent_fetches,
+        max_blocks_ahead,
+        max_bytes_ahead,
+        cometbft_websocket_url,
+        record_blocks,
+        replay_blocks,
+        metrics_address,
+    } = config;
+
+    if let Some(address) = metrics_address {
+        shared::metrics::install_prometheus_exporter(address)
+            .into_main_error("Metrics error")?;
+    }
+
+    run_migrations(&app_state).await?;
+
+    db_service::migrate_legacy_witness_maps(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    match command {
+        Some(Command::Rollback { to_height }) => {
+            return rollback(&app_state, BlockHeight::from(to_height)).await;
+        }
+        Some(Command::Rebuild { from_height }) => {
+            return rebuild(
+                &app_state,
+                BlockHeight::from(from_height.unwrap_or_default()),
+            )
+            .await;
+        }
+        Some(Command::ExportSnapshot { path }) => {
+            return export_snapshot(&app_state, &path).await;
+        }
+        Some(Command::ImportSnapshot { path }) => {
+            let client = Client::new(&cometbft_url);
+            return import_snapshot(&app_state, &client, &path).await;
+        }
+        Some(Command::Verify {
+            check_anchors,
+            report,
+        }) => {
+            let client = Client::new(&cometbft_url);
+            return verify(
+                &app_state,
+                &client,
+                check_anchors,
+                report.as_deref(),
+            )
+            .await;
+        }
+        None => {}
+    }
+
+    let (last_block_height, commitment_tree) =
+        load_committed_state(&app_state, starting_block_height).await?;
+
+    let client = Client::new(&cometbft_url);
+
+    let retry_interval = Duration::from_millis(
+        interval
+            .map(|millis| millis * 1000)
+            .unwrap_or(DEFAULT_INTERVAL * 1000),
+    );
+
+    // NB: replayed blocks are checked against the recorded network
+    // identity as they are committed
+    if replay_blocks.is_none() {
+        check_network_identity(&app_state, &client).await?;
+    }
+
+    let fetch_window = FetchWindow::new(max_blocks_ahead, max_bytes_ahead);
+
+    match (record_blocks, replay_blocks) {
+        (_, Some(path)) => {
+            tracing::info!(path = %path.display(), "Replaying blocks from archive");
+
+            let block_source =
+                ReplayBlockSource::open(&path, last_block_height)
+                    .into_main_error("Block archive error")?;
+
+            crawl(
+                &app_state,
+                &client,
+                block_source,
+                None,
+                last_block_height,
+                commitment_tree,
+                retry_interval,
+                fetch_window,
+                max_concurrent_fetches,
+                number_of_witness_map_roots_to_check,
+            )
+            .await
+        }
+        (Some(path), None) => {
+            tracing::info!(path = %path.display(), "Recording blocks to archive");
+
+            let subscription =
+                subscribe_to_new_blocks(cometbft_websocket_url, retry_interval);
+
+            let block_source = RecordingBlockSource::new(client.clone(), &path)
+                .into_main_error("Block archive error")?;
+
+            watching_conversions(
+                &app_state,
+                &client,
+                retry_interval,
+                crawl(
+                    &app_state,
+                    &client,
+                    block_source,
+                    subscription,
+                    last_block_height,
+                    commitment_tree,
+                    retry_interval,
+                    fetch_window,
+                    max_concurrent_fetches,
+                    number_of_witness_map_roots_to_check,
+                ),
+            )
+            .await
+        }
+        (None, None) => {
+            let subscription =
+                subscribe_to_new_blocks(cometbft_websocket_url, retry_interval);
+
+            watching_conversions(
+                &app_state,
+                &client,
+                retry_interval,
+                crawl(
+                    &app_state,
+                    &client,
+                    client.clone(),
+                    subscription,
+                    last_block_height,
+                    commitment_tree,
+                    retry_interval,
+                    fetch_window,
+                    max_concurrent_fetches,
+                    number_of_witness_map_roots_to_check,
+                ),
+            )
+            .await
+        }
+    }
+}
+
+/// Index the blocks served by `block_source`, until the indexer is
+/// requested to exit, or the last block of a finite source has been
+/// indexed.
+#[allow(clippy::too_many_arguments)]
+async fn crawl<S: BlockSource>(
+    app_state: &AppState,
+    client: &Client,
+    block_source: S,
+    subscription: Option<NewBlockSubscription>,
+    last_block_height: Option<BlockHeight>,
+    mut commitment_tree: CommitmentTree,
+    retry_interval: Duration,
+    fetch_window: FetchWindow,
+    max_concurrent_fetches: usize,
+    number_of_witness_map_roots_to_check: usize,
+) -> Result<(), MainError> {
+    let final_height = block_source.final_height();
+
+    if final_height.is_some_and(|final_height| {
+        last_block_height.unwrap_or_default() >= final_height
+    }) {
+        tracing::info!(
+            ?last_block_height,
+            "All the blocks of the block source have been indexed"
+        );
+        return Ok(());
+    }
+
+    let mut tx_notes_index = TxNoteMap::default();
+    let mut shielded_txs = BTreeMap::new();
+
+    let mut fetched_blocks = fetch_blocks_and_get_handle(
+        last_block_height,
+        max_concurrent_fetches,
+        retry_interval,
+        block_source,
+        subscription,
+        fetch_window.clone(),
+    );
+
+    let mut unprocessed_blocks = UnprocessedBlocks::new(last_block_height);
+
+    'crawl: while let Some(block_data) =
+        get_new_block_from_fetcher(&mut fetched_blocks).await
+    {
+        let received_block_height = block_data.header.height;
+
+        // Sort the fetched block
+        let next_block = unprocessed_blocks.next_to_process(block_data);
+
+        shared::metrics::record_queued_blocks(
+            fetched_blocks.len() + unprocessed_blocks.num_buffered(),
+        );
+
+        let Some(block_data) = next_block else {
+            tracing::info!(%received_block_height, "Queueing block to be processed");
+            continue;
+        };
+
+        // NB: process the block we just received, followed by any
+        // blocks that were buffered while waiting for it
+        let mut next_block = Some(block_data);
+
+        while let Some(block_data) = next_block {
+            next_block = unprocessed_blocks.next_buffered();
+
+            let block_height = block_data.header.height;
+
+            // Check if we can skip committing this block for now.
+            // This is because the block is empty. We can make a
+            // single remote procedure call to Postgres, when we
+            // exit.
+            if unprocessed_blocks.pre_commit_check_if_skip(&block_data) {
+                tracing::info!(%block_height, "Skipping commit of empty block");
+                shared::metrics::record_committed_block(block_height, 0, 0);
+            } else {
+                tracing::info!(%block_height, "Dequeued block to be processed");
+
+                // Build and commit MASP data at the block height
+                match retry::every(retry_interval, async || {
+                    build_and_commit_masp_data_at_height(
+                        block_data.clone(),
+                        client,
+                        &mut commitment_tree,
+                        &mut tx_notes_index,
+                        &mut shielded_txs,
+                        app_state,
+                        number_of_witness_map_roots_to_check,
+                    )
+                    .await
+                })
+                .await
+                {
+                    ControlFlow::Continue(ControlFlow::Continue(())) => {}
+                    ControlFlow::Continue(ControlFlow::Break(())) => {
+                        return Err(MainError);
+                    }
+                    ControlFlow::Break(()) => break 'crawl,
+                }
+            }
+
+            fetch_window.release(block_height);
+
+            if final_height == Some(block_height) {
+                tracing::info!(
+                    %block_height,
+                    "Reached the last block of the block source"
+                );
+                break 'crawl;
+            }
+        }
+    }
+
+    if let Some(block_data) = unprocessed_blocks.finalize() {
+        tracing::warn!(
+            block_height = %block_data.header.height,
+            "Single attempt at commiting data to db just before exiting"
+        );
+
+        // Make a feeble attempt at committing before exiting
+        // for good
+        if let ControlFlow::Break(()) = build_and_commit_masp_data_at_height(
+            block_data.clone(),
+            client,
+            &mut commitment_tree,
+            &mut tx_notes_index,
+            &mut shielded_txs,
+            app_state,
+            number_of_witness_map_roots_to_check,
+        )
+        .await?
+        {
+            return Err(MainError);
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `crawler` to completion, while indexing the MASP conversion
+/// state of each new MASP epoch, and registering the asset types of the
+/// committed txs.
+async fn watching_conversions(
+    app_state: &AppState,
+    client: &Client,
+    interval: Duration,
+    crawler: impl Future<Output = Result<(), MainError>>,
+) -> Result<(), MainError> {
+    tokio::select! {
+        result = crawler => result,
+        never = watch_conversions(app_state, client, interval) => match never {},
+    }
+}
+
+/// Poll the MASP epoch of the chain every `interval`, and index the
+/// conversion state of every MASP epoch not yet in the db. The asset
+/// types of the txs committed in the meantime are then registered.
+async fn watch_conversions(
+    app_state: &AppState,
+    client: &Client,
+    interval: Duration,
+) -> Infallible {
+    let mut last_masp_epoch = None;
+    let mut tx_asset_types = TxAssetTypes::default();
+
+    loop {
+        match retry::every(interval, async || {
+            index_conversion_state(app_state, client, last_masp_epoch).await
+        })
+        .await
+        {
+            ControlFlow::Continue(masp_epoch) => {
+                last_masp_epoch = masp_epoch;
+            }
+            // NB: leave it to the crawler to wind down
+            ControlFlow::Break(()) => std::future::pending().await,
+        }
+
+        // NB: the asset types are registered on a best effort basis, such
+        // that a failure does not hold back the conversion state
+        if let Err(err) =
+            register_tx_asset_types(app_state, client, &mut tx_asset_types)
+                .await
+        {
+            tracing::warn!(
+                summary = %err,
+                "Failed to register the asset types of committed txs"
+            );
+        }
+
+        sleep(interval).await;
+    }
+}
+
+/// Index the current conversion state, if its MASP epoch is more recent
+/// than `last_masp_epoch`. Returns the last indexed MASP epoch.
+async fn index_conversion_state(
+    app_state: &AppState,
+    client: &Client,
+    last_masp_epoch: Option<i32>,
+) -> Result<Option<i32>, MainError> {
+    let last_masp_epoch = match last_masp_epoch {
+        Some(masp_epoch) => Some(masp_epoch),
+        None => db_service::get_last_conversions_epoch(
+            app_state.get_db_connection().await.into_db_error()?,
+        )
+        .await
+        .into_db_error()?,
+    };
+
+    let masp_epoch = cometbft_service::query_masp_epoch(client)
+        .await
+        .and_then(masp_epoch_to_db)
+        .into_rpc_error()?;
+
+    if last_masp_epoch.is_some_and(|last| last >= masp_epoch) {
+        return Ok(last_masp_epoch);
+    }
+
+    tracing::info!(masp_epoch, "Indexing MASP conversion state...");
+
+    let conversion_state = cometbft_service::query_conversion_state(client)
+        .await
+        .into_rpc_error()?;
+    let masp_epoch = masp_epoch_to_db(conversion_state.masp_epoch)
+        .into_conversion_error()?;
+    let conversions = conversion_state.into_db().into_conversion_error()?;
+    let assets = conversion_state.assets().into_conversion_error()?;
+    let num_conversions = conversions.len();
+    let num_assets = assets.len();
+
+    db_service::insert_conversions(
+        app_state.get_db_connection().await.into_db_error()?,
+        masp_epoch,
+        conversions,
+        assets,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(
+        masp_epoch,
+        num_conversions,
+        num_assets,
+        "Indexed MASP conversion state"
+    );
+
+    Ok(Some(masp_epoch))
+}
+
+/// Asset types of the committed txs, which could not be registered yet.
+#[derive(Default)]
+struct TxAssetTypes {
+    unregistered: BTreeSet<String>,
+    /// Last height of the txs whose asset types were read from the db.
+    last_block_height: Option<i32>,
+    /// Denominations of the tokens queried so far.
+    denoms: BTreeMap<Address, Option<Denomination>>,
+}
+
+/// Register the pre-images of the asset types of the committed txs,
+/// which are not part of the conversion state. These are the undated
+/// asset types of the native token and of the IBC tokens.
+async fn register_tx_asset_types(
+    app_state: &AppState,
+    client: &Client,
+    tx_asset_types: &mut TxAssetTypes,
+) -> Result<(), MainError> {
+    let UnregisteredAssetTypes {
+        asset_types,
+        last_block_height,
+    } = db_service::get_unregistered_asset_types(
+        app_state.get_db_connection().await.into_db_error()?,
+        tx_asset_types.last_block_height,
+        // NB: the unregistered asset types must outlive a failed read
+        tx_asset_types.unregistered.clone(),
+    )
+    .await
+    .into_db_error()?;
+
+    tx_asset_types.unregistered = asset_types;
+    tx_asset_types.last_block_height = last_block_height;
+
+    if tx_asset_types.unregistered.is_empty() {
+        return Ok(());
+    }
+
+    let mut tokens = cometbft_service::query_ibc_tokens(client)
+        .await
+        .into_rpc_error()?;
+    tokens.push(
+        cometbft_service::query_native_token(client)
+            .await
+            .into_rpc_error()?,
+    );
+
+    let mut assets = vec![];
+    for token in tokens {
+        let denom = match tx_asset_types.denoms.get(&token) {
+            Some(denom) => *denom,
+            None => {
+                let denom = cometbft_service::query_denom(client, &token)
+                    .await
+                    .into_rpc_error()?;
+                tx_asset_types.denoms.insert(token.clone(), denom);
+                denom
+            }
+        };
+        let Some(denom) = denom else {
+            continue;
+        };
+
+        let token_assets =
+            undated_assets(&token, denom).into_conversion_error()?;
+        if token_assets.iter().any(|asset| {
+            tx_asset_types.unregistered.contains(&asset.asset_type)
+        }) {
+            assets.extend(token_assets);
+        }
+    }
+
+    if assets.is_empty() {
+        tracing::debug!(
+            num_unregistered = tx_asset_types.unregistered.len(),
+            "No pre-image found for the asset types of committed txs"
+        );
+        return Ok(());
+    }
+
+    for asset in &assets {
+        tx_asset_types.unregistered.remove(&asset.asset_type);
+    }
+    let num_assets = assets.len();
+
+    db_service::insert_assets(
+        app_state.get_db_connection().await.into_db_error()?,
+        assets,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(
+        num_assets,
+        num_unregistered = tx_asset_types.unregistered.len(),
+        "Registered asset types of committed txs"
+    );
+
+    Ok(())
+}
+
+fn fetch_blocks_and_get_handle<S: BlockSource>(
+    last_block_height: Option<BlockHeight>,
+    max_concurrent_fetches: usize,
+    retry_interval: Duration,
+    block_source: S,
+    subscription: Option<NewBlockSubscription>,
+    fetch_window: FetchWindow,
+) -> mpsc::Receiver<Block> {
+    let (tx, rx) = mpsc::channel(fetch_window.max_blocks());
+
+    tokio::spawn(async move {
+        let mut heights_to_process = FollowingHeights::after(last_block_height)
+            .with_subscription(subscription);
+
+        let sem = Arc::new(Semaphore::new(if max_concurrent_fetches == 0 {
+            DEFAULT_MAX_CONCURRENT_FETCHES
+        } else {
+            max_concurrent_fetches
+        }));
+
+        while let Some(block_height) = heights_to_process
+            .next_height(&block_source, retry_interval)
+            .await
+        {
+            // NB: wait for the processing stage to catch up, if we are
+            // too far ahead of it
+            fetch_window.reserve().await;
+
+            let permit = sem
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Failed to acquire semaphore handle");
+
+            let block_source = block_source.clone();
+            let fetch_window = fetch_window.clone();
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                let _permit = permit;
+
+                let ControlFlow::Continue(block_data) =
+                    retry::every(retry_interval, async move || {
+                        let mut checkpoint = Instant::now();
+
+                        tracing::info!(%block_height, "Fetching block data");
+
+                        let block_data =
+                            cometbft_service::query_masp_txs_in_block(
+                                &block_source,
+                                block_height,
+                            )
+                            .await?;
+
+                        with_time_taken(&mut checkpoint, |time_taken| {
+                            shared::metrics::record_time_taken(
+                                FETCH_DURATION_HISTOGRAM,
+                                time_taken,
+                            );
+                            tracing::info!(
+                                time_taken,
+                                %block_height,
+                                "Acquired block data"
+                            );
+                        });
+
+                        anyhow::Ok(block_data)
+                    })
+                    .await
+                else {
+                    return;
+                };
+
+                fetch_window.insert(&block_data);
+
+                match tx.send(block_data).await {
+                    Err(_) if exit_handle::must_exit() => {}
+                    Err(err) => panic!(
+                        "Block data consumer has terminated unexpectedly: \
+                         {err}"
+                    ),
+                    _ => {}
+                }
+            });
+        }
+    });
+
+    rx
+}
+
+fn subscribe_to_new_blocks(
+    cometbft_websocket_url: Option<String>,
+    retry_interval: Duration,
+) -> Option<NewBlockSubscription> {
+    cometbft_websocket_url.map(|url| {
+        tracing::info!(url, "Following new blocks over WebSocket");
+        NewBlockSubscription::spawn(url, retry_interval)
+    })
+}
+
+async fn run_migrations(app_state: &AppState) -> Result<(), MainError> {
+    let mut max_retries = env::var("DATABASE_MAX_MIGRATION_RETRY")
+        .unwrap_or_else(|_| 5.to_string())
+        .parse::<u64>()
+        .unwrap_or(5_u64);
+    loop {
+        let migration_res = db_service::run_migrations(
+            app_state.get_db_connection().await.into_db_error()?,
+        )
+        .await;
+
+        match &migration_res {
+            Ok(_) => {
+                return migration_res
+                    .context("Failed to run db migrations")
+                    .into_db_error();
+            }
+            Err(e) => {
+                tracing::debug!(
+                    "Failed running migrations: {} ({}/5)",
+                    e.to_string(),
+                    max_retries
+                );
+                if max_retries == 0 {
+                    return migration_res
+                        .context("Failed to run db migrations")
+                        .into_db_error();
+                }
+                max_retries -= 1;
+                sleep(Duration::from_secs(3)).await;
+            }
+        }
+    }
+}
+
+async fn rollback(
+    app_state: &AppState,
+    to_height: BlockHeight,
+) -> Result<(), MainError> {
+    tracing::info!(%to_height, "Rolling back indexed MASP data...");
+
+    let report = db_service::rollback_to_height(
+        app_state.get_db_connection().await.into_db_error()?,
+        to_height,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(
+        %to_height,
+        chain_state = report.chain_state,
+        commitment_tree = report.commitment_tree,
+        witness_delta = report.witness_delta,
+        notes_index = report.notes_index,
+        tx = report.tx,
+        tx_flow = report.tx_flow,
+        block = report.block,
+        block_index = report.block_index,
+        "Rolled back indexed MASP data"
+    );
+
+    Ok(())
+}
+
+async fn rebuild(
+    app_state: &AppState,
+    from_height: BlockHeight,
+) -> Result<(), MainError> {
+    tracing::info!(%from_height, "Rebuilding derived MASP data...");
+
+    let report = db_service::rebuild_from_height(
+        app_state.get_db_connection().await.into_db_error()?,
+        from_height,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(
+        %from_height,
+        heights = report.num_heights,
+        txs = report.num_txs,
+        notes = report.num_notes,
+        "Rebuilt derived MASP data"
+    );
+
+    Ok(())
+}
+
+async fn export_snapshot(
+    app_state: &AppState,
+    path: &Path,
+) -> Result<(), MainError> {
+    tracing::info!(path = %path.display(), "Exporting snapshot...");
+
+    let SnapshotRows {
+        block_height,
+        network,
+        tree,
+        notes_index,
+        txs,
+        blocks,
+    } = db_service::read_snapshot_rows(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    let snapshot = Snapshot {
+        chain_id: network.chain_id,
+        first_block_height: network.first_block_height.0,
+        first_block_hash: network.first_block_hash,
+        block_height: block_height.0,
+        commitment_tree: tree.commitment_tree().serialize_to_vec(),
+        leaves: witness_delta::serialize_leaves(tree.leaves_from(0)),
+        notes_index: notes_index.into_iter().map(Into::into).collect(),
+        txs: txs.into_iter().map(Into::into).collect(),
+        blocks: blocks.into_iter().map(Into::into).collect(),
+    };
+
+    tokio::task::block_in_place(|| snapshot.write_to_file(path))
+        .into_serialization_error()?;
+
+    tracing::info!(
+        chain_id = snapshot.chain_id,
+        %block_height,
+        num_notes = tree.size(),
+        num_txs = snapshot.txs.len(),
+        "Exported snapshot"
+    );
+
+    Ok(())
+}
+
+async fn import_snapshot(
+    app_state: &AppState,
+    client: &Client,
+    path: &Path,
+) -> Result<(), MainError> {
+    tracing::info!(path = %path.display(), "Importing snapshot...");
+
+    let snapshot =
+        tokio::task::block_in_place(|| Snapshot::read_from_file(path))
+            .into_serialization_error()?;
+
+    let network = snapshot.network();
+    check_network(client, &network).await?;
+
+    let block_height = snapshot.block_height();
+
+    let commitment_tree = tokio::task::block_in_place(|| {
+        let leaves = witness_delta::deserialize_leaves(&snapshot.leaves)?;
+        let expected_tree = MaspCommitmentTree::<Node>::try_from_slice(
+            &snapshot.commitment_tree,
+        )
+        .context("Failed to decode snapshot commitment tree")?;
+
+        let mut commitment_tree = CommitmentTree::new(BridgeTree::default());
+        for leaf in leaves {
+            if !commitment_tree.append(leaf) {
+                anyhow::bail!("Note commitment tree is full");
+            }
+        }
+
+        if commitment_tree.size() != expected_tree.size()
+            || commitment_tree.root() != expected_tree.root()
+        {
+            anyhow::bail!("Snapshot leaves do not match its commitment tree");
+        }
+
+        anyhow::Ok(commitment_tree)
+    })
+    .into_masp_error()?;
+
+    let num_notes = commitment_tree.size();
+    let num_txs = snapshot.txs.len();
+    let blocks = snapshot
+        .blocks
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<anyhow::Result<_>>()
+        .into_serialization_error()?;
+
+    db_service::import_snapshot(
+        app_state.get_db_connection().await.into_db_error()?,
+        ChainState {
+            block_height,
+            network: Some(network.clone()),
+        },
+        &commitment_tree,
+        snapshot.notes_index.into_iter().map(Into::into).collect(),
+        snapshot.txs.into_iter().map(Into::into).collect(),
+        blocks,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(
+        chain_id = network.chain_id,
+        %block_height,
+        num_notes,
+        num_txs,
+        "Imported snapshot"
+    );
+
+    Ok(())
+}
+
+async fn verify(
+    app_state: &AppState,
+    client: &Client,
+    check_anchors: bool,
+    report_path: Option<&Path>,
+) -> Result<(), MainError> {
+    tracing::info!("Verifying indexed MASP data...");
+
+    let Verification {
+        mut summary,
+        mut inconsistency,
+        tree_roots,
+    } = db_service::verify_index(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    if check_anchors && inconsistency.is_none() {
+        for (block_height, root) in tree_roots {
+            if let Err(err) =
+                cometbft_service::query_commitment_tree_anchor_existence(
+                    client, root,
+                )
+                .await
+            {
+                inconsistency = Some(
+                    Inconsistency::new(Check::Anchor, format!("{err:#}"))
+                        .at_height(block_height),
+                );
+                break;
+            }
+            summary.num_anchors += 1;
+        }
+    }
+
+    let report = match inconsistency {
+        Some(inconsistency) => VerificationReport::Inconsistent(inconsistency),
+        None => VerificationReport::Consistent(summary),
+    };
+    let json = serde_json::to_string_pretty(&report)
+        .context("Failed to serialize verification report")
+        .into_serialization_error()?;
+
+    match report_path {
+        Some(path) => std::fs::write(path, format!("{json}\n"))
+            .with_context(|| {
+                format!(
+                    "Failed to write verification report to {}",
+                    path.display()
+                )
+            })
+            .into_main_error("IO error")?,
+        None => println!("{json}"),
+    }
+
+    match report {
+        VerificationReport::Consistent(_) => {
+            tracing::info!("Indexed MASP data is consistent");
+            Ok(())
+        }
+        VerificationReport::Inconsistent(inconsistency) => {
+            Err(anyhow::anyhow!(
+                "Indexed MASP data is inconsistent: {}",
+                inconsistency.message
+            ))
+            .into_main_error("Verification error")
+        }
+    }
+}
+
+/// Check that the CometBFT node is running the network the db was
+/// built from.
+async fn check_network_identity(
+    app_state: &AppState,
+    client: &Client,
+) -> Result<(), MainError> {
+    let Some(network) = db_service::get_network_identity(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?
+    else {
+        tracing::info!(
+            "No network identity recorded yet, it will be recorded along with \
+             the next committed block"
+        );
+        return Ok(());
+    };
+
+    check_network(client, &network).await
+}
+
+/// Check that the CometBFT node is running the given network.
+async fn check_network(
+    client: &Client,
+    network: &NetworkIdentity,
+) -> Result<(), MainError> {
+    let chain_id = cometbft_service::query_chain_id(client)
+        .await
+        .into_rpc_error()?;
+    if network.chain_id != chain_id {
+        return Err(anyhow::anyhow!(
+            "The indexed blocks belong to chain {}, but the CometBFT node is \
+             running chain {chain_id}",
+            network.chain_id
+        ))
+        .into_main_error("Network identity error");
+    }
+
+    match cometbft_service::query_block_hash(client, network.first_block_height)
+        .await
+    {
+        Ok(block_hash) if block_hash != network.first_block_hash => {
+            return Err(anyhow::anyhow!(
+                "The first indexed block is {} at height {}, but the CometBFT \
+                 node has block {block_hash} at that height",
+                network.first_block_hash,
+                network.first_block_height
+            ))
+            .into_main_error("Network identity error");
+        }
+        Ok(_) => {}
+        Err(err) => {
+            tracing::warn!(
+                first_block_height = %network.first_block_height,
+                summary = %err,
+                "Could not check the hash of the first indexed block, it \
+                 may have been pruned by the CometBFT node"
+            );
+        }
+    }
+
+    tracing::info!(
+        chain_id,
+        first_block_height = %network.first_block_height,
+        "Checked the network identity of the CometBFT node"
+    );
+
+    Ok(())
+}
+
+async fn load_committed_state(
+    app_state: &AppState,
+    starting_block_height: Option<u64>,
+) -> Result<(Option<BlockHeight>, CommitmentTree), MainError> {
+    tracing::info!("Loading last committed state from db...");
+
+    let last_block_height = db_service::get_last_synced_block(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    let last_block_height = std::cmp::max(
+        last_block_height,
+        starting_block_height.map(BlockHeight::from),
+    );
+
+    let commitment_tree = db_service::get_last_commitment_tree(
+        app_state.get_db_connection().await.into_db_error()?,
+    )
+    .await
+    .into_db_error()?;
+
+    tracing::info!(?last_block_height, "Last state has been loaded");
+
+    shared::error::ok((last_block_height, commitment_tree))
+}
+
+/// Build and commit the MASP data of `block_data`. Breaks if the block
+/// belongs to another network than the indexed one, in which case the
+/// crawler must shut down rather than retry.
+#[allow(clippy::too_many_arguments)]
+async fn build_and_commit_masp_data_at_height(
+    block_data: Block,
+    client: &Client,
+    commitment_tree: &mut CommitmentTree,
+    tx_notes_index: &mut TxNoteMap,
+    shielded_txs: &mut BTreeMap<MaspIndexedTx, Transaction>,
+    app_state: &AppState,
+    number_of_witness_map_roots_to_check: usize,
+) -> Result<ControlFlow<()>, MainError> {
+    // NB: rollback changes from previous failed commit attempts
+    commitment_tree.rollback();
+    tx_notes_index.clear();
+    shielded_txs.clear();
+
+    let conn_obj = app_state.get_db_connection().await.into_db_error()?;
+
+    let mut checkpoint = Instant::now();
+
+    let num_transactions = block_data.transactions.len();
+    let block_height = block_data.header.height;
+    let first_note_position = commitment_tree.size();
+    let chain_state = ChainState::from_block(&block_data);
+    let committed_block =
+        CommittedBlock::from_block(&block_data).into_conversion_error()?;
+
+    tracing::info!(
+        %block_height,
+        num_transactions,
+        "Attempting to process new masp transactions..."
+    );
+
+    for (masp_indexed_tx, tx) in block_data.transactions.into_iter() {
+        masp_service::update_witness_map(
+            commitment_tree,
+            tx_notes_index,
+            masp_indexed_tx,
+            &tx.masp_tx,
+        )
+        .into_masp_error()?;
+
+        shielded_txs.insert(masp_indexed_tx, tx);
+    }
+
+    with_time_taken(&mut checkpoint, |time_taken| {
+        tracing::info!(
+            %block_height,
+            num_transactions,
+            time_taken,
+            "Processed new masp transactions",
+        );
+    });
+
+    validate_masp_state(
+        &mut checkpoint,
+        client,
+        commitment_tree,
+        number_of_witness_map_roots_to_check,
+    )
+    .await?;
+
+    let committed = db_service::commit(
+        &mut checkpoint,
+        &conn_obj,
+        chain_state,
+        committed_block,
+        commitment_tree,
+        tx_notes_index,
+        shielded_txs,
+    );
+
+    if let Err(reason) = &committed {
+        if reason.downcast_ref::<NetworkMismatch>().is_some() {
+            tracing::error!(
+                ?reason,
+                "Refusing to index a block of another network"
+            );
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+    committed.into_db_error()?;
+
+    shared::metrics::record_committed_block(
+        block_height,
+        num_transactions,
+        commitment_tree.size() - first_note_position,
+    );
+
+    Ok(ControlFlow::Continue(()))
+}
+
+async fn validate_masp_state(
+    checkpoint: &mut Instant,
+    client: &Client,
+    commitment_tree: &CommitmentTree,
+    number_of_witness_map_roots_to_check: usize,
+) -> Result<(), MainError> {
+    if commitment_tree.is_dirty() && number_of_witness_map_roots_to_check > 0 {
+        tracing::info!("Validating MASP state...");
+
+        let tree_root = tokio::task::block_in_place(|| commitment_tree.root());
+
+        // NB: witnesses are computed from the commitment tree itself, so
+        // only its root is checked, against the anchors of the chain
+        cometbft_service::query_commitment_tree_anchor_existence(
+            client, tree_root,
+        )
+        .await
+        .into_rpc_error()?;
+
+        with_time_taken(checkpoint, |time_taken| {
+            shared::metrics::record_time_taken(
+                VALIDATION_DURATION_HISTOGRAM,
+                time_taken,
+            );
+            tracing::info!(time_taken, "Validated MASP state");
+        });
+    }
+
+    Ok(())
+}
+
+async fn get_new_block_from_fetcher(
+    blocks: &mut mpsc::Receiver<Block>,
+) -> Option<Block> {
+    poll_fn(|cx| {
+        if exit_handle::must_exit() {
+            return Poll::Ready(None);
+        }
+
+        match blocks.poll_recv(cx) {
+            poll @ Poll::Ready(None) if exit_handle::must_exit() => poll,
+            Poll::Ready(None) => {
+                panic!("The block fetching task has unexpectedly terminated")
+            }
+            poll => poll,
+        }
+    })
+    .await
+}
+
+fn with_time_taken<F, T>(checkpoint: &mut Instant, callback: F) -> T
+where
+    F: FnOnce(f64) -> T,
+{
+    let last_checkpoint = std::mem::replace(checkpoint, Instant::now());
+    let time_taken = last_checkpoint.elapsed().as_secs_f64();
+
+    callback(time_taken)
+}
@@ -1,44 +1,54 @@
 use anyhow::{Context, anyhow};
+use namada_core::masp::MaspEpoch;
 use namada_core::masp_primitives::sapling::Node;
+use namada_sdk::address::{Address, InternalAddress};
+use namada_sdk::ibc::storage::{ibc_trace_key_prefix, is_ibc_trace_key};
+use namada_sdk::queries::RPC;
+use namada_sdk::token::Denomination;
 use shared::block::Block;
+use shared::block_source::{BlockSource, RawBlock};
+use shared::client::Client;
 use shared::height::BlockHeight;
-use tendermint_rpc::endpoint::{block, block_results};
-use tendermint_rpc::{Client, HttpClient};
+use shared::id::Id;
+use tendermint_rpc::Client as _;
 
-pub async fn query_masp_txs_in_block(
-    client: &HttpClient,
+use crate::entity::conversion::ConversionState;
+
+pub async fn query_masp_txs_in_block<S: BlockSource>(
+    block_source: &S,
     height: BlockHeight,
 ) -> anyhow::Result<Block> {
-    let (raw_block, raw_block_results) = futures::try_join!(
-        query_raw_block(client, height),
-        query_raw_block_results_at_height(client, height),
-    )?;
+    let RawBlock {
+        block,
+        block_results,
+    } = block_source.raw_block(height).await?;
 
-    Block::new(raw_block, raw_block_results).map_err(|err| anyhow!(err))
+    Block::new(block, block_results).map_err(|err| anyhow!(err))
 }
 
-async fn query_raw_block(
-    client: &HttpClient,
-    height: BlockHeight,
-) -> anyhow::Result<block::Response> {
-    client
-        .block(height)
+pub async fn query_chain_id(client: &Client) -> anyhow::Result<String> {
+    let status = client
+        .status()
         .await
-        .context("Failed to query CometBFT's last committed height")
+        .context("Failed to query CometBFT's node status")?;
+
+    Ok(status.node_info.network.to_string())
 }
 
-async fn query_raw_block_results_at_height(
-    client: &HttpClient,
+pub async fn query_block_hash(
+    client: &Client,
     height: BlockHeight,
-) -> anyhow::Result<block_results::Response> {
-    client
-        .block_results(height)
+) -> anyhow::Result<String> {
+    let block = client
+        .block(height.0 as u32)
         .await
-        .context("Failed to query CometBFT's block results")
+        .with_context(|| format!("Failed to query block at height {height}"))?;
+
+    Ok(Id::from(block.block_id).to_string())
 }
 
 pub async fn query_commitment_tree_anchor_existence(
-    client: &HttpClient,
+    client: &Client,
     commitment_tree_root: Node,
 ) -> anyhow::Result<()> {
     let anchor_key = namada_sdk::token::storage_key::masp_commitment_anchor_key(
@@ -57,3 +67,74 @@ pub async fn query_commitment_tree_anchor_existence(
 
     Ok(())
 }
+
+pub async fn query_masp_epoch(client: &Client) -> anyhow::Result<MaspEpoch> {
+    namada_sdk::rpc::query_masp_epoch(client)
+        .await
+        .context("Failed to query the current MASP epoch")
+}
+
+pub async fn query_conversion_state(
+    client: &Client,
+) -> anyhow::Result<ConversionState> {
+    let masp_epoch = query_masp_epoch(client).await?;
+
+    let conversions = namada_sdk::rpc::query_conversions(client)
+        .await
+        .context("Failed to query the MASP conversion state")?;
+
+    // NB: the conversion state is updated at the start of each MASP
+    // epoch, so make sure it did not change under our feet
+    let last_masp_epoch = query_masp_epoch(client).await?;
+    if last_masp_epoch != masp_epoch {
+        anyhow::bail!(
+            "MASP epoch changed from {masp_epoch} to {last_masp_epoch} while \
+             querying the conversion state"
+        )
+    }
+
+    Ok(ConversionState {
+        masp_epoch,
+        conversions,
+    })
+}
+
+pub async fn query_native_token(client: &Client) -> anyhow::Result<Address> {
+    namada_sdk::rpc::query_native_token(client)
+        .await
+        .context("Failed to query the native token")
+}
+
+/// Query the addresses of the IBC tokens known to the chain.
+pub async fn query_ibc_tokens(client: &Client) -> anyhow::Result<Vec<Address>> {
+    let traces = RPC
+        .shell()
+        .storage_prefix(client, None, None, false, &ibc_trace_key_prefix(None))
+        .await
+        .context("Failed to query the IBC token traces")?;
+
+    traces
+        .data
+        .iter()
+        .filter_map(|trace| is_ibc_trace_key(&trace.key))
+        .map(|(_, hash)| {
+            let hash = hash.parse().map_err(|err| {
+                anyhow!("Invalid IBC token hash {hash}: {err:?}")
+            })?;
+            Ok(Address::Internal(InternalAddress::IbcToken(hash)))
+        })
+        .collect()
+}
+
+pub async fn query_denom(
+    client: &Client,
+    token: &Address,
+) -> anyhow::Result<Option<Denomination>> {
+    RPC.vp()
+        .token()
+        .denomination(client, token)
+        .await
+        .with_context(|| {
+            format!("Failed to query the denomination of token {token}")
+        })
+}
@@ -1,37 +1,61 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
 use anyhow::{Context, anyhow};
 use deadpool_diesel::postgres::Object;
 use diesel::connection::DefaultLoadingMode as DbDefaultLoadingMode;
-use diesel::dsl::max;
+use diesel::dsl::{exists, max, not};
+use diesel::sql_types::{Nullable, SingleValue, SqlType};
+use diesel::upsert::excluded;
 use diesel::{
-    ExpressionMethods, NullableExpressionMethods, OptionalExtension, QueryDsl,
-    RunQueryDsl, SelectableHelper,
+    ExpressionMethods, NullableExpressionMethods, OptionalExtension,
+    PgConnection, QueryDsl, RunQueryDsl, SelectableHelper,
 };
 use diesel_migrations::{
     EmbeddedMigrations, MigrationHarness, embed_migrations,
 };
 use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
-use namada_sdk::masp_primitives::merkle_tree::IncrementalWitness;
+use namada_sdk::masp_primitives::merkle_tree::CommitmentTree as MaspCommitmentTree;
 use namada_sdk::masp_primitives::sapling::Node;
 use namada_sdk::masp_primitives::transaction::Transaction;
-use orm::schema::{self, chain_state, commitment_tree, witness};
+use orm::asset::AssetInsertDb;
+use orm::block::{BlockDb, BlockInsertDb};
+use orm::chain_state::NetworkIdentityDb;
+use orm::conversion::ConversionInsertDb;
+use orm::notes_index::{NotesIndexDb, NotesIndexInsertDb};
+use orm::notification::Notification;
+use orm::schema::{self, chain_state, commitment_tree, witness_delta};
 use orm::tree::TreeDb;
-use orm::tx::TxInsertDb;
-use orm::witness::WitnessDb;
+use orm::tx::{TxDb, TxInsertDb};
+use orm::tx_flow::TxFlowInsertDb;
+use orm::witness_delta::WitnessDeltaDb;
+use shared::bridge_tree::BridgeTree;
 use shared::error::ContextDbInteractError;
 use shared::height::BlockHeight;
-use shared::indexed_tx::MaspIndexedTx;
+use shared::indexed_tx::{IndexedTx, MaspIndexedTx, MaspTxKind};
+use shared::metrics::COMMIT_DURATION_HISTOGRAM;
+use shared::tx_index::{MaspTxIndex, TxIndex};
+use shared::witness_delta::{deserialize_leaves, serialize_leaves};
 use tokio::time::Instant;
 
-use crate::entity::chain_state::ChainState;
+use crate::entity::block::CommittedBlock;
+use crate::entity::chain_state::{ChainState, NetworkIdentity};
 use crate::entity::commitment_tree::CommitmentTree;
+use crate::entity::tx_flow::TxFlows;
 use crate::entity::tx_notes_index::TxNoteMap;
-use crate::entity::witness_map::WitnessMap;
+use crate::entity::verification::{Check, Inconsistency, VerificationSummary};
+use crate::services::masp as masp_service;
 use crate::with_time_taken;
 
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../orm/migrations/");
 
+diesel::define_sql_function! {
+    fn coalesce<T: SqlType + SingleValue>(
+        x: Nullable<T>,
+        y: Nullable<T>,
+    ) -> Nullable<T>;
+}
+
 pub async fn run_migrations(conn: Object) -> anyhow::Result<()> {
     tracing::debug!("Running db migrations...");
 
@@ -71,121 +95,728 @@ pub async fn get_last_synced_block(
     Ok(block_height)
 }
 
-pub async fn get_last_commitment_tree(
+pub async fn get_network_identity(
     conn: Object,
-) -> anyhow::Result<Option<CommitmentTree>> {
-    tracing::debug!("Reading last commitment tree from db");
+) -> anyhow::Result<Option<NetworkIdentity>> {
+    tracing::debug!("Reading network identity from db");
 
-    let maybe_tree = conn
+    let network = conn
         .interact(move |conn| {
-            diesel::alias!(commitment_tree as commitment_tree_alias: CommitmentTreeAlias);
-
-            let max_block_height = commitment_tree_alias
-                .select(max(commitment_tree_alias.field(commitment_tree::dsl::block_height)))
-                .single_value();
-
-            let tree = commitment_tree::dsl::commitment_tree
-                .filter(
-                    commitment_tree::dsl::block_height
-                        .nullable()
-                        .eq(max_block_height),
-                )
-                .select(TreeDb::as_select())
+            chain_state::table
+                .select(NetworkIdentityDb::as_select())
                 .first(conn)
                 .optional()
-                .context("Failed to read commitment tree from db")?;
-            anyhow::Ok(tree)
+        })
+        .await
+        .context_db_interact_error()??
+        .and_then(NetworkIdentity::from_db);
+
+    tracing::debug!(?network, "Read network identity from db");
+
+    Ok(network)
+}
+
+pub async fn get_last_conversions_epoch(
+    conn: Object,
+) -> anyhow::Result<Option<i32>> {
+    tracing::debug!("Reading last MASP epoch of the conversions from db");
+
+    let masp_epoch = conn
+        .interact(move |conn| {
+            schema::conversion::table
+                .select(max(schema::conversion::dsl::masp_epoch))
+                .first::<Option<i32>>(conn)
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(
+        ?masp_epoch,
+        "Read last MASP epoch of the conversions from db"
+    );
+
+    Ok(masp_epoch)
+}
+
+pub async fn insert_conversions(
+    conn: Object,
+    masp_epoch: i32,
+    conversions: Vec<ConversionInsertDb>,
+    assets: Vec<AssetInsertDb>,
+) -> anyhow::Result<()> {
+    tracing::debug!(masp_epoch, "Inserting conversions into db");
+
+    conn.interact(move |conn| {
+        conn.build_transaction().read_write().run(|conn| {
+            for chunk in conversions.chunks(SNAPSHOT_INSERT_CHUNK_SIZE) {
+                diesel::insert_into(schema::conversion::table)
+                    .values(chunk)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .context("Failed to insert conversions into db")?;
+            }
+
+            insert_asset_types(conn, &assets)
+        })
+    })
+    .await
+    .context_db_interact_error()??;
+
+    tracing::debug!(masp_epoch, "Inserted conversions into db");
+
+    Ok(())
+}
+
+/// Asset types of the committed MASP txs, which have no registered
+/// pre-image.
+pub struct UnregisteredAssetTypes {
+    pub asset_types: BTreeSet<String>,
+    /// Last height of the txs whose asset types were read.
+    pub last_block_height: Option<i32>,
+}
+
+/// Get the asset types of the MASP txs committed above
+/// `from_block_height` (if any) which have no registered pre-image,
+/// along with those of `asset_types` which are still unregistered.
+pub async fn get_unregistered_asset_types(
+    conn: Object,
+    from_block_height: Option<i32>,
+    asset_types: BTreeSet<String>,
+) -> anyhow::Result<UnregisteredAssetTypes> {
+    tracing::debug!(
+        ?from_block_height,
+        "Reading unregistered asset types of committed txs from db"
+    );
+
+    let unregistered = conn
+        .interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let above_block_height = from_block_height.unwrap_or(-1);
+
+                    let last_block_height = schema::tx_flow::table
+                        .filter(
+                            schema::tx_flow::dsl::block_height
+                                .gt(above_block_height),
+                        )
+                        .select(max(schema::tx_flow::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to read last height of tx flows")?;
+
+                    let new_asset_types = schema::tx_flow::table
+                        .filter(
+                            schema::tx_flow::dsl::block_height
+                                .gt(above_block_height),
+                        )
+                        .filter(not(exists(
+                            schema::asset::table.filter(
+                                schema::asset::dsl::asset_type
+                                    .eq(schema::tx_flow::dsl::asset_type),
+                            ),
+                        )))
+                        .select(schema::tx_flow::dsl::asset_type)
+                        .distinct()
+                        .load::<String>(conn)
+                        .context("Failed to read asset types of tx flows")?;
+
+                    // NB: asset types might have been registered in the
+                    // meantime, e.g. along with the conversion state
+                    let registered = schema::asset::table
+                        .filter(
+                            schema::asset::dsl::asset_type
+                                .eq_any(asset_types.iter()),
+                        )
+                        .select(schema::asset::dsl::asset_type)
+                        .load::<String>(conn)
+                        .context("Failed to read registered asset types")?;
+
+                    let mut asset_types = asset_types;
+                    asset_types.extend(new_asset_types);
+                    for asset_type in registered {
+                        asset_types.remove(&asset_type);
+                    }
+
+                    anyhow::Ok(UnregisteredAssetTypes {
+                        asset_types,
+                        last_block_height: last_block_height
+                            .or(from_block_height),
+                    })
+                })
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(
+        num_asset_types = unregistered.asset_types.len(),
+        last_block_height = ?unregistered.last_block_height,
+        "Read unregistered asset types of committed txs from db"
+    );
+
+    Ok(unregistered)
+}
+
+pub async fn insert_assets(
+    conn: Object,
+    assets: Vec<AssetInsertDb>,
+) -> anyhow::Result<()> {
+    tracing::debug!(num_assets = assets.len(), "Inserting asset types into db");
+
+    conn.interact(move |conn| insert_asset_types(conn, &assets))
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!("Inserted asset types into db");
+
+    Ok(())
+}
+
+fn insert_asset_types(
+    conn: &mut PgConnection,
+    assets: &[AssetInsertDb],
+) -> anyhow::Result<()> {
+    for chunk in assets.chunks(SNAPSHOT_INSERT_CHUNK_SIZE) {
+        diesel::insert_into(schema::asset::table)
+            .values(chunk)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to insert asset types into db")?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_last_commitment_tree(
+    conn: Object,
+) -> anyhow::Result<CommitmentTree> {
+    tracing::debug!("Reading commitment tree leaves from db");
+
+    let tree = conn
+        .interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .run(read_commitment_tree)
         })
         .await
         .context_db_interact_error()??;
 
+    tracing::debug!(size = tree.size(), "Rebuilt commitment tree from db");
+
+    Ok(CommitmentTree::new(tree))
+}
+
+/// Rebuild the commitment tree from the leaves stored in the witness
+/// map deltas, and check it against the last stored commitment tree.
+fn read_commitment_tree(conn: &mut PgConnection) -> anyhow::Result<BridgeTree> {
+    let mut leaves = Vec::new();
+
+    for maybe_delta in witness_delta::table
+        .order(witness_delta::dsl::block_height.asc())
+        .select(WitnessDeltaDb::as_select())
+        .load_iter::<_, DbDefaultLoadingMode>(conn)
+        .context("Failed to query witness map deltas from db")?
+    {
+        let delta = maybe_delta
+            .context("Failed to get witness map delta row data from db")?;
+        let delta_leaves = delta.leaves.with_context(|| {
+            format!(
+                "Witness map delta at height {} is missing its leaves",
+                delta.block_height
+            )
+        })?;
+        leaves.extend(deserialize_leaves(&delta_leaves)?);
+    }
+
+    diesel::alias!(commitment_tree as commitment_tree_alias: CommitmentTreeAlias);
+
+    let max_block_height = commitment_tree_alias
+        .select(max(
+            commitment_tree_alias.field(commitment_tree::dsl::block_height)
+        ))
+        .single_value();
+
+    let maybe_tree = commitment_tree::dsl::commitment_tree
+        .filter(
+            commitment_tree::dsl::block_height
+                .nullable()
+                .eq(max_block_height),
+        )
+        .select(TreeDb::as_select())
+        .first(conn)
+        .optional()
+        .context("Failed to read commitment tree from db")?;
+
     tracing::debug!(
+        num_leaves = leaves.len(),
         present_in_db = maybe_tree.is_some(),
-        "Read last commitment tree from db"
+        "Read commitment tree leaves from db"
     );
 
-    let maybe_tree = maybe_tree
+    let tree = BridgeTree::from_leaves(leaves)
+        .context("Failed to build commitment tree from db leaves")?;
+
+    let stored_tree = maybe_tree
         .map(|tree| {
-            tracing::debug!("Deserializing commitment tree from db");
-            let deserialized = tree.try_into().context(
+            MaspCommitmentTree::<Node>::try_from_slice(&tree.tree).context(
                 "Failed to deserialize commitment tree from db row data",
-            )?;
-            tracing::debug!("Deserialized commitment tree from db");
-            tracing::trace!(commitment_tree = ?deserialized, "Commitment tree data");
-            anyhow::Ok(deserialized)
+            )
         })
-        .transpose()?;
+        .transpose()?
+        .unwrap_or_else(MaspCommitmentTree::empty);
+    tracing::trace!(commitment_tree = ?stored_tree, "Commitment tree data");
 
-    anyhow::Ok(maybe_tree)
+    if stored_tree.size() != tree.size() || stored_tree.root() != tree.root() {
+        anyhow::bail!(
+            "Invalid database state: The last stored commitment tree has {} \
+             notes, but {} leaves were found in the witness map deltas",
+            stored_tree.size(),
+            tree.size()
+        );
+    }
+
+    Ok(tree)
 }
 
-pub async fn get_last_witness_map(conn: Object) -> anyhow::Result<WitnessMap> {
-    tracing::debug!("Reading last witness map from db");
+/// Check that the notes index does not refer to positions past the end
+/// of the commitment tree.
+fn verify_notes_index(
+    conn: &mut PgConnection,
+    tree_size: usize,
+) -> anyhow::Result<()> {
+    let max_note_position = schema::notes_index::table
+        .select(max(schema::notes_index::dsl::note_position))
+        .first::<Option<i32>>(conn)
+        .context("Failed to query the last indexed note position")?;
+
+    if max_note_position.is_some_and(|pos| pos as usize > tree_size) {
+        let note_position = max_note_position.unwrap_or_default();
+        anyhow::bail!(
+            "Invalid database state: The notes index refers to note position \
+             {note_position}, but the commitment tree has {tree_size} notes"
+        );
+    }
+
+    Ok(())
+}
+
+/// Number of rows removed from each table by [`rollback_to_height`],
+/// or, in the case of the chain state, rewound.
+#[derive(Debug, Default)]
+pub struct RollbackReport {
+    pub chain_state: usize,
+    pub commitment_tree: usize,
+    pub witness_delta: usize,
+    pub notes_index: usize,
+    pub tx: usize,
+    pub tx_flow: usize,
+    pub block: usize,
+    pub block_index: usize,
+}
+
+/// Atomically remove all MASP data above `block_height`, and verify
+/// that the remaining commitment tree and notes index are consistent.
+pub async fn rollback_to_height(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<RollbackReport> {
+    let height = i32::try_from(block_height.0)
+        .context("Rollback height does not fit in the db")?;
+
+    tracing::debug!(%block_height, "Rolling back MASP data in db");
 
-    let witnesses = conn
+    let report = conn
         .interact(move |conn| {
-            diesel::alias!(witness as witness_alias: WitnessMapAlias);
+            conn.build_transaction().read_write().run(|conn| {
+                let report = RollbackReport {
+                    chain_state: diesel::update(
+                        chain_state::table
+                            .filter(chain_state::dsl::block_height.gt(height)),
+                    )
+                    .set(chain_state::dsl::block_height.eq(height))
+                    .execute(conn)
+                    .context("Failed to roll back chain state")?,
+                    commitment_tree: diesel::delete(
+                        commitment_tree::table.filter(
+                            commitment_tree::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete commitment trees")?,
+                    witness_delta: diesel::delete(
+                        witness_delta::table.filter(
+                            witness_delta::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete witness map deltas")?,
+                    notes_index: diesel::delete(
+                        schema::notes_index::table.filter(
+                            schema::notes_index::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete notes index")?,
+                    tx: diesel::delete(
+                        schema::tx::table
+                            .filter(schema::tx::dsl::block_height.gt(height)),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete masp txs")?,
+                    tx_flow: diesel::delete(
+                        schema::tx_flow::table.filter(
+                            schema::tx_flow::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete masp tx flows")?,
+                    block: diesel::delete(
+                        schema::block::table.filter(
+                            schema::block::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete block headers")?,
+                    // NB: the block index covers every height up to the
+                    // one it was built at, so it must be rebuilt from
+                    // scratch if it includes rolled back heights
+                    block_index: diesel::delete(
+                        schema::block_index::table.filter(
+                            schema::block_index::dsl::block_height.gt(height),
+                        ),
+                    )
+                    .execute(conn)
+                    .context("Failed to delete masp txs block index")?,
+                };
+
+                let tree = read_commitment_tree(conn)?;
+                verify_notes_index(conn, tree.size())?;
+
+                anyhow::Ok(report)
+            })
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(%block_height, ?report, "Rolled back MASP data in db");
+
+    Ok(report)
+}
+
+/// Number of heights and MASP txs replayed by [`rebuild_from_height`].
+#[derive(Debug, Default)]
+pub struct RebuildReport {
+    pub num_heights: usize,
+    pub num_txs: usize,
+    pub num_notes: usize,
+}
+
+/// Maximum number of heights whose MASP txs are read at once when
+/// rebuilding the derived MASP state.
+const REBUILD_HEIGHTS_PER_QUERY: usize = 1000;
 
-            let max_block_height = witness_alias
-                .select(max(witness_alias.field(witness::dsl::block_height)))
-                .single_value();
+/// Atomically rewrite the commitment trees, witness map deltas, notes
+/// index and tx flows from `block_height` onwards, by replaying the stored
+/// MASP txs on top of the state left at the preceding heights.
+pub async fn rebuild_from_height(
+    conn: Object,
+    block_height: BlockHeight,
+) -> anyhow::Result<RebuildReport> {
+    let height = i32::try_from(block_height.0)
+        .context("Rebuild height does not fit in the db")?;
+
+    tracing::debug!(%block_height, "Rebuilding derived MASP data in db");
 
-            witness::dsl::witness
-                .filter(
-                    witness::dsl::block_height.nullable().eq(max_block_height),
+    let report = conn
+        .interact(move |conn| {
+            conn.build_transaction().read_write().run(|conn| {
+                diesel::delete(
+                    commitment_tree::table
+                        .filter(commitment_tree::dsl::block_height.ge(height)),
                 )
-                .select(WitnessDb::as_select())
-                .load_iter::<_, DbDefaultLoadingMode>(conn)
-                .context("Failed to query note witnesses from db")?
-                .try_fold(HashMap::new(), |mut accum, maybe_witness| {
-                    tracing::debug!("Reading new witness map entry from db");
-                    let witness = maybe_witness.context(
-                        "Failed to get note witnesses row data from db",
-                    )?;
-                    tracing::debug!("Read new witness map entry from db");
-                    let witness_node =
-                        IncrementalWitness::<Node>::try_from_slice(
-                            &witness.witness_bytes,
-                        )
-                        .context(
-                            "Failed to deserialize note witness from db",
-                        )?;
-                    let note_index = usize::try_from(witness.witness_idx)
-                        .with_context(|| {
-                            let db_note_index = witness.witness_idx;
-                            format!(
-                                "Failed to convert note index {db_note_index} \
-                                 from witnesses db table"
-                            )
-                        })?;
-                    tracing::debug!("Deserialized new witness map entry");
-                    tracing::trace!(
-                        ?note_index,
-                        ?witness_node,
-                        "Witness map entry data"
-                    );
-                    accum.insert(note_index, witness_node);
-                    tracing::trace!("Inserted data into witness map");
-                    anyhow::Ok(accum)
-                })
+                .execute(conn)
+                .context("Failed to delete commitment trees")?;
+                diesel::delete(
+                    witness_delta::table
+                        .filter(witness_delta::dsl::block_height.ge(height)),
+                )
+                .execute(conn)
+                .context("Failed to delete witness map deltas")?;
+                diesel::delete(
+                    schema::notes_index::table.filter(
+                        schema::notes_index::dsl::block_height.ge(height),
+                    ),
+                )
+                .execute(conn)
+                .context("Failed to delete notes index")?;
+                diesel::delete(
+                    schema::tx_flow::table
+                        .filter(schema::tx_flow::dsl::block_height.ge(height)),
+                )
+                .execute(conn)
+                .context("Failed to delete masp tx flows")?;
+
+                let (commitment_tree, report) =
+                    replay_stored_txs(conn, height)?;
+
+                verify_notes_index(conn, commitment_tree.size())?;
+
+                anyhow::Ok(report)
+            })
         })
         .await
         .context_db_interact_error()??;
 
-    tracing::debug!("Read and deserialized witness map from db");
+    tracing::debug!(%block_height, ?report, "Rebuilt derived MASP data in db");
+
+    Ok(report)
+}
+
+/// Replay the stored MASP txs from `height` onwards, on top of the
+/// commitment tree left at the preceding heights, and insert the
+/// commitment tree, witness map delta, notes index and tx flows of each
+/// replayed height.
+fn replay_stored_txs(
+    conn: &mut PgConnection,
+    height: i32,
+) -> anyhow::Result<(CommitmentTree, RebuildReport)> {
+    let mut commitment_tree = CommitmentTree::new(read_commitment_tree(conn)?);
+    let first_note = commitment_tree.size();
+
+    let heights: Vec<i32> = schema::tx::table
+        .filter(schema::tx::dsl::block_height.ge(height))
+        .select(schema::tx::dsl::block_height)
+        .distinct()
+        .order(schema::tx::dsl::block_height.asc())
+        .load(conn)
+        .context("Failed to query masp tx heights from db")?;
+
+    let mut report = RebuildReport {
+        num_heights: heights.len(),
+        ..Default::default()
+    };
+
+    for heights in heights.chunks(REBUILD_HEIGHTS_PER_QUERY) {
+        let (first_height, last_height) =
+            (heights[0], heights[heights.len() - 1]);
+
+        // NB: follow the ordering of `MaspIndexedTx`
+        let txs: Vec<TxDb> = schema::tx::table
+            .filter(
+                schema::tx::dsl::block_height
+                    .between(first_height, last_height),
+            )
+            .order((
+                schema::tx::dsl::block_height.asc(),
+                schema::tx::dsl::is_masp_fee_payment.desc(),
+                schema::tx::dsl::block_index.asc(),
+                schema::tx::dsl::masp_tx_index.asc(),
+            ))
+            .select(TxDb::as_select())
+            .load(conn)
+            .context("Failed to query masp txs from db")?;
+
+        for txs in txs.chunk_by(|a, b| a.block_height == b.block_height) {
+            let block_height = BlockHeight::from(txs[0].block_height);
+            let mut notes_index = TxNoteMap::default();
+            let mut tx_flows = vec![];
+
+            for tx in txs {
+                let masp_tx = Transaction::try_from_slice(&tx.tx_bytes)
+                    .context("Failed to deserialize masp tx from db")?;
+                let indexed_tx = indexed_tx_of(
+                    tx.block_height,
+                    tx.block_index,
+                    tx.masp_tx_index,
+                    tx.is_masp_fee_payment,
+                );
+
+                masp_service::update_witness_map(
+                    &mut commitment_tree,
+                    &mut notes_index,
+                    indexed_tx,
+                    &masp_tx,
+                )?;
+                tx_flows.extend(TxFlows::new(&masp_tx).into_db(indexed_tx));
+            }
+
+            insert_masp_state(
+                conn,
+                block_height,
+                &mut commitment_tree,
+                &mut notes_index,
+            )?;
+            insert_tx_flows(conn, &tx_flows)?;
+            report.num_txs += txs.len();
+        }
+    }
+
+    report.num_notes = commitment_tree.size() - first_note;
+
+    Ok((commitment_tree, report))
+}
+
+/// Return the position of a stored MASP tx in the order it was applied
+/// to the commitment tree.
+fn indexed_tx_of(
+    block_height: i32,
+    block_index: i32,
+    masp_tx_index: i32,
+    is_masp_fee_payment: bool,
+) -> MaspIndexedTx {
+    MaspIndexedTx {
+        kind: if is_masp_fee_payment {
+            MaspTxKind::FeePayment
+        } else {
+            MaspTxKind::Transfer
+        },
+        indexed_tx: IndexedTx {
+            block_height: BlockHeight::from(block_height),
+            block_index: TxIndex(block_index as u32),
+            masp_tx_index: MaspTxIndex(masp_tx_index as usize),
+        },
+    }
+}
+
+/// Maximum number of legacy witness map deltas backfilled per db
+/// transaction.
+const LEGACY_MIGRATION_BATCH_SIZE: i64 = 1000;
+
+/// Backfill the leaves of the witness map deltas inherited from the full
+/// witness maps stored at every height by older versions of the indexer.
+///
+/// The leaves appended at each legacy height are recovered from the
+/// `tx` table, a batch of heights at a time.
+pub async fn migrate_legacy_witness_maps(conn: Object) -> anyhow::Result<()> {
+    conn.interact(|conn| {
+        let has_legacy_deltas = diesel::select(exists(
+            witness_delta::table.filter(witness_delta::dsl::leaves.is_null()),
+        ))
+        .get_result::<bool>(conn)
+        .context("Failed to query legacy witness map deltas")?;
+
+        if !has_legacy_deltas {
+            return anyhow::Ok(());
+        }
+
+        tracing::info!("Migrating legacy witness maps to delta encoding...");
+
+        let mut num_heights = 0;
+
+        loop {
+            let num_migrated = conn
+                .build_transaction()
+                .read_write()
+                .run(migrate_legacy_witness_map_batch)?;
+
+            if num_migrated == 0 {
+                break;
+            }
+            num_heights += num_migrated;
+
+            tracing::debug!(num_heights, "Migrated legacy witness maps");
+        }
+
+        tracing::info!(
+            num_heights,
+            "Migrated legacy witness maps to delta encoding"
+        );
+
+        anyhow::Ok(())
+    })
+    .await
+    .context_db_interact_error()??;
+
+    Ok(())
+}
+
+/// Backfill the leaves of the first batch of legacy witness map deltas,
+/// and return the number of deltas that were backfilled.
+fn migrate_legacy_witness_map_batch(
+    conn: &mut PgConnection,
+) -> anyhow::Result<usize> {
+    let legacy_heights: Vec<i32> = witness_delta::table
+        .filter(witness_delta::dsl::leaves.is_null())
+        .order(witness_delta::dsl::block_height.asc())
+        .select(witness_delta::dsl::block_height)
+        .limit(LEGACY_MIGRATION_BATCH_SIZE)
+        .load(conn)
+        .context("Failed to query legacy witness map heights")?;
+
+    let mut leaves_per_height = read_leaves_per_height(conn, &legacy_heights)?;
+
+    for &height in &legacy_heights {
+        let leaves = leaves_per_height.remove(&height).unwrap_or_default();
 
-    Ok(WitnessMap::new(witnesses))
+        diesel::update(witness_delta::table.find(height))
+            .set(witness_delta::dsl::leaves.eq(serialize_leaves(&leaves)))
+            .execute(conn)
+            .context("Failed to update legacy witness map delta")?;
+    }
+
+    Ok(legacy_heights.len())
+}
+
+/// Read the leaves appended to the commitment tree at each of the given
+/// heights, from the stored MASP txs.
+fn read_leaves_per_height(
+    conn: &mut PgConnection,
+    block_heights: &[i32],
+) -> anyhow::Result<BTreeMap<i32, Vec<Node>>> {
+    // NB: follow the ordering of `MaspIndexedTx`
+    schema::tx::table
+        .filter(schema::tx::dsl::block_height.eq_any(block_heights))
+        .order((
+            schema::tx::dsl::block_height.asc(),
+            schema::tx::dsl::is_masp_fee_payment.desc(),
+            schema::tx::dsl::block_index.asc(),
+            schema::tx::dsl::masp_tx_index.asc(),
+        ))
+        .select((schema::tx::dsl::block_height, schema::tx::dsl::tx_bytes))
+        .load_iter::<(i32, Vec<u8>), DbDefaultLoadingMode>(conn)
+        .context("Failed to query masp txs from db")?
+        .try_fold(BTreeMap::new(), |mut accum, maybe_tx| {
+            let (block_height, tx_bytes) =
+                maybe_tx.context("Failed to get masp tx row data from db")?;
+            let leaves: &mut Vec<Node> = accum.entry(block_height).or_default();
+            leaves
+                .extend(shared::transaction::masp_note_commitments(&tx_bytes)?);
+            anyhow::Ok(accum)
+        })
 }
 
 #[allow(clippy::too_many_arguments)]
+/// Error committing a block of another network than the one the db was
+/// built from, which retrying the commit cannot fix.
+#[derive(Debug)]
+pub struct NetworkMismatch {
+    pub block_chain_id: String,
+    pub db_chain_id: String,
+}
+
+impl fmt::Display for NetworkMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The block belongs to chain {}, but the db was built from chain {}",
+            self.block_chain_id, self.db_chain_id
+        )
+    }
+}
+
+impl std::error::Error for NetworkMismatch {}
+
 pub fn commit(
     checkpoint: &mut Instant,
     conn: &Object,
     chain_state: ChainState,
+    committed_block: CommittedBlock,
     commitment_tree: &mut CommitmentTree,
-    witness_map: &mut WitnessMap,
     notes_index: &mut TxNoteMap,
-    shielded_txs: &mut BTreeMap<MaspIndexedTx, Transaction>,
+    shielded_txs: &mut BTreeMap<
+        MaspIndexedTx,
+        shared::transaction::Transaction,
+    >,
 ) -> anyhow::Result<()> {
     tracing::info!(
         block_height = %chain_state.block_height,
@@ -195,15 +826,19 @@ pub fn commit(
     tokio::task::block_in_place(|| {
         commit_inner(
             conn,
-            chain_state,
+            &chain_state,
+            &committed_block,
             commitment_tree,
-            witness_map,
             notes_index,
             shielded_txs,
         )
     })?;
 
     with_time_taken(checkpoint, |time_taken| {
+        shared::metrics::record_time_taken(
+            COMMIT_DURATION_HISTOGRAM,
+            time_taken,
+        );
         tracing::info!(
             block_height = %chain_state.block_height,
             time_taken,
@@ -217,11 +852,14 @@ pub fn commit(
 #[allow(clippy::too_many_arguments)]
 fn commit_inner(
     pool_conn: &Object,
-    chain_state: ChainState,
+    chain_state: &ChainState,
+    committed_block: &CommittedBlock,
     commitment_tree: &mut CommitmentTree,
-    witness_map: &mut WitnessMap,
     notes_index: &mut TxNoteMap,
-    shielded_txs: &mut BTreeMap<MaspIndexedTx, Transaction>,
+    shielded_txs: &mut BTreeMap<
+        MaspIndexedTx,
+        shared::transaction::Transaction,
+    >,
 ) -> anyhow::Result<()> {
     let mut conn = pool_conn
         .lock()
@@ -230,77 +868,32 @@ fn commit_inner(
     conn.build_transaction()
         .read_write()
         .run(|transaction_conn| {
-            if let Some(commitment_tree_db) =
-                commitment_tree.into_db(chain_state.block_height)
-            {
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committing commitment tree"
-                );
-
-                diesel::insert_into(schema::commitment_tree::table)
-                    .values(&commitment_tree_db)
-                    .on_conflict_do_nothing()
-                    .execute(transaction_conn)
-                    .context("Failed to insert commitment tree into db")?;
-
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committed commitment tree"
-                );
-            }
-
-            if let Some(witness_map_db) =
-                witness_map.into_db(chain_state.block_height)
-            {
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committing witness map"
-                );
-
-                diesel::insert_into(schema::witness::table)
-                    .values(&witness_map_db)
-                    .on_conflict_do_nothing()
-                    .execute(transaction_conn)
-                    .context("Failed to insert witness map into db")?;
-
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committed witness map"
-                );
-            }
-
-            if !notes_index.is_empty() {
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committing notes map"
-                );
-
-                let notes_index_db = notes_index.into_db();
-                diesel::insert_into(schema::notes_index::table)
-                    .values(&notes_index_db)
-                    .on_conflict_do_nothing()
-                    .execute(transaction_conn)
-                    .context("Failed to insert notes map into db")?;
-
-                tracing::debug!(
-                    block_height = %chain_state.block_height,
-                    "Pre-committed notes map"
-                );
-            }
+            insert_masp_state(
+                transaction_conn,
+                chain_state.block_height,
+                commitment_tree,
+                notes_index,
+            )?;
 
+            let num_masp_txs = shielded_txs.len();
             if !shielded_txs.is_empty() {
                 tracing::debug!(
                     block_height = %chain_state.block_height,
                     "Pre-committing shielded txs"
                 );
 
+                let mut tx_flows_db = vec![];
                 let shielded_txs_db = {
                     let mut rows = Vec::with_capacity(shielded_txs.len());
 
-                    while let Some((MaspIndexedTx { kind, indexed_tx }, tx)) =
-                        shielded_txs.pop_first()
+                    while let Some((
+                        masp_indexed_tx @ MaspIndexedTx { kind, indexed_tx },
+                        tx,
+                    )) = shielded_txs.pop_first()
                     {
+                        tx_flows_db.extend(
+                            TxFlows::new(&tx.masp_tx).into_db(masp_indexed_tx),
+                        );
                         rows.push({
                             let is_masp_fee_payment = matches!(
                                 kind,
@@ -309,11 +902,12 @@ fn commit_inner(
 
                             TxInsertDb {
                                 block_index: indexed_tx.block_index.0 as i32,
-                                tx_bytes: tx.serialize_to_vec(),
+                                tx_bytes: tx.masp_tx.serialize_to_vec(),
                                 block_height: indexed_tx.block_height.0 as i32,
                                 masp_tx_index: indexed_tx.masp_tx_index.0
                                     as i32,
                                 is_masp_fee_payment,
+                                tx_hash: Some(tx.hash.to_string()),
                             }
                         });
                     }
@@ -325,6 +919,7 @@ fn commit_inner(
                     .on_conflict_do_nothing()
                     .execute(transaction_conn)
                     .context("Failed to insert shielded txs into db")?;
+                insert_tx_flows(transaction_conn, &tx_flows_db)?;
 
                 tracing::debug!(
                     block_height = %chain_state.block_height,
@@ -332,18 +927,59 @@ fn commit_inner(
                 );
             }
 
+            diesel::insert_into(schema::block::table)
+                .values(&committed_block.into_db())
+                .on_conflict_do_nothing()
+                .execute(transaction_conn)
+                .context("Failed to insert block header into db")?;
+
             let chain_state_db = chain_state.into_db();
-            diesel::insert_into(schema::chain_state::table)
+            // NB: the network identity is only recorded once, from the
+            // first committed block
+            let chain_id = diesel::insert_into(schema::chain_state::table)
                 .values(&chain_state_db)
                 .on_conflict(schema::chain_state::dsl::id)
                 .do_update()
-                .set(
+                .set((
                     schema::chain_state::block_height
                         .eq(chain_state_db.block_height),
-                )
-                .execute(transaction_conn)
+                    schema::chain_state::chain_id.eq(coalesce(
+                        schema::chain_state::chain_id,
+                        excluded(schema::chain_state::chain_id),
+                    )),
+                    schema::chain_state::first_block_height.eq(coalesce(
+                        schema::chain_state::first_block_height,
+                        excluded(schema::chain_state::first_block_height),
+                    )),
+                    schema::chain_state::first_block_hash.eq(coalesce(
+                        schema::chain_state::first_block_hash,
+                        excluded(schema::chain_state::first_block_hash),
+                    )),
+                ))
+                .returning(schema::chain_state::chain_id)
+                .get_result::<Option<String>>(transaction_conn)
                 .context("Failed to insert last chain state into db")?;
 
+            if let (Some(network), Some(chain_id)) =
+                (&chain_state.network, chain_id)
+            {
+                if network.chain_id != chain_id {
+                    return Err(NetworkMismatch {
+                        block_chain_id: network.chain_id.clone(),
+                        db_chain_id: chain_id,
+                    }
+                    .into());
+                }
+            }
+
+            Notification::Height {
+                block_height: chain_state.block_height.0,
+                num_masp_txs: num_masp_txs as u64,
+                commitment_tree_size: commitment_tree.size() as u64,
+            }
+            .send(transaction_conn)
+            .context("Failed to notify the committed block")?;
+
             tracing::debug!(
                 block_height = %chain_state.block_height,
                 "All data was successfully pre-committed, committing..."
@@ -360,3 +996,669 @@ fn commit_inner(
 
     Ok(())
 }
+
+/// Insert the commitment tree, witness map delta and notes index at the
+/// given height, if they changed since they were last inserted.
+fn insert_masp_state(
+    conn: &mut PgConnection,
+    block_height: BlockHeight,
+    commitment_tree: &mut CommitmentTree,
+    notes_index: &mut TxNoteMap,
+) -> anyhow::Result<()> {
+    if let Some((commitment_tree_db, witness_delta_db)) =
+        commitment_tree.into_db(block_height)
+    {
+        tracing::debug!(
+            %block_height,
+            "Pre-committing commitment tree"
+        );
+
+        diesel::insert_into(schema::commitment_tree::table)
+            .values(&commitment_tree_db)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to insert commitment tree into db")?;
+
+        tracing::debug!(
+            %block_height,
+            "Pre-committing witness map delta"
+        );
+
+        diesel::insert_into(schema::witness_delta::table)
+            .values(&witness_delta_db)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to insert witness map delta into db")?;
+
+        tracing::debug!(
+            %block_height,
+            "Pre-committed commitment tree and witness map delta"
+        );
+    }
+
+    if !notes_index.is_empty() {
+        tracing::debug!(
+            %block_height,
+            "Pre-committing notes map"
+        );
+
+        let notes_index_db = notes_index.into_db();
+        diesel::insert_into(schema::notes_index::table)
+            .values(&notes_index_db)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to insert notes map into db")?;
+
+        tracing::debug!(
+            %block_height,
+            "Pre-committed notes map"
+        );
+    }
+
+    Ok(())
+}
+
+/// Insert the flows of some MASP txs.
+fn insert_tx_flows(
+    conn: &mut PgConnection,
+    tx_flows: &[TxFlowInsertDb],
+) -> anyhow::Result<()> {
+    for chunk in tx_flows.chunks(SNAPSHOT_INSERT_CHUNK_SIZE) {
+        diesel::insert_into(schema::tx_flow::table)
+            .values(chunk)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to insert masp tx flows into db")?;
+    }
+
+    Ok(())
+}
+
+/// State of the indexer read from the db, from which snapshots are
+/// exported.
+pub struct SnapshotRows {
+    pub block_height: BlockHeight,
+    pub network: NetworkIdentity,
+    pub tree: BridgeTree,
+    pub notes_index: Vec<NotesIndexDb>,
+    pub txs: Vec<TxDb>,
+    pub blocks: Vec<BlockDb>,
+}
+
+pub async fn read_snapshot_rows(conn: Object) -> anyhow::Result<SnapshotRows> {
+    tracing::debug!("Reading snapshot data from db");
+
+    let rows = conn
+        .interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let block_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to read last synced height from db")?
+                        .context("No block has been indexed yet")?;
+
+                    let network = chain_state::table
+                        .select(NetworkIdentityDb::as_select())
+                        .first(conn)
+                        .context("Failed to read network identity from db")
+                        .map(NetworkIdentity::from_db)?
+                        .context(
+                            "No network identity has been recorded yet, it \
+                             will be recorded along with the next committed \
+                             block",
+                        )?;
+
+                    let tree = read_commitment_tree(conn)?;
+
+                    let notes_index = schema::notes_index::table
+                        .order(schema::notes_index::dsl::note_position.asc())
+                        .select(NotesIndexDb::as_select())
+                        .load(conn)
+                        .context("Failed to read notes index from db")?;
+
+                    let txs = schema::tx::table
+                        .order(schema::tx::dsl::id.asc())
+                        .select(TxDb::as_select())
+                        .load(conn)
+                        .context("Failed to read masp txs from db")?;
+
+                    let blocks = schema::block::table
+                        .order(schema::block::dsl::block_height.asc())
+                        .select(BlockDb::as_select())
+                        .load(conn)
+                        .context("Failed to read block headers from db")?;
+
+                    anyhow::Ok(SnapshotRows {
+                        block_height: BlockHeight::from(block_height),
+                        network,
+                        tree,
+                        notes_index,
+                        txs,
+                        blocks,
+                    })
+                })
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(
+        block_height = %rows.block_height,
+        num_notes = rows.tree.size(),
+        num_txs = rows.txs.len(),
+        "Read snapshot data from db"
+    );
+
+    Ok(rows)
+}
+
+/// Maximum number of rows inserted per statement when importing a
+/// snapshot, to stay clear of Postgres' bind parameter limit.
+const SNAPSHOT_INSERT_CHUNK_SIZE: usize = 4096;
+
+/// Import the state of the indexer into an empty db.
+///
+/// The commitment trees, witness map deltas, notes index and tx flows of
+/// every height are rebuilt from the imported txs, and checked against
+/// the commitment tree and notes index of the snapshot.
+pub async fn import_snapshot(
+    conn: Object,
+    chain_state: ChainState,
+    commitment_tree: &CommitmentTree,
+    notes_index: Vec<NotesIndexInsertDb>,
+    txs: Vec<TxInsertDb>,
+    blocks: Vec<BlockInsertDb>,
+) -> anyhow::Result<()> {
+    let block_height = chain_state.block_height;
+    let (tree_size, tree_root) =
+        (commitment_tree.size(), commitment_tree.root());
+
+    tracing::debug!(%block_height, "Importing snapshot into db");
+
+    conn.interact(move |conn| {
+        conn.build_transaction().read_write().run(|conn| {
+            let is_empty = diesel::select((
+                not(exists(chain_state::table.select(chain_state::dsl::id))),
+                not(exists(
+                    commitment_tree::table.select(commitment_tree::dsl::id),
+                )),
+                not(exists(
+                    witness_delta::table
+                        .select(witness_delta::dsl::block_height),
+                )),
+                not(exists(
+                    schema::notes_index::table
+                        .select(schema::notes_index::dsl::note_position),
+                )),
+                not(exists(schema::tx::table.select(schema::tx::dsl::id))),
+                not(exists(
+                    schema::block::table
+                        .select(schema::block::dsl::block_height),
+                )),
+                not(exists(
+                    schema::tx_flow::table
+                        .select(schema::tx_flow::dsl::block_height),
+                )),
+            ))
+            .get_result::<(bool, bool, bool, bool, bool, bool, bool)>(conn)
+            .context("Failed to check if the db is empty")?;
+
+            if is_empty != (true, true, true, true, true, true, true) {
+                anyhow::bail!(
+                    "Snapshots can only be imported into an empty db"
+                );
+            }
+
+            for chunk in txs.chunks(SNAPSHOT_INSERT_CHUNK_SIZE) {
+                diesel::insert_into(schema::tx::table)
+                    .values(chunk)
+                    .execute(conn)
+                    .context("Failed to insert shielded txs into db")?;
+            }
+
+            for chunk in blocks.chunks(SNAPSHOT_INSERT_CHUNK_SIZE) {
+                diesel::insert_into(schema::block::table)
+                    .values(chunk)
+                    .execute(conn)
+                    .context("Failed to insert block headers into db")?;
+            }
+
+            diesel::insert_into(schema::chain_state::table)
+                .values(&chain_state.into_db())
+                .execute(conn)
+                .context("Failed to insert last chain state into db")?;
+
+            let (rebuilt_tree, _) = replay_stored_txs(conn, 0)?;
+
+            if rebuilt_tree.size() != tree_size
+                || rebuilt_tree.root() != tree_root
+            {
+                anyhow::bail!(
+                    "Snapshot txs do not match its commitment tree, they \
+                     created {} notes but the tree has {tree_size} notes",
+                    rebuilt_tree.size()
+                );
+            }
+
+            let rebuilt_notes_index = schema::notes_index::table
+                .order(schema::notes_index::dsl::note_position.asc())
+                .select(NotesIndexDb::as_select())
+                .load(conn)
+                .context("Failed to read rebuilt notes index from db")?;
+
+            // NB: the notes index of the snapshot is ordered by position
+            let matches_notes_index = rebuilt_notes_index.len()
+                == notes_index.len()
+                && rebuilt_notes_index.iter().zip(&notes_index).all(
+                    |(rebuilt, note)| {
+                        rebuilt.note_position == note.note_position
+                            && rebuilt.block_height == note.block_height
+                            && rebuilt.block_index == note.block_index
+                            && rebuilt.masp_tx_index == note.masp_tx_index
+                            && rebuilt.is_masp_fee_payment
+                                == note.is_masp_fee_payment
+                    },
+                );
+
+            if !matches_notes_index {
+                anyhow::bail!("Snapshot txs do not match its notes index");
+            }
+
+            anyhow::Ok(())
+        })
+    })
+    .await
+    .context_db_interact_error()??;
+
+    tracing::debug!(%block_height, "Imported snapshot into db");
+
+    Ok(())
+}
+
+/// Result of the verification of the data stored in the db.
+#[derive(Debug, Default)]
+pub struct Verification {
+    pub summary: VerificationSummary,
+    /// The first inconsistency found, if any.
+    pub inconsistency: Option<Inconsistency>,
+    /// Roots of the stored commitment trees, in ascending height order.
+    pub tree_roots: Vec<(BlockHeight, Node)>,
+}
+
+/// MASP tx stored in the db, along with the notes it created.
+struct StoredTx {
+    block_height: i32,
+    block_index: i32,
+    masp_tx_index: i32,
+    is_masp_fee_payment: bool,
+    /// Position of the first note created by the tx.
+    note_position: usize,
+}
+
+impl StoredTx {
+    fn describe(&self) -> String {
+        format!(
+            "MASP tx {} of tx {} at height {}{}",
+            self.masp_tx_index,
+            self.block_index,
+            self.block_height,
+            if self.is_masp_fee_payment {
+                " (fee payment)"
+            } else {
+                ""
+            }
+        )
+    }
+
+    fn matches(&self, note: &NotesIndexDb) -> bool {
+        self.block_height == note.block_height
+            && self.block_index == note.block_index
+            && self.masp_tx_index == note.masp_tx_index
+            && self.is_masp_fee_payment == note.is_masp_fee_payment
+    }
+}
+
+/// MASP txs stored in the db, along with the notes they created.
+struct StoredNotes {
+    /// Stored MASP txs, in the order they were applied to the
+    /// commitment tree.
+    txs: Vec<StoredTx>,
+    /// Leaves of the commitment tree, derived from the stored txs.
+    leaves: Vec<Node>,
+    /// Size of the commitment tree after the txs at each height.
+    tree_sizes: BTreeMap<i32, usize>,
+}
+
+impl StoredNotes {
+    /// Return the size of the commitment tree after the txs at heights
+    /// up to and including `block_height`.
+    fn tree_size_at(&self, block_height: i32) -> usize {
+        self.tree_sizes
+            .range(..=block_height)
+            .next_back()
+            .map_or(0, |(_, size)| *size)
+    }
+}
+
+/// Walk every stored height, and check that the notes index, witness map
+/// deltas and commitment trees agree with the stored MASP txs.
+///
+/// Verification stops at the first inconsistency found.
+pub async fn verify_index(conn: Object) -> anyhow::Result<Verification> {
+    tracing::debug!("Verifying MASP data in db");
+
+    let verification = conn
+        .interact(move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let mut verification = Verification::default();
+
+                    verification.summary.last_block_height = chain_state::table
+                        .select(max(chain_state::dsl::block_height))
+                        .first::<Option<i32>>(conn)
+                        .context("Failed to read last synced height from db")?
+                        .map(|height| height as u64);
+
+                    let notes = read_stored_notes(conn)?;
+                    verification.summary.num_txs = notes.txs.len();
+                    verification.summary.num_notes = notes.leaves.len();
+
+                    verification.inconsistency = run_verification_checks(
+                        conn,
+                        &notes,
+                        &mut verification,
+                    )?;
+
+                    anyhow::Ok(verification)
+                })
+        })
+        .await
+        .context_db_interact_error()??;
+
+    tracing::debug!(
+        consistent = verification.inconsistency.is_none(),
+        "Verified MASP data in db"
+    );
+
+    Ok(verification)
+}
+
+fn run_verification_checks(
+    conn: &mut PgConnection,
+    notes: &StoredNotes,
+    verification: &mut Verification,
+) -> anyhow::Result<Option<Inconsistency>> {
+    if let Some(inconsistency) =
+        verify_notes_index_entries(conn, notes, &mut verification.summary)?
+    {
+        return Ok(Some(inconsistency));
+    }
+    if let Some(inconsistency) =
+        verify_witness_map_deltas(conn, notes, &mut verification.summary)?
+    {
+        return Ok(Some(inconsistency));
+    }
+    verify_commitment_trees(conn, notes, verification)
+}
+
+/// Read the stored MASP txs, in the order they were applied to the
+/// commitment tree, along with the notes they created.
+fn read_stored_notes(conn: &mut PgConnection) -> anyhow::Result<StoredNotes> {
+    let mut notes = StoredNotes {
+        txs: Vec::new(),
+        leaves: Vec::new(),
+        tree_sizes: BTreeMap::new(),
+    };
+
+    // NB: follow the ordering of `MaspIndexedTx`
+    for maybe_tx in schema::tx::table
+        .order((
+            schema::tx::dsl::block_height.asc(),
+            schema::tx::dsl::is_masp_fee_payment.desc(),
+            schema::tx::dsl::block_index.asc(),
+            schema::tx::dsl::masp_tx_index.asc(),
+        ))
+        .select(TxDb::as_select())
+        .load_iter::<_, DbDefaultLoadingMode>(conn)
+        .context("Failed to query masp txs from db")?
+    {
+        let tx = maybe_tx.context("Failed to get masp tx row data from db")?;
+
+        notes.txs.push(StoredTx {
+            block_height: tx.block_height,
+            block_index: tx.block_index,
+            masp_tx_index: tx.masp_tx_index,
+            is_masp_fee_payment: tx.is_masp_fee_payment,
+            note_position: notes.leaves.len(),
+        });
+        notes
+            .leaves
+            .extend(shared::transaction::masp_note_commitments(&tx.tx_bytes)?);
+        notes.tree_sizes.insert(tx.block_height, notes.leaves.len());
+    }
+
+    Ok(notes)
+}
+
+/// Check that the notes index holds an entry for the first note of
+/// each MASP tx, and no other entry.
+///
+/// MASP txs that create no notes share the position of the first note
+/// of the next tx, in which case only the first of them is indexed.
+fn verify_notes_index_entries(
+    conn: &mut PgConnection,
+    notes: &StoredNotes,
+    summary: &mut VerificationSummary,
+) -> anyhow::Result<Option<Inconsistency>> {
+    let mut expected: BTreeMap<usize, &StoredTx> = BTreeMap::new();
+    for tx in &notes.txs {
+        expected.entry(tx.note_position).or_insert(tx);
+    }
+
+    let stored: BTreeMap<usize, NotesIndexDb> = schema::notes_index::table
+        .select(NotesIndexDb::as_select())
+        .load(conn)
+        .context("Failed to read notes index from db")?
+        .into_iter()
+        .map(|note| (note.note_position as usize, note))
+        .collect();
+    summary.num_notes_index_entries = stored.len();
+
+    let positions: BTreeSet<usize> =
+        expected.keys().chain(stored.keys()).copied().collect();
+
+    for note_position in positions {
+        let inconsistency =
+            match (expected.get(&note_position), stored.get(&note_position)) {
+                (Some(tx), Some(note)) if tx.matches(note) => continue,
+                (Some(tx), Some(note)) => Inconsistency::new(
+                    Check::NotesIndex,
+                    format!(
+                        "The notes index entry at position {note_position} \
+                         refers to MASP tx {} of tx {} at height {}, but the \
+                         first note at that position was created by the {}",
+                        note.masp_tx_index,
+                        note.block_index,
+                        note.block_height,
+                        tx.describe()
+                    ),
+                )
+                .at_height(BlockHeight::from(tx.block_height)),
+                (Some(tx), None) => Inconsistency::new(
+                    Check::NotesIndex,
+                    format!(
+                        "The notes index has no entry for the {}, whose first \
+                         note is at position {note_position}",
+                        tx.describe()
+                    ),
+                )
+                .at_height(BlockHeight::from(tx.block_height)),
+                (None, Some(note)) => Inconsistency::new(
+                    Check::NotesIndex,
+                    format!(
+                        "The notes index entry at position {note_position} \
+                         does not match the first note of any stored MASP tx"
+                    ),
+                )
+                .at_height(BlockHeight::from(note.block_height)),
+                (None, None) => {
+                    unreachable!("Position should be in either map")
+                }
+            };
+
+        return Ok(Some(inconsistency.at_note(note_position)));
+    }
+
+    Ok(None)
+}
+
+/// Check that the leaves of each witness map delta are the notes
+/// created by the MASP txs stored since the previous delta.
+fn verify_witness_map_deltas(
+    conn: &mut PgConnection,
+    notes: &StoredNotes,
+    summary: &mut VerificationSummary,
+) -> anyhow::Result<Option<Inconsistency>> {
+    let mut tree_size = 0;
+
+    for maybe_delta in witness_delta::table
+        .order(witness_delta::dsl::block_height.asc())
+        .select(WitnessDeltaDb::as_select())
+        .load_iter::<_, DbDefaultLoadingMode>(conn)
+        .context("Failed to query witness map deltas from db")?
+    {
+        let delta = maybe_delta
+            .context("Failed to get witness map delta row data from db")?;
+        let block_height = BlockHeight::from(delta.block_height);
+        summary.num_witness_map_deltas += 1;
+
+        let Some(leaves) = delta.leaves else {
+            return Ok(Some(
+                Inconsistency::new(
+                    Check::WitnessMapDelta,
+                    "The witness map delta is missing its leaves",
+                )
+                .at_height(block_height),
+            ));
+        };
+        let leaves = deserialize_leaves(&leaves)?;
+
+        let next_tree_size = notes.tree_size_at(delta.block_height);
+        let expected_leaves = &notes.leaves[tree_size..next_tree_size];
+
+        if let Some(offset) = leaves
+            .iter()
+            .zip(expected_leaves)
+            .position(|(leaf, expected_leaf)| leaf != expected_leaf)
+        {
+            return Ok(Some(
+                Inconsistency::new(
+                    Check::WitnessMapDelta,
+                    "The witness map delta holds a different note than the \
+                     one created by the stored MASP txs",
+                )
+                .at_height(block_height)
+                .at_note(tree_size + offset),
+            ));
+        }
+        if leaves.len() != expected_leaves.len() {
+            return Ok(Some(
+                Inconsistency::new(
+                    Check::WitnessMapDelta,
+                    format!(
+                        "The witness map delta holds {} notes, but the stored \
+                         MASP txs created {} notes since the previous delta",
+                        leaves.len(),
+                        expected_leaves.len()
+                    ),
+                )
+                .at_height(block_height),
+            ));
+        }
+
+        tree_size = next_tree_size;
+    }
+
+    if tree_size != notes.leaves.len() {
+        return Ok(Some(
+            Inconsistency::new(
+                Check::WitnessMapDelta,
+                format!(
+                    "The stored MASP txs created {} notes, but only {} are \
+                     covered by witness map deltas",
+                    notes.leaves.len(),
+                    tree_size
+                ),
+            )
+            .at_note(tree_size),
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Check that each stored commitment tree holds the notes created by
+/// the MASP txs up to its height.
+fn verify_commitment_trees(
+    conn: &mut PgConnection,
+    notes: &StoredNotes,
+    verification: &mut Verification,
+) -> anyhow::Result<Option<Inconsistency>> {
+    let mut tree = MaspCommitmentTree::<Node>::empty();
+
+    for maybe_tree in commitment_tree::table
+        .order(commitment_tree::dsl::block_height.asc())
+        .select(TreeDb::as_select())
+        .load_iter::<_, DbDefaultLoadingMode>(conn)
+        .context("Failed to query commitment trees from db")?
+    {
+        let stored_tree =
+            maybe_tree.context("Failed to get commitment tree row from db")?;
+        let block_height = BlockHeight::from(stored_tree.block_height);
+        verification.summary.num_commitment_trees += 1;
+
+        let stored_tree =
+            MaspCommitmentTree::<Node>::try_from_slice(&stored_tree.tree)
+                .context(
+                    "Failed to deserialize commitment tree from db row data",
+                )?;
+
+        let tree_size = notes.tree_size_at(block_height.0 as i32);
+        for leaf in &notes.leaves[tree.size()..tree_size] {
+            tree.append(*leaf)
+                .map_err(|()| anyhow!("Note commitment tree is full"))?;
+        }
+
+        if stored_tree.size() != tree_size {
+            return Ok(Some(
+                Inconsistency::new(
+                    Check::CommitmentTree,
+                    format!(
+                        "The commitment tree has {} notes, but the stored \
+                         MASP txs created {tree_size} notes up to its height",
+                        stored_tree.size()
+                    ),
+                )
+                .at_height(block_height),
+            ));
+        }
+        if stored_tree.root() != tree.root() {
+            return Ok(Some(
+                Inconsistency::new(
+                    Check::CommitmentTree,
+                    "The root of the commitment tree does not match the notes \
+                     created by the stored MASP txs",
+                )
+                .at_height(block_height),
+            ));
+        }
+
+        verification.tree_roots.push((block_height, tree.root()));
+    }
+
+    Ok(None)
+}
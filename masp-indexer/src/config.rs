@@ -0,0 +1,59 @@
+use block_index::config::IndexerConfig;
+use chain::config::CrawlerConfig;
+use clap::{ArgMatches, CommandFactory};
+use clap_verbosity_flag::{InfoLevel, Verbosity};
+use webserver::config::ServerConfig;
+
+#[derive(clap::Parser)]
+pub struct AppConfig {
+    /// Link to the Postgres database, shared by all the components
+    #[clap(long, env)]
+    pub database_url: String,
+
+    /// Components to run, separated by commas
+    #[clap(
+        long,
+        env,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "chain,webserver,block-index"
+    )]
+    pub components: Vec<Component>,
+
+    #[command(flatten, next_help_heading = "Crawler")]
+    pub crawler: CrawlerConfig,
+
+    #[command(flatten, next_help_heading = "Webserver")]
+    pub server: ServerConfig,
+
+    #[command(flatten, next_help_heading = "Block index builder")]
+    pub indexer: IndexerConfig,
+
+    #[command(flatten)]
+    pub verbosity: Verbosity<InfoLevel>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Component {
+    /// The crawler, indexing the MASP data of the chain
+    Chain,
+    /// The API server
+    Webserver,
+    /// The builder of the index of the block heights with MASP txs
+    BlockIndex,
+}
+
+/// Parse the command line arguments of the components.
+///
+/// The arguments of each component are defined by its own crate, and
+/// are adjusted such that they can coexist in a single command line.
+pub fn parse_args() -> ArgMatches {
+    AppConfig::command()
+        // NB: only required if the crawler is run
+        .mut_arg("cometbft_url", |arg| arg.required(false))
+        // NB: disambiguate from the crawler's interval
+        .mut_arg("block_index_interval", |arg| {
+            arg.long("block-index-interval").env("BLOCK_INDEX_INTERVAL")
+        })
+        .get_matches()
+}
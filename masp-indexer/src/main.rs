@@ -0,0 +1,103 @@
+pub mod config;
+
+use anyhow::anyhow;
+use clap::FromArgMatches;
+use shared::appstate::AppState;
+use shared::error::{IntoMainError, MainError};
+use shared::exit_handle;
+use tokio::task::JoinSet;
+use webserver::app::ApplicationServer;
+
+use crate::config::{AppConfig, Component};
+
+const VERSION_STRING: &str = env!("VERGEN_GIT_SHA");
+
+#[tokio::main]
+async fn main() -> Result<(), MainError> {
+    let AppConfig {
+        database_url,
+        components,
+        crawler,
+        server,
+        indexer,
+        verbosity,
+    } = AppConfig::from_arg_matches(&config::parse_args())
+        .unwrap_or_else(|err| err.exit());
+
+    chain::config::install_tracing_subscriber(verbosity);
+
+    tracing::info!(
+        version = VERSION_STRING,
+        ?components,
+        "Started the namada-masp-indexer"
+    );
+    exit_handle::spawn_signal_handler();
+
+    if components.contains(&Component::Chain) && crawler.cometbft_url.is_empty()
+    {
+        return Err(anyhow!("No CometBFT URL was provided to the crawler"))
+            .into_main_error("Configuration error");
+    }
+
+    // NB: all the components share the same pool of db connections
+    let app_state = AppState::new(database_url).await.into_db_error()?;
+
+    let mut tasks = JoinSet::new();
+
+    if components.contains(&Component::Webserver) {
+        let app_state = app_state.clone();
+        tasks.spawn(async move {
+            let result =
+                ApplicationServer::serve_with_state(&server, app_state)
+                    .await
+                    .into_main_error("Webserver error");
+            (Component::Webserver, result)
+        });
+    }
+    if components.contains(&Component::BlockIndex) {
+        let app_state = app_state.clone();
+        tasks.spawn(async move {
+            (
+                Component::BlockIndex,
+                block_index::run(indexer, app_state).await,
+            )
+        });
+    }
+
+    // NB: the crawler is run on the main task, since its future is not
+    // `Send`
+    let crawl = async {
+        if !components.contains(&Component::Chain) {
+            return Ok(());
+        }
+
+        let result = chain::run(crawler, None, app_state).await;
+        exited(Component::Chain);
+        result
+    };
+
+    let serve = async {
+        let mut result = Ok(());
+
+        while let Some(joined) = tasks.join_next().await {
+            let (component, component_result) = joined
+                .map_err(anyhow::Error::from)
+                .into_tokio_join_error()?;
+
+            exited(component);
+            result = result.and(component_result);
+        }
+
+        result
+    };
+
+    let (crawl_result, serve_result) = tokio::join!(crawl, serve);
+
+    crawl_result.and(serve_result)
+}
+
+/// Stop all the components as soon as one of them exits.
+fn exited(component: Component) {
+    tracing::info!(?component, "Component has exited");
+    exit_handle::exit();
+}
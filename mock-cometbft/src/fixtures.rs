@@ -0,0 +1,134 @@
+//! Fixture blocks served by the mock CometBFT RPC server.
+//!
+//! A fixture directory is laid out as follows:
+//!
+//! - `chain.json`: the chain id, and the storage keys that are present in the
+//!   chain's storage (e.g. commitment tree anchors).
+//! - `blocks/<height>.json`: the raw `block` and `block_results` RPC responses
+//!   at each height, starting at height 1.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::Context;
+use namada_sdk::address::Address;
+use namada_sdk::token::Denomination;
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::endpoint::{block, block_results};
+
+/// Address of the native token of the fixture chain, in which the
+/// values of the generated MASP txs are denominated.
+const NATIVE_TOKEN: &str = "tnam1q99c37u38grkdcc2qze0hz4zjjd8zr3yucd3mzgz";
+
+/// Denomination of the native token of the fixture chain.
+pub const NATIVE_TOKEN_DENOM: Denomination = Denomination(6);
+
+/// Return the address of the native token of the fixture chain.
+pub fn native_token() -> Address {
+    Address::decode(NATIVE_TOKEN).expect("Native token address should be valid")
+}
+
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    pub chain_id: String,
+    pub storage_keys: BTreeSet<String>,
+    pub blocks: BTreeMap<u64, FixtureBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureBlock {
+    pub block: block::Response,
+    pub block_results: block_results::Response,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChainFixture {
+    chain_id: String,
+    storage_keys: BTreeSet<String>,
+}
+
+impl Fixtures {
+    /// Return the height of the last fixture block.
+    pub fn latest_height(&self) -> u64 {
+        self.blocks
+            .last_key_value()
+            .map_or(0, |(height, _)| *height)
+    }
+
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let ChainFixture {
+            chain_id,
+            storage_keys,
+        } = read_json(&dir.join("chain.json"))?;
+
+        let mut blocks = BTreeMap::new();
+
+        for entry in
+            std::fs::read_dir(dir.join("blocks")).with_context(|| {
+                format!("Failed to list fixture blocks in {}", dir.display())
+            })?
+        {
+            let path = entry
+                .context("Failed to read fixture blocks directory entry")?
+                .path();
+            let height = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok())
+                .with_context(|| {
+                    format!("Invalid fixture block file {}", path.display())
+                })?;
+            blocks.insert(height, read_json(&path)?);
+        }
+
+        if let Some(missing) = (1..=blocks.len() as u64)
+            .find(|height| !blocks.contains_key(height))
+        {
+            anyhow::bail!("Fixture block at height {missing} is missing");
+        }
+
+        Ok(Self {
+            chain_id,
+            storage_keys,
+            blocks,
+        })
+    }
+
+    pub fn write(&self, dir: &Path) -> anyhow::Result<()> {
+        let blocks_dir = dir.join("blocks");
+        std::fs::create_dir_all(&blocks_dir).with_context(|| {
+            format!("Failed to create fixture directory {}", dir.display())
+        })?;
+
+        write_json(
+            &dir.join("chain.json"),
+            &ChainFixture {
+                chain_id: self.chain_id.clone(),
+                storage_keys: self.storage_keys.clone(),
+            },
+        )?;
+
+        for (height, block) in &self.blocks {
+            write_json(&blocks_dir.join(format!("{height}.json")), block)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<T> {
+    let contents = std::fs::read(path).with_context(|| {
+        format!("Failed to read fixture file {}", path.display())
+    })?;
+    serde_json::from_slice(&contents).with_context(|| {
+        format!("Failed to parse fixture file {}", path.display())
+    })
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let contents = serde_json::to_vec_pretty(value)
+        .context("Failed to serialize fixture")?;
+    std::fs::write(path, contents).with_context(|| {
+        format!("Failed to write fixture file {}", path.display())
+    })
+}
@@ -0,0 +1,384 @@
+//! Generation of synthetic fixture chains.
+//!
+//! The generated MASP transactions carry random, yet well formed, note
+//! commitments and value commitments, but no valid proofs or signatures.
+//! This is enough for the indexer, which does not verify transactions.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context;
+use namada_core::chain::ChainId;
+use namada_core::masp::encode_asset_type;
+use namada_core::masp_primitives::consensus::{BlockHeight, BranchId};
+use namada_core::masp_primitives::ff::PrimeField;
+use namada_core::masp_primitives::group::Group;
+use namada_core::masp_primitives::merkle_tree::CommitmentTree;
+use namada_core::masp_primitives::sapling::Node;
+use namada_core::masp_primitives::sapling::redjubjub::Signature;
+use namada_core::masp_primitives::transaction::components::I128Sum;
+use namada_core::masp_primitives::transaction::components::sapling::{
+    self, Authorized, OutputDescription,
+};
+use namada_core::masp_primitives::transaction::components::transparent::{
+    self, TxIn, TxOut,
+};
+use namada_core::masp_primitives::transaction::{
+    self, Transaction, TransactionData, TransparentAddress, TxVersion,
+};
+use namada_core::masp_primitives::{bls12_381, jubjub};
+use namada_core::time::DateTimeUtc;
+use namada_sdk::events::Event;
+use namada_sdk::state::TxIndex;
+use namada_sdk::token::MaspDigitPos;
+use namada_sdk::token::storage_key::masp_commitment_anchor_key;
+use namada_tx::event::{MaspEvent, MaspEventKind, MaspTxRef};
+use namada_tx::{Data, IndexedTx, Tx};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tendermint::block::header::Version;
+use tendermint::block::parts::Header as PartSetHeader;
+use tendermint::block::{self as tm_block, Header};
+use tendermint::{Hash, Time, account, chain};
+use tendermint_rpc::endpoint::{block, block_results};
+
+use crate::fixtures::{self, FixtureBlock, Fixtures};
+
+/// Chain id of the generated fixtures.
+pub const CHAIN_ID: &str = "masp-indexer-e2e.a1b2c3d4e5f6a7b8c9d0e";
+
+/// Unix timestamp of the first generated block.
+const GENESIS_TIME: i64 = 1_735_689_600;
+
+/// Seconds elapsed between two consecutive generated blocks.
+const BLOCK_TIME: i64 = 6;
+
+/// Value paid by each generated MASP fee payment.
+const FEE_AMOUNT: u64 = 5_000;
+
+/// Value moved across the boundary of the shielded pool by a MASP
+/// transaction.
+#[derive(Clone, Copy)]
+enum Flow {
+    None,
+    Shield(u64),
+    Unshield(u64),
+}
+
+/// A MASP transaction within a Namada transaction batch.
+struct MaspSpec {
+    kind: MaspEventKind,
+    batch_index: u32,
+    num_outputs: usize,
+    flow: Flow,
+}
+
+const fn transfer(batch_index: u32, num_outputs: usize) -> MaspSpec {
+    MaspSpec {
+        kind: MaspEventKind::Transfer,
+        batch_index,
+        num_outputs,
+        flow: Flow::None,
+    }
+}
+
+const fn shielding(
+    batch_index: u32,
+    num_outputs: usize,
+    value: u64,
+) -> MaspSpec {
+    MaspSpec {
+        flow: Flow::Shield(value),
+        ..transfer(batch_index, num_outputs)
+    }
+}
+
+const fn unshielding(
+    batch_index: u32,
+    num_outputs: usize,
+    value: u64,
+) -> MaspSpec {
+    MaspSpec {
+        flow: Flow::Unshield(value),
+        ..transfer(batch_index, num_outputs)
+    }
+}
+
+const fn fee_payment(num_outputs: usize) -> MaspSpec {
+    MaspSpec {
+        kind: MaspEventKind::FeePayment,
+        batch_index: 0,
+        num_outputs,
+        flow: Flow::Unshield(FEE_AMOUNT),
+    }
+}
+
+/// Namada transactions of each block of the generated chain. Each
+/// transaction is described by the MASP transactions it carries.
+///
+/// The chain covers empty blocks, batches with multiple MASP
+/// transactions, MASP fee payments, transactions without MASP data,
+/// MASP transactions without shielded outputs, and shielding and
+/// unshielding transactions.
+const BLOCKS: &[&[&[MaspSpec]]] = &[
+    // 1
+    &[],
+    // 2
+    &[&[shielding(0, 2, 1_000_000)]],
+    // 3
+    &[],
+    // 4
+    &[&[transfer(0, 1)], &[transfer(0, 3), transfer(1, 1)]],
+    // 5
+    &[&[]],
+    // 6
+    &[&[fee_payment(1), transfer(0, 2)], &[]],
+    // 7
+    &[&[transfer(0, 0)]],
+    // 8
+    &[],
+    // 9
+    &[&[transfer(0, 4)], &[shielding(0, 5, 250_000)]],
+    // 10
+    &[&[fee_payment(1)], &[fee_payment(2), transfer(0, 1)]],
+    // 11
+    &[],
+    // 12
+    &[&[transfer(0, 2), unshielding(2, 3, 400_000)]],
+    // 13
+    &[&[transfer(0, 1)]],
+    // 14
+    &[],
+    // 15
+    &[&[transfer(0, 6)], &[transfer(0, 1)], &[fee_payment(1)]],
+];
+
+/// Generate the fixture chain, deterministically from the given seed.
+pub fn generate(seed: u64) -> anyhow::Result<Fixtures> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut tree = CommitmentTree::<Node>::empty();
+    let mut storage_keys = BTreeSet::new();
+    let mut blocks = BTreeMap::new();
+    let mut last_block_id = None;
+
+    for (height, txs) in (1u64..).zip(BLOCKS) {
+        let mut data = Vec::with_capacity(txs.len());
+        let mut events = Vec::new();
+        let mut notes = BTreeMap::new();
+
+        let block_time = GENESIS_TIME + height as i64 * BLOCK_TIME;
+
+        for (block_index, masp_txs) in (0u32..).zip(txs.iter()) {
+            let mut tx = Tx::new(ChainId(CHAIN_ID.to_string()), None);
+            tx.header.timestamp = DateTimeUtc::from_unix_timestamp(block_time)
+                .context("Invalid fixture tx timestamp")?;
+            // NB: data sections are salted with random bytes by
+            // default, which would make the fixtures non-deterministic
+            tx.set_data(Data {
+                salt: rng.random(),
+                data: height.to_le_bytes().to_vec(),
+            });
+
+            for spec in masp_txs.iter() {
+                let masp_tx =
+                    random_masp_tx(&mut rng, spec.num_outputs, spec.flow)?;
+                let cmus: Vec<_> = masp_tx
+                    .sapling_bundle()
+                    .map_or(&vec![], |bundle| &bundle.shielded_outputs)
+                    .iter()
+                    .map(|output| Node::new(output.cmu.to_repr()))
+                    .collect();
+                let (_, masp_tx_id) = tx.add_masp_tx_section(masp_tx);
+
+                let tx_index = IndexedTx {
+                    block_height: height.into(),
+                    block_index: TxIndex(block_index),
+                    batch_index: Some(spec.batch_index),
+                };
+                let is_fee_payment =
+                    matches!(spec.kind, MaspEventKind::FeePayment);
+
+                // NB: notes are appended to the commitment tree with
+                // fee payments first, in the same order as the indexer
+                notes.insert(
+                    (!is_fee_payment, block_index, spec.batch_index),
+                    cmus,
+                );
+                events.push(MaspEvent {
+                    tx_index,
+                    kind: spec.kind,
+                    data: MaspTxRef::MaspSection(masp_tx_id),
+                });
+            }
+
+            data.push(tx.to_bytes());
+        }
+
+        // NB: events are emitted in reverse order, to check that the
+        // indexer does not depend on the order of the events
+        let end_block_events = events
+            .into_iter()
+            .rev()
+            .map(|event| Event::from(event).into())
+            .collect();
+
+        let mut appended_notes = false;
+        for cmu in notes.into_values().flatten() {
+            tree.append(cmu)
+                .map_err(|()| anyhow::anyhow!("Commitment tree is full"))?;
+            appended_notes = true;
+        }
+        if appended_notes {
+            storage_keys
+                .insert(masp_commitment_anchor_key(tree.root()).to_string());
+        }
+
+        let header = Header {
+            version: Version { block: 11, app: 0 },
+            chain_id: chain::Id::try_from(CHAIN_ID)
+                .context("Invalid fixture chain id")?,
+            height: tm_block::Height::try_from(height)
+                .context("Invalid fixture block height")?,
+            time: Time::from_unix_timestamp(block_time, 0)
+                .context("Invalid fixture block time")?,
+            last_block_id,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::Sha256([0; 32]),
+            next_validators_hash: Hash::Sha256([0; 32]),
+            consensus_hash: Hash::Sha256([0; 32]),
+            app_hash: Default::default(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([0; 20]),
+        };
+        let block_id = tm_block::Id {
+            hash: header.hash(),
+            part_set_header: PartSetHeader::default(),
+        };
+        last_block_id = Some(block_id);
+
+        blocks.insert(
+            height,
+            FixtureBlock {
+                block: block::Response {
+                    block_id,
+                    block: tm_block::Block::new(
+                        header,
+                        data,
+                        Default::default(),
+                        None,
+                    ),
+                },
+                block_results: block_results::Response {
+                    height: tm_block::Height::try_from(height)
+                        .context("Invalid fixture block height")?,
+                    txs_results: None,
+                    finalize_block_events: vec![],
+                    begin_block_events: None,
+                    end_block_events: Some(end_block_events),
+                    validator_updates: vec![],
+                    consensus_param_updates: None,
+                    app_hash: Default::default(),
+                },
+            },
+        );
+    }
+
+    Ok(Fixtures {
+        chain_id: CHAIN_ID.to_string(),
+        storage_keys,
+        blocks,
+    })
+}
+
+/// Build a MASP transaction with the given number of random shielded
+/// outputs, moving the given value across the shielded pool.
+fn random_masp_tx(
+    rng: &mut StdRng,
+    num_outputs: usize,
+    flow: Flow,
+) -> anyhow::Result<Transaction> {
+    let asset_type = encode_asset_type(
+        fixtures::native_token(),
+        fixtures::NATIVE_TOKEN_DENOM,
+        MaspDigitPos::Zero,
+        None,
+    )
+    .context("Invalid fixture asset type")?;
+
+    let (transparent_bundle, value_balance) = match flow {
+        Flow::None => (None, 0),
+        Flow::Shield(value) => (
+            Some(transparent::Bundle {
+                vin: vec![TxIn {
+                    asset_type,
+                    value,
+                    address: TransparentAddress(rng.random()),
+                    transparent_sig: (),
+                }],
+                vout: vec![],
+                authorization: transparent::Authorized,
+            }),
+            -i128::from(value),
+        ),
+        Flow::Unshield(value) => (
+            Some(transparent::Bundle {
+                vin: vec![],
+                vout: vec![TxOut {
+                    asset_type,
+                    value,
+                    address: TransparentAddress(rng.random()),
+                }],
+                authorization: transparent::Authorized,
+            }),
+            i128::from(value),
+        ),
+    };
+
+    let sapling_bundle = (num_outputs > 0).then(|| sapling::Bundle {
+        shielded_spends: vec![],
+        shielded_converts: vec![],
+        shielded_outputs: (0..num_outputs)
+            .map(|_| random_output(rng))
+            .collect(),
+        value_balance: I128Sum::from_pair(asset_type, value_balance),
+        authorization: Authorized {
+            binding_sig: Signature::read(&[0u8; 64][..])
+                .expect("Reading from a byte slice should not fail"),
+        },
+    });
+
+    TransactionData::<transaction::Authorized>::from_parts(
+        TxVersion::MASPv5,
+        BranchId::MASP,
+        0,
+        BlockHeight::from_u32(0),
+        transparent_bundle,
+        sapling_bundle,
+    )
+    .freeze()
+    .context("Failed to build fixture MASP transaction")
+}
+
+fn random_output(rng: &mut StdRng) -> OutputDescription<[u8; 192]> {
+    let value_commitment_trapdoor = jubjub::Fr::from_bytes_wide(&rng.random());
+    let note_commitment = bls12_381::Scalar::from_bytes_wide(&rng.random());
+
+    let mut enc_ciphertext = [0u8; 612];
+    rng.fill(&mut enc_ciphertext[..]);
+    let mut out_ciphertext = [0u8; 80];
+    rng.fill(&mut out_ciphertext[..]);
+    let mut zkproof = [0u8; 192];
+    rng.fill(&mut zkproof[..]);
+
+    OutputDescription {
+        cv: (jubjub::SubgroupPoint::generator() * value_commitment_trapdoor)
+            .into(),
+        cmu: note_commitment,
+        ephemeral_key: rng.random::<[u8; 32]>().into(),
+        enc_ciphertext,
+        out_ciphertext,
+        zkproof,
+    }
+}
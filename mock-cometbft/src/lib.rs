@@ -0,0 +1,6 @@
+pub mod fixtures;
+pub mod generate;
+pub mod server;
+
+pub use fixtures::Fixtures;
+pub use server::{Conversions, MockCometBft};
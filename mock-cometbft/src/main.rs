@@ -0,0 +1,55 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::Parser;
+use mock_cometbft::{Fixtures, MockCometBft, generate};
+
+#[derive(clap::Parser)]
+enum Command {
+    /// Serve the fixture blocks in the given directory
+    Serve {
+        /// Directory holding the fixtures
+        #[clap(long)]
+        fixtures: PathBuf,
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:26657")]
+        address: SocketAddr,
+    },
+    /// Generate a synthetic fixture chain into the given directory
+    Generate {
+        /// Directory to write the fixtures to
+        #[clap(long)]
+        out: PathBuf,
+        /// Seed of the random data in the fixtures
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .init();
+
+    match Command::parse() {
+        Command::Serve { fixtures, address } => {
+            let server =
+                MockCometBft::spawn(Fixtures::load(&fixtures)?, address)?;
+            tracing::info!(url = server.url(), "Serving fixtures");
+            tokio::signal::ctrl_c().await?;
+            server.shutdown().await;
+        }
+        Command::Generate { out, seed } => {
+            let fixtures = generate::generate(seed)?;
+            fixtures.write(&out)?;
+            tracing::info!(
+                out = %out.display(),
+                latest_height = fixtures.latest_height(),
+                "Generated fixtures"
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,548 @@
+//! JSON-RPC server mimicking the subset of the CometBFT RPC used by
+//! the indexer.
+
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Context;
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use namada_core::masp::MaspEpoch;
+use namada_sdk::address::Address;
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::masp_primitives::asset_type::AssetType;
+use namada_sdk::masp_primitives::transaction::components::I128Sum;
+use namada_sdk::storage::PrefixValue;
+use namada_sdk::token::{Denomination, MaspDigitPos};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tendermint::block::Height;
+use tendermint_rpc::endpoint::{abci_query, block, block_results};
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+
+use crate::fixtures::{self, Fixtures};
+
+/// Prefix of the ABCI query paths used to check for the existence of
+/// a storage key.
+const HAS_KEY_PATH_PREFIX: &str = "/shell/has_key/";
+
+/// ABCI query path of the MASP epoch of the last block.
+const MASP_EPOCH_PATH: &str = "/shell/masp_epoch";
+
+/// ABCI query path of the MASP conversion state.
+const CONVERSIONS_PATH: &str = "/shell/conversions";
+
+/// ABCI query path of the native token.
+const NATIVE_TOKEN_PATH: &str = "/shell/native_token";
+
+/// Prefix of the ABCI query paths used to read the denomination of a
+/// token.
+const DENOMINATION_PATH_PREFIX: &str = "/vp/token/denomination/";
+
+/// Prefix of the ABCI query paths used to iterate over the storage
+/// values under a key prefix.
+const PREFIX_PATH_PREFIX: &str = "/shell/prefix/";
+
+/// Conversion of each MASP asset type, as returned by the conversion
+/// state query.
+pub type Conversions = BTreeMap<
+    AssetType,
+    (Address, Denomination, MaspDigitPos, MaspEpoch, I128Sum),
+>;
+
+/// JSON-RPC error code returned by CometBFT on invalid requests.
+const INTERNAL_ERROR: i64 = -32603;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Clone)]
+struct ServerState {
+    fixtures: Arc<Fixtures>,
+    earliest_height: Arc<AtomicU64>,
+    latest_height: Arc<watch::Sender<u64>>,
+    conversion_state: Arc<watch::Sender<(MaspEpoch, Conversions)>>,
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: &'static str,
+    data: String,
+}
+
+/// Handle to a running mock CometBFT RPC server.
+///
+/// The server is shut down when the handle is dropped.
+pub struct MockCometBft {
+    url: String,
+    websocket_url: String,
+    earliest_height: Arc<AtomicU64>,
+    latest_height: Arc<watch::Sender<u64>>,
+    max_height: u64,
+    conversion_state: Arc<watch::Sender<(MaspEpoch, Conversions)>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl MockCometBft {
+    /// Start serving the given fixtures at `addr`. All fixture blocks
+    /// are initially visible to clients.
+    pub fn spawn(fixtures: Fixtures, addr: SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).with_context(|| {
+            format!("Failed to bind mock CometBFT server to {addr}")
+        })?;
+        listener
+            .set_nonblocking(true)
+            .context("Failed to configure mock CometBFT listener")?;
+        let local_addr = listener
+            .local_addr()
+            .context("Failed to get mock CometBFT server address")?;
+
+        let max_height = fixtures.latest_height();
+        let earliest_height = Arc::new(AtomicU64::new(1));
+        let latest_height = Arc::new(watch::Sender::new(max_height));
+        let conversion_state = Arc::new(watch::Sender::new((
+            MaspEpoch::zero(),
+            Conversions::new(),
+        )));
+
+        let state = ServerState {
+            fixtures: Arc::new(fixtures),
+            earliest_height: earliest_height.clone(),
+            latest_height: latest_height.clone(),
+            conversion_state: conversion_state.clone(),
+        };
+        let app = Router::new()
+            .route("/", post(rpc))
+            .route("/websocket", get(websocket))
+            .with_state(state);
+
+        let server = axum::Server::from_tcp(listener)
+            .context("Failed to start mock CometBFT server")?
+            .serve(app.into_make_service());
+
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let graceful = server.with_graceful_shutdown(async {
+                _ = shutdown_rx.await;
+            });
+            if let Err(err) = graceful.await {
+                tracing::error!(%err, "Mock CometBFT server failed");
+            }
+        });
+
+        tracing::info!(%local_addr, max_height, "Mock CometBFT server started");
+
+        Ok(Self {
+            url: format!("http://{local_addr}"),
+            websocket_url: format!("ws://{local_addr}/websocket"),
+            earliest_height,
+            latest_height,
+            max_height,
+            conversion_state,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+
+    /// Return the URL at which the server is reachable.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Return the URL at which `NewBlock` events can be subscribed to.
+    pub fn websocket_url(&self) -> &str {
+        &self.websocket_url
+    }
+
+    /// Return the height of the last block visible to clients.
+    pub fn latest_height(&self) -> u64 {
+        *self.latest_height.borrow()
+    }
+
+    /// Only make the blocks up to `height` visible to clients, to
+    /// simulate a chain that is still producing blocks.
+    ///
+    /// Subscribers are sent a `NewBlock` event for every block made
+    /// visible.
+    pub fn set_latest_height(&self, height: u64) {
+        self.latest_height.send_replace(height.min(self.max_height));
+    }
+
+    /// Only make the blocks from `height` onwards visible to clients,
+    /// to simulate a node that has pruned older blocks.
+    pub fn set_earliest_height(&self, height: u64) {
+        self.earliest_height
+            .store(height.clamp(1, self.max_height), Ordering::SeqCst);
+    }
+
+    /// Serve the given MASP conversion state, as of `masp_epoch`. No
+    /// conversions are served at MASP epoch 0 initially.
+    pub fn set_conversion_state(
+        &self,
+        masp_epoch: MaspEpoch,
+        conversions: Conversions,
+    ) {
+        self.conversion_state
+            .send_replace((masp_epoch, conversions));
+    }
+
+    /// Stop the server, and wait for it to shut down.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            _ = task.await;
+        }
+    }
+}
+
+impl Drop for MockCometBft {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            _ = shutdown.send(());
+        }
+    }
+}
+
+async fn rpc(
+    State(state): State<ServerState>,
+    Json(request): Json<RpcRequest>,
+) -> Json<Value> {
+    tracing::debug!(method = request.method, params = %request.params, "RPC request");
+
+    let result = match request.method.as_str() {
+        "block" => serde_json::from_value::<block::Request>(request.params)
+            .map_err(invalid_params)
+            .and_then(|req| state.block(req.height))
+            .map(|block| json!(block.block)),
+        "block_results" => {
+            serde_json::from_value::<block_results::Request>(request.params)
+                .map_err(invalid_params)
+                .and_then(|req| state.block(req.height))
+                .map(|block| json!(block.block_results))
+        }
+        "abci_query" => {
+            serde_json::from_value::<abci_query::Request>(request.params)
+                .map_err(invalid_params)
+                .map(|req| state.abci_query(req))
+        }
+        "status" => Ok(state.status()),
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: "Method not found",
+            data: String::new(),
+        }),
+    };
+
+    Json(match result {
+        Ok(result) => json!({
+            "jsonrpc": "2.0",
+            "id": request.id,
+            "result": result,
+        }),
+        Err(RpcError {
+            code,
+            message,
+            data,
+        }) => json!({
+            "jsonrpc": "2.0",
+            "id": request.id,
+            "error": {
+                "code": code,
+                "message": message,
+                "data": data,
+            },
+        }),
+    })
+}
+
+async fn websocket(
+    State(state): State<ServerState>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(|socket| async move {
+        if let Err(err) = state.stream_new_blocks(socket).await {
+            tracing::debug!(%err, "WebSocket connection closed");
+        }
+    })
+}
+
+fn invalid_params(err: serde_json::Error) -> RpcError {
+    RpcError {
+        code: INVALID_PARAMS,
+        message: "Invalid params",
+        data: err.to_string(),
+    }
+}
+
+impl ServerState {
+    fn earliest_height(&self) -> u64 {
+        self.earliest_height.load(Ordering::SeqCst)
+    }
+
+    fn latest_height(&self) -> u64 {
+        *self.latest_height.borrow()
+    }
+
+    /// Acknowledge the first subscription request received on `socket`,
+    /// then send a `NewBlock` event for every block made visible from
+    /// there on.
+    async fn stream_new_blocks(
+        &self,
+        mut socket: WebSocket,
+    ) -> Result<(), axum::Error> {
+        let request = loop {
+            match socket.recv().await.transpose()? {
+                Some(Message::Text(text)) => {
+                    match serde_json::from_str::<RpcRequest>(&text) {
+                        Ok(request) if request.method == "subscribe" => {
+                            break request;
+                        }
+                        _ => continue,
+                    }
+                }
+                Some(Message::Close(_)) | None => return Ok(()),
+                Some(_) => continue,
+            }
+        };
+
+        let mut latest_height = self.latest_height.subscribe();
+        let mut last_sent = *latest_height.borrow_and_update();
+
+        socket
+            .send(Message::Text(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": request.id,
+                    "result": {},
+                })
+                .to_string(),
+            ))
+            .await?;
+
+        loop {
+            tokio::select! {
+                changed = latest_height.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                }
+                message = socket.recv() => {
+                    match message.transpose()? {
+                        Some(Message::Close(_)) | None => return Ok(()),
+                        Some(_) => continue,
+                    }
+                }
+            }
+
+            let new_height = *latest_height.borrow_and_update();
+            for height in last_sent + 1..=new_height {
+                let Some(block) = self.fixtures.blocks.get(&height) else {
+                    continue;
+                };
+                let event = json!({
+                    "jsonrpc": "2.0",
+                    "id": request.id,
+                    "result": {
+                        "query": "tm.event='NewBlock'",
+                        "data": {
+                            "type": "tendermint/event/NewBlock",
+                            "value": {
+                                "block": block.block.block,
+                            },
+                        },
+                    },
+                });
+                socket.send(Message::Text(event.to_string())).await?;
+            }
+            last_sent = last_sent.max(new_height);
+        }
+    }
+
+    fn block(
+        &self,
+        height: Option<Height>,
+    ) -> Result<&crate::fixtures::FixtureBlock, RpcError> {
+        let latest_height = self.latest_height();
+        let height = height.map_or(latest_height, |height| height.value());
+
+        if height > latest_height {
+            return Err(RpcError {
+                code: INTERNAL_ERROR,
+                message: "Internal error",
+                data: format!(
+                    "height {height} must be less than or equal to the \
+                     current blockchain height {latest_height}"
+                ),
+            });
+        }
+
+        let earliest_height = self.earliest_height();
+
+        if height < earliest_height {
+            return Err(RpcError {
+                code: INTERNAL_ERROR,
+                message: "Internal error",
+                data: format!(
+                    "height {height} is not available, lowest height is \
+                     {earliest_height}"
+                ),
+            });
+        }
+
+        self.fixtures.blocks.get(&height).ok_or_else(|| RpcError {
+            code: INTERNAL_ERROR,
+            message: "Internal error",
+            data: format!("height {height} is not available"),
+        })
+    }
+
+    fn abci_query(&self, request: abci_query::Request) -> Value {
+        let path = request.path.unwrap_or_default();
+
+        let value = match path.as_str() {
+            MASP_EPOCH_PATH => {
+                Some(self.conversion_state.borrow().0.serialize_to_vec())
+            }
+            CONVERSIONS_PATH => {
+                Some(self.conversion_state.borrow().1.serialize_to_vec())
+            }
+            NATIVE_TOKEN_PATH => {
+                Some(fixtures::native_token().serialize_to_vec())
+            }
+            _ => path
+                .strip_prefix(HAS_KEY_PATH_PREFIX)
+                .map(|key| {
+                    self.fixtures.storage_keys.contains(key).serialize_to_vec()
+                })
+                .or_else(|| {
+                    path.strip_prefix(DENOMINATION_PATH_PREFIX).map(|token| {
+                        self.denomination(token).serialize_to_vec()
+                    })
+                })
+                // NB: the fixture chain has no storage values to iterate
+                // over, e.g. no IBC tokens
+                .or_else(|| {
+                    path.strip_prefix(PREFIX_PATH_PREFIX)
+                        .map(|_| Vec::<PrefixValue>::new().serialize_to_vec())
+                }),
+        };
+
+        let response = match value {
+            Some(value) => abci_query::AbciQuery {
+                value,
+                height: Height::try_from(self.latest_height())
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+            None => abci_query::AbciQuery {
+                code: 1u32.into(),
+                info: format!("Unsupported query path {path}"),
+                ..Default::default()
+            },
+        };
+
+        json!(abci_query::Response { response })
+    }
+
+    /// Return the denomination of the native token, or of a token of the
+    /// conversion state.
+    fn denomination(&self, token: &str) -> Option<Denomination> {
+        let token = Address::decode(token).ok()?;
+
+        if token == fixtures::native_token() {
+            return Some(fixtures::NATIVE_TOKEN_DENOM);
+        }
+
+        self.conversion_state
+            .borrow()
+            .1
+            .values()
+            .find(|(conversion_token, ..)| *conversion_token == token)
+            .map(|(_, denom, ..)| *denom)
+    }
+
+    fn status(&self) -> Value {
+        let latest = self.block(None).ok();
+        let earliest = Height::try_from(self.earliest_height())
+            .ok()
+            .and_then(|height| self.block(Some(height)).ok());
+
+        let block_info = |block: Option<&crate::fixtures::FixtureBlock>| {
+            block.map_or_else(
+                || {
+                    (
+                        String::new(),
+                        "0".to_string(),
+                        json!("1970-01-01T00:00:00Z"),
+                    )
+                },
+                |block| {
+                    (
+                        block.block.block_id.hash.to_string(),
+                        block.block.block.header.height.to_string(),
+                        json!(block.block.block.header.time),
+                    )
+                },
+            )
+        };
+        let (latest_hash, latest_height, latest_time) = block_info(latest);
+        let (earliest_hash, earliest_height, earliest_time) =
+            block_info(earliest);
+
+        json!({
+            "node_info": {
+                "protocol_version": {
+                    "p2p": "8",
+                    "block": "11",
+                    "app": "0",
+                },
+                "id": "0000000000000000000000000000000000000000",
+                "listen_addr": "tcp://0.0.0.0:26656",
+                "network": self.fixtures.chain_id,
+                "version": "0.37.15",
+                "channels": "40202122233038606100",
+                "moniker": "mock-cometbft",
+                "other": {
+                    "tx_index": "on",
+                    "rpc_address": "tcp://0.0.0.0:26657",
+                },
+            },
+            "sync_info": {
+                "latest_block_hash": latest_hash,
+                "latest_app_hash": "",
+                "latest_block_height": latest_height,
+                "latest_block_time": latest_time,
+                "earliest_block_hash": earliest_hash,
+                "earliest_app_hash": "",
+                "earliest_block_height": earliest_height,
+                "earliest_block_time": earliest_time,
+                "catching_up": false,
+            },
+            "validator_info": {
+                "address": "0000000000000000000000000000000000000000",
+                "pub_key": {
+                    "type": "tendermint/PubKeyEd25519",
+                    "value": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                },
+                "voting_power": "0",
+            },
+        })
+    }
+}
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+-- NB: witnesses pruned from delta heights cannot be restored,
+-- the index must be rebuilt after reverting this migration
+DROP TABLE witness_delta;
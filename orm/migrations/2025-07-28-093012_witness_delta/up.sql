@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE witness_delta (
+  block_height INT PRIMARY KEY,
+  -- NB: borsh serialized leaves appended to the commitment tree at
+  -- this height. Rows inherited from the legacy storage scheme are
+  -- backfilled by the crawler, and start out as NULL.
+  leaves bytea,
+  -- NB: checkpoints hold the full witness map in the `witness` table,
+  -- while other heights only hold the witnesses of new notes
+  is_checkpoint BOOLEAN NOT NULL
+);
+
+CREATE INDEX witness_delta_checkpoint ON witness_delta (block_height) WHERE is_checkpoint;
+
+-- NB: every height stored so far contains a full witness map
+INSERT INTO witness_delta (block_height, leaves, is_checkpoint)
+SELECT DISTINCT block_height, NULL::bytea, true FROM witness;
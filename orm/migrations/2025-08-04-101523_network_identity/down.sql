@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE chain_state
+  DROP COLUMN chain_id,
+  DROP COLUMN first_block_height,
+  DROP COLUMN first_block_hash;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- NB: the network the index was built from, recorded from the first
+-- block committed after this migration, and checked against CometBFT
+-- before indexing any further blocks
+ALTER TABLE chain_state
+  ADD COLUMN chain_id VARCHAR,
+  ADD COLUMN first_block_height INT,
+  ADD COLUMN first_block_hash VARCHAR;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX tx_hash_index;
+
+ALTER TABLE tx DROP COLUMN tx_hash;
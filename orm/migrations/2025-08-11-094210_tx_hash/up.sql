@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+-- NB: the hash of the Namada tx each MASP tx was extracted from. Left
+-- empty for the txs indexed before this migration
+ALTER TABLE tx ADD COLUMN tx_hash VARCHAR;
+
+CREATE INDEX tx_hash_index ON tx USING HASH (tx_hash);
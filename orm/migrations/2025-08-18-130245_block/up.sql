@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+-- NB: headers of the blocks with MASP txs, and of the last committed
+-- block, recorded from the first block committed after this migration
+CREATE TABLE block (
+  block_height INT PRIMARY KEY,
+  hash VARCHAR NOT NULL,
+  timestamp TIMESTAMP NOT NULL,
+  proposer_address VARCHAR NOT NULL,
+  app_hash VARCHAR NOT NULL
+);
+
+CREATE INDEX block_timestamp_asc ON block (timestamp ASC);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+-- NB: conversion state of the MASP at the start of each MASP epoch,
+-- recorded from the first epoch observed after this migration. Epochs
+-- without any conversions are not recorded. The conversion_bytes
+-- column holds the borsh encoded `I128Sum` of the asset.
+CREATE TABLE conversion (
+  masp_epoch INT NOT NULL,
+  asset_type VARCHAR NOT NULL,
+  token VARCHAR NOT NULL,
+  denom SMALLINT NOT NULL,
+  digit_pos SMALLINT NOT NULL,
+  asset_epoch INT NOT NULL,
+  conversion_bytes BYTEA NOT NULL,
+  PRIMARY KEY (masp_epoch, asset_type)
+);
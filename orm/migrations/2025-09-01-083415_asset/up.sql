@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+-- NB: pre-images of the MASP asset types of the tokens found in the
+-- conversion state, i.e. the tokens with MASP rewards, and of the
+-- undated asset types of the committed txs, resolved against the native
+-- and IBC tokens of the chain. Undated asset types have no epoch.
+CREATE TABLE asset (
+  asset_type VARCHAR PRIMARY KEY,
+  token VARCHAR NOT NULL,
+  denom SMALLINT NOT NULL,
+  digit_pos SMALLINT NOT NULL,
+  epoch INT
+);
+
+CREATE INDEX asset_token ON asset USING HASH (token);
+
+-- NB: the undated asset types of the tokens in the conversion state are
+-- registered along with the conversions of the next MASP epoch
+INSERT INTO asset (asset_type, token, denom, digit_pos, epoch)
+SELECT DISTINCT ON (asset_type) asset_type, token, denom, digit_pos, asset_epoch
+FROM conversion
+ORDER BY asset_type;
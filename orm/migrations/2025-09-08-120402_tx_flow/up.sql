@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+-- NB: value moved in and out of the shielded pool by each MASP tx, per
+-- asset type. Flows of the txs committed before this migration can be
+-- recovered with the `rebuild` command.
+CREATE TABLE tx_flow (
+  block_height INT NOT NULL,
+  block_index INT NOT NULL,
+  masp_tx_index INT NOT NULL,
+  is_masp_fee_payment BOOLEAN NOT NULL,
+  asset_type VARCHAR NOT NULL,
+  transparent_in NUMERIC NOT NULL,
+  transparent_out NUMERIC NOT NULL,
+  value_balance NUMERIC NOT NULL,
+  PRIMARY KEY (block_height, block_index, masp_tx_index, is_masp_fee_payment, asset_type)
+);
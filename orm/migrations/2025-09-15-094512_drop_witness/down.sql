@@ -0,0 +1,21 @@
+-- This file should undo anything in `up.sql`
+
+-- NB: dropped witnesses cannot be restored, the index must be rebuilt
+-- after reverting this migration
+DROP INDEX witness_delta_legacy;
+
+ALTER TABLE witness_delta ADD COLUMN is_checkpoint BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX witness_delta_checkpoint ON witness_delta (block_height) WHERE is_checkpoint;
+
+CREATE TABLE witness (
+  id SERIAL PRIMARY KEY,
+  witness_bytes bytea NOT NULL,
+  witness_idx INT NOT NULL,
+  block_height INT NOT NULL
+);
+
+CREATE INDEX witness_block_height_asc ON witness (block_height ASC);
+CREATE INDEX witness_block_height_desc ON witness (block_height DESC);
+
+CREATE INDEX witness_block_height ON witness USING HASH (block_height);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+-- NB: witnesses are computed on demand from the leaves of the witness
+-- map deltas, so neither stored witnesses nor checkpoints are needed
+DROP TABLE witness;
+
+ALTER TABLE witness_delta DROP COLUMN is_checkpoint;
+
+-- NB: legacy deltas are backfilled by the crawler on startup
+CREATE INDEX witness_delta_legacy ON witness_delta (block_height) WHERE leaves IS NULL;
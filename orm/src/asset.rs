@@ -0,0 +1,26 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::asset;
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = asset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AssetDb {
+    pub asset_type: String,
+    pub token: String,
+    pub denom: i16,
+    pub digit_pos: i16,
+    pub epoch: Option<i32>,
+}
+
+#[derive(Serialize, Insertable, Clone)]
+#[diesel(table_name = asset)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AssetInsertDb {
+    pub asset_type: String,
+    pub token: String,
+    pub denom: i16,
+    pub digit_pos: i16,
+    pub epoch: Option<i32>,
+}
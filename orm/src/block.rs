@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::block;
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = block)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BlockDb {
+    pub block_height: i32,
+    pub hash: String,
+    pub timestamp: NaiveDateTime,
+    pub proposer_address: String,
+    pub app_hash: String,
+}
+
+#[derive(Serialize, Insertable, Clone)]
+#[diesel(table_name = block)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BlockInsertDb {
+    pub block_height: i32,
+    pub hash: String,
+    pub timestamp: NaiveDateTime,
+    pub proposer_address: String,
+    pub app_hash: String,
+}
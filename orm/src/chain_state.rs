@@ -16,4 +16,16 @@ pub struct ChainStateDb {
 pub struct ChainStateteInsertDb {
     pub id: i32,
     pub block_height: i32,
+    pub chain_id: Option<String>,
+    pub first_block_height: Option<i32>,
+    pub first_block_hash: Option<String>,
+}
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = chain_state)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NetworkIdentityDb {
+    pub chain_id: Option<String>,
+    pub first_block_height: Option<i32>,
+    pub first_block_hash: Option<String>,
 }
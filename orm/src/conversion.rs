@@ -0,0 +1,30 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::schema::conversion;
+
+#[derive(Serialize, Queryable, Selectable, Clone)]
+#[diesel(table_name = conversion)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ConversionDb {
+    pub masp_epoch: i32,
+    pub asset_type: String,
+    pub token: String,
+    pub denom: i16,
+    pub digit_pos: i16,
+    pub asset_epoch: i32,
+    pub conversion_bytes: Vec<u8>,
+}
+
+#[derive(Serialize, Insertable, Clone)]
+#[diesel(table_name = conversion)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ConversionInsertDb {
+    pub masp_epoch: i32,
+    pub asset_type: String,
+    pub token: String,
+    pub denom: i16,
+    pub digit_pos: i16,
+    pub asset_epoch: i32,
+    pub conversion_bytes: Vec<u8>,
+}
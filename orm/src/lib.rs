@@ -1,7 +1,13 @@
+pub mod asset;
+pub mod block;
 pub mod block_index;
 pub mod chain_state;
+pub mod conversion;
 pub mod notes_index;
+pub mod notification;
+pub mod numeric;
 pub mod schema;
 pub mod tree;
 pub mod tx;
-pub mod witness;
+pub mod tx_flow;
+pub mod witness_delta;
@@ -0,0 +1,40 @@
+//! Notifications of the data committed to the db, sent over a Postgres
+//! `NOTIFY` channel.
+
+use diesel::pg::PgConnection;
+use diesel::sql_types::Text;
+use diesel::{QueryResult, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+/// Postgres channel of the notifications.
+pub const CHANNEL: &str = "masp_indexer";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    /// The crawler committed a new block.
+    Height {
+        block_height: u64,
+        num_masp_txs: u64,
+        commitment_tree_size: u64,
+    },
+    /// A new block index was stored.
+    BlockIndex { block_height: u64 },
+}
+
+impl Notification {
+    /// Notify the listeners of [`CHANNEL`], once the current transaction
+    /// commits.
+    pub fn send(&self, conn: &mut PgConnection) -> QueryResult<()> {
+        let payload = serde_json::to_string(self).map_err(|err| {
+            diesel::result::Error::SerializationError(Box::new(err))
+        })?;
+
+        diesel::sql_query("SELECT pg_notify($1, $2)")
+            .bind::<Text, _>(CHANNEL)
+            .bind::<Text, _>(payload)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
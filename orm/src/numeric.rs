@@ -0,0 +1,61 @@
+//! Conversions between integers and the `NUMERIC` values of the db.
+
+use diesel::pg::data_types::PgNumeric;
+
+/// Base of the digits of a [`PgNumeric`].
+const NBASE: i128 = 10_000;
+
+/// Encode an integer as a `NUMERIC`.
+pub fn numeric_from_i128(value: i128) -> PgNumeric {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+
+    while magnitude > 0 {
+        digits.push((magnitude % NBASE as u128) as i16);
+        magnitude /= NBASE as u128;
+    }
+    digits.reverse();
+
+    let weight = digits.len().saturating_sub(1) as i16;
+
+    if value < 0 {
+        PgNumeric::Negative {
+            weight,
+            scale: 0,
+            digits,
+        }
+    } else {
+        PgNumeric::Positive {
+            weight,
+            scale: 0,
+            digits,
+        }
+    }
+}
+
+/// Decode a `NUMERIC` holding an integer. Returns `None` if it is not
+/// an integer, or if it does not fit in an `i128`.
+pub fn numeric_to_i128(numeric: &PgNumeric) -> Option<i128> {
+    let (is_negative, weight, digits) = match numeric {
+        PgNumeric::Positive { weight, digits, .. } => (false, *weight, digits),
+        PgNumeric::Negative { weight, digits, .. } => (true, *weight, digits),
+        PgNumeric::NaN => return None,
+    };
+
+    let mut value = 0i128;
+    for (index, &digit) in digits.iter().enumerate() {
+        let Ok(exponent) = u32::try_from(i32::from(weight) - index as i32)
+        else {
+            // NB: fractional digits
+            if digit != 0 {
+                return None;
+            }
+            continue;
+        };
+        value = value.checked_add(
+            i128::from(digit).checked_mul(NBASE.checked_pow(exponent)?)?,
+        )?;
+    }
+
+    Some(if is_negative { -value } else { value })
+}
@@ -1,5 +1,25 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    asset (asset_type) {
+        asset_type -> Varchar,
+        token -> Varchar,
+        denom -> Int2,
+        digit_pos -> Int2,
+        epoch -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    block (block_height) {
+        block_height -> Int4,
+        hash -> Varchar,
+        timestamp -> Timestamp,
+        proposer_address -> Varchar,
+        app_hash -> Varchar,
+    }
+}
+
 diesel::table! {
     block_index (id) {
         id -> Int4,
@@ -12,6 +32,9 @@ diesel::table! {
     chain_state (id) {
         id -> Int4,
         block_height -> Int4,
+        chain_id -> Nullable<Varchar>,
+        first_block_height -> Nullable<Int4>,
+        first_block_hash -> Nullable<Varchar>,
     }
 }
 
@@ -23,6 +46,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    conversion (masp_epoch, asset_type) {
+        masp_epoch -> Int4,
+        asset_type -> Varchar,
+        token -> Varchar,
+        denom -> Int2,
+        digit_pos -> Int2,
+        asset_epoch -> Int4,
+        conversion_bytes -> Bytea,
+    }
+}
+
 diesel::table! {
     notes_index (note_position) {
         note_position -> Int4,
@@ -41,23 +76,39 @@ diesel::table! {
         block_height -> Int4,
         masp_tx_index -> Int4,
         is_masp_fee_payment -> Bool,
+        tx_hash -> Nullable<Varchar>,
     }
 }
 
 diesel::table! {
-    witness (id) {
-        id -> Int4,
-        witness_bytes -> Bytea,
-        witness_idx -> Int4,
+    tx_flow (block_height, block_index, masp_tx_index, is_masp_fee_payment, asset_type) {
+        block_height -> Int4,
+        block_index -> Int4,
+        masp_tx_index -> Int4,
+        is_masp_fee_payment -> Bool,
+        asset_type -> Varchar,
+        transparent_in -> Numeric,
+        transparent_out -> Numeric,
+        value_balance -> Numeric,
+    }
+}
+
+diesel::table! {
+    witness_delta (block_height) {
         block_height -> Int4,
+        leaves -> Nullable<Bytea>,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    asset,
+    block,
     block_index,
     chain_state,
     commitment_tree,
+    conversion,
     notes_index,
     tx,
-    witness,
+    tx_flow,
+    witness_delta,
 );
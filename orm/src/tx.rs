@@ -13,6 +13,7 @@ pub struct TxDb {
     pub block_height: i32,
     pub masp_tx_index: i32,
     pub is_masp_fee_payment: bool,
+    pub tx_hash: Option<String>,
 }
 
 #[derive(Serialize, Insertable, Clone)]
@@ -24,4 +25,5 @@ pub struct TxInsertDb {
     pub block_height: i32,
     pub masp_tx_index: i32,
     pub is_masp_fee_payment: bool,
+    pub tx_hash: Option<String>,
 }
@@ -0,0 +1,32 @@
+use diesel::pg::data_types::PgNumeric;
+use diesel::{Insertable, Queryable, Selectable};
+
+use crate::schema::tx_flow;
+
+#[derive(Queryable, Selectable, Clone)]
+#[diesel(table_name = tx_flow)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TxFlowDb {
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+    pub is_masp_fee_payment: bool,
+    pub asset_type: String,
+    pub transparent_in: PgNumeric,
+    pub transparent_out: PgNumeric,
+    pub value_balance: PgNumeric,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = tx_flow)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TxFlowInsertDb {
+    pub block_height: i32,
+    pub block_index: i32,
+    pub masp_tx_index: i32,
+    pub is_masp_fee_payment: bool,
+    pub asset_type: String,
+    pub transparent_in: PgNumeric,
+    pub transparent_out: PgNumeric,
+    pub value_balance: PgNumeric,
+}
@@ -1,23 +1,20 @@
 use diesel::{Insertable, Queryable, Selectable};
 use serde::Serialize;
 
-use crate::schema::witness;
+use crate::schema::witness_delta;
 
 #[derive(Serialize, Queryable, Selectable, Clone)]
-#[diesel(table_name = witness)]
+#[diesel(table_name = witness_delta)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
-pub struct WitnessDb {
-    pub id: i32,
-    pub witness_idx: i32,
+pub struct WitnessDeltaDb {
     pub block_height: i32,
-    pub witness_bytes: Vec<u8>,
+    pub leaves: Option<Vec<u8>>,
 }
 
 #[derive(Serialize, Insertable, Clone)]
-#[diesel(table_name = witness)]
+#[diesel(table_name = witness_delta)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
-pub struct WitnessInsertDb {
-    pub witness_bytes: Vec<u8>,
-    pub witness_idx: i32,
+pub struct WitnessDeltaInsertDb {
     pub block_height: i32,
+    pub leaves: Option<Vec<u8>>,
 }
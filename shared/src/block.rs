@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::fmt::Display;
 
+use namada_core::borsh;
 use namada_sdk::state::TxIndex as NamadaTxIndex;
 use namada_tx::Tx as NamadaTx;
 use namada_tx::event::MaspEvent;
@@ -8,7 +9,6 @@ use tendermint_rpc::endpoint::{block, block_results};
 
 use crate::block_results::locate_masp_txs;
 use crate::header::BlockHeader;
-use crate::height::BlockHeight;
 use crate::id::Id;
 use crate::indexed_tx::MaspIndexedTx;
 use crate::transaction::Transaction;
@@ -21,12 +21,6 @@ pub struct Block {
 }
 
 impl Block {
-    pub fn empty_block(block_height: BlockHeight) -> Self {
-        let mut block = Self::default();
-        block.header.height = block_height;
-        block
-    }
-
     pub fn new(
         raw_block: block::Response,
         raw_results: block_results::Response,
@@ -75,6 +69,19 @@ impl Block {
 
         Ok(block)
     }
+
+    /// Estimate the memory taken up by this block, from the encoded
+    /// size of its MASP transactions.
+    pub fn size_in_bytes(&self) -> usize {
+        self.transactions
+            .values()
+            .map(|tx| {
+                size_of::<(MaspIndexedTx, Transaction)>()
+                    + borsh::object_length(&tx.masp_tx).unwrap_or_default()
+            })
+            .sum::<usize>()
+            + size_of::<Self>()
+    }
 }
 
 impl Display for Block {
@@ -0,0 +1,303 @@
+//! On-disk archive of raw block data.
+//!
+//! Archives start with the [`MAGIC`] bytes and the format version,
+//! followed by one record per block. Each record is made up of the
+//! block height and the length of its payload, both little endian,
+//! and the payload itself: the gzip compressed JSON encoding of a
+//! [`RawBlock`]. Records are appended in the order blocks are fetched,
+//! which need not be the order of their heights.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::block_source::RawBlock;
+use crate::height::BlockHeight;
+
+/// Magic bytes at the start of every block archive.
+const MAGIC: &[u8; 8] = b"MASPBLKS";
+
+/// Version of the block archive format.
+const FORMAT_VERSION: u32 = 1;
+
+/// Length of the archive header.
+const HEADER_LEN: u64 = (MAGIC.len() + size_of::<u32>()) as u64;
+
+/// Length of the header of each record.
+const RECORD_HEADER_LEN: u64 = (size_of::<u64>() + size_of::<u32>()) as u64;
+
+/// Location of a record's payload within an archive.
+#[derive(Debug, Clone, Copy)]
+struct RecordLocation {
+    offset: u64,
+    len: u32,
+}
+
+/// Read-only handle to a block archive.
+#[derive(Debug)]
+pub struct BlockArchive {
+    path: PathBuf,
+    file: Mutex<File>,
+    index: BTreeMap<BlockHeight, RecordLocation>,
+}
+
+impl BlockArchive {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = File::open(path).with_context(|| {
+            format!("Failed to open block archive {}", path.display())
+        })?;
+        let (index, _) = scan(&mut file, path)?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            file: Mutex::new(file),
+            index,
+        })
+    }
+
+    /// Return the height of the last block in the archive.
+    pub fn last_height(&self) -> Option<BlockHeight> {
+        self.index.last_key_value().map(|(height, _)| *height)
+    }
+
+    /// Return the first height starting at `from` whose block is
+    /// missing from the archive, up to the last block in the archive.
+    pub fn first_missing_height(
+        &self,
+        from: BlockHeight,
+    ) -> Option<BlockHeight> {
+        let last_height = self.last_height()?;
+
+        (from.0..=last_height.0)
+            .map(BlockHeight)
+            .find(|height| !self.index.contains_key(height))
+    }
+
+    /// Read the raw block data at the given height.
+    pub fn read(&self, height: BlockHeight) -> anyhow::Result<RawBlock> {
+        let RecordLocation { offset, len } =
+            *self.index.get(&height).with_context(|| {
+                format!(
+                    "Block at height {height} is not in block archive {}",
+                    self.path.display()
+                )
+            })?;
+
+        let mut payload = vec![0u8; len as usize];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_exact(&mut payload))
+                .with_context(|| {
+                    format!(
+                        "Failed to read block at height {height} from block \
+                         archive {}",
+                        self.path.display()
+                    )
+                })?;
+        }
+
+        serde_json::from_reader(GzDecoder::new(payload.as_slice()))
+            .with_context(|| {
+                format!(
+                    "Failed to decode block at height {height} from block \
+                     archive {}",
+                    self.path.display()
+                )
+            })
+    }
+}
+
+/// Append-only handle to a block archive.
+#[derive(Debug)]
+pub struct BlockArchiveWriter {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl BlockArchiveWriter {
+    /// Open the archive at `path` for appending, creating it if it
+    /// does not exist yet.
+    ///
+    /// A record left incomplete by a previous writer (e.g. due to a
+    /// crash) is discarded.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| {
+                format!("Failed to open block archive {}", path.display())
+            })?;
+
+        let file_len = file
+            .metadata()
+            .with_context(|| {
+                format!("Failed to read block archive {}", path.display())
+            })?
+            .len();
+
+        if file_len == 0 {
+            let mut header = Vec::with_capacity(HEADER_LEN as usize);
+            header.extend_from_slice(MAGIC);
+            header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+            file.write_all(&header).with_context(|| {
+                format!("Failed to write block archive {}", path.display())
+            })?;
+        } else {
+            let (_, valid_len) = scan(&mut file, path)?;
+
+            if valid_len < file_len {
+                tracing::warn!(
+                    path = %path.display(),
+                    discarded_bytes = file_len - valid_len,
+                    "Discarding incomplete record at the end of the block \
+                     archive"
+                );
+                file.set_len(valid_len).with_context(|| {
+                    format!(
+                        "Failed to truncate block archive {}",
+                        path.display()
+                    )
+                })?;
+            }
+
+            file.seek(SeekFrom::End(0)).with_context(|| {
+                format!("Failed to seek block archive {}", path.display())
+            })?;
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append the raw block data at the given height to the archive.
+    pub fn append(
+        &self,
+        height: BlockHeight,
+        raw_block: &RawBlock,
+    ) -> anyhow::Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, raw_block)
+            .context("Failed to encode raw block data")?;
+        let payload = encoder
+            .finish()
+            .context("Failed to compress raw block data")?;
+        let payload_len = u32::try_from(payload.len())
+            .context("Raw block data is too large to be archived")?;
+
+        let mut record =
+            Vec::with_capacity(RECORD_HEADER_LEN as usize + payload.len());
+        record.extend_from_slice(&height.0.to_le_bytes());
+        record.extend_from_slice(&payload_len.to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&record)
+            .with_context(|| {
+                format!(
+                    "Failed to append block at height {height} to block \
+                     archive {}",
+                    self.path.display()
+                )
+            })
+    }
+}
+
+/// Validate the header of an archive, and index its records.
+///
+/// Returns the index, along with the length of the archive up to the
+/// end of its last complete record.
+fn scan(
+    file: &mut File,
+    path: &Path,
+) -> anyhow::Result<(BTreeMap<BlockHeight, RecordLocation>, u64)> {
+    let file_len = file
+        .metadata()
+        .with_context(|| {
+            format!("Failed to read block archive {}", path.display())
+        })?
+        .len();
+
+    file.seek(SeekFrom::Start(0)).with_context(|| {
+        format!("Failed to seek block archive {}", path.display())
+    })?;
+    let mut reader = BufReader::new(&mut *file);
+
+    let mut header = [0u8; HEADER_LEN as usize];
+    reader.read_exact(&mut header).with_context(|| {
+        format!("Failed to read block archive header {}", path.display())
+    })?;
+    let (magic, version) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        anyhow::bail!("{} is not a block archive", path.display());
+    }
+    let version = u32::from_le_bytes(
+        version.try_into().expect("Version should be 4 bytes long"),
+    );
+    if version != FORMAT_VERSION {
+        anyhow::bail!(
+            "Unsupported block archive format version {version}, expected \
+             {FORMAT_VERSION}"
+        );
+    }
+
+    let mut index = BTreeMap::new();
+    let mut offset = HEADER_LEN;
+
+    loop {
+        let mut record_header = [0u8; RECORD_HEADER_LEN as usize];
+        match reader.read_exact(&mut record_header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read block archive {}", path.display())
+                });
+            }
+        }
+
+        let (height, len) = record_header.split_at(size_of::<u64>());
+        let height = u64::from_le_bytes(
+            height.try_into().expect("Height should be 8 bytes long"),
+        );
+        let len = u32::from_le_bytes(
+            len.try_into().expect("Length should be 4 bytes long"),
+        );
+
+        let payload_offset = offset + RECORD_HEADER_LEN;
+        let record_end = payload_offset + u64::from(len);
+        if record_end > file_len {
+            break;
+        }
+
+        reader.seek_relative(i64::from(len)).with_context(|| {
+            format!("Failed to seek block archive {}", path.display())
+        })?;
+
+        index.insert(
+            BlockHeight(height),
+            RecordLocation {
+                offset: payload_offset,
+                len,
+            },
+        );
+        offset = record_end;
+    }
+
+    Ok((index, offset))
+}
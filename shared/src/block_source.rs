@@ -0,0 +1,171 @@
+//! Sources of raw block data.
+//!
+//! Blocks are normally fetched from a CometBFT node, but they can also be
+//! recorded into a [`BlockArchive`] while crawling, and later replayed from
+//! it, e.g. to rebuild the index without querying the node again.
+
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::Client as _;
+use tendermint_rpc::endpoint::{block, block_results};
+
+use crate::block_archive::{BlockArchive, BlockArchiveWriter};
+use crate::client::Client;
+use crate::height::BlockHeight;
+
+/// Raw block data, as returned by CometBFT.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawBlock {
+    pub block: block::Response,
+    pub block_results: block_results::Response,
+}
+
+/// Source of raw block data.
+pub trait BlockSource: Clone + Send + Sync + 'static {
+    /// Return the height of the last block available from this source.
+    fn latest_height(
+        &self,
+    ) -> impl Future<Output = anyhow::Result<BlockHeight>> + Send;
+
+    /// Return the raw block data at the given height.
+    fn raw_block(
+        &self,
+        height: BlockHeight,
+    ) -> impl Future<Output = anyhow::Result<RawBlock>> + Send;
+
+    /// Return the height of the last block this source will ever
+    /// serve, if it is finite.
+    fn final_height(&self) -> Option<BlockHeight> {
+        None
+    }
+}
+
+impl BlockSource for Client {
+    async fn latest_height(&self) -> anyhow::Result<BlockHeight> {
+        let block = self
+            .latest_block()
+            .await
+            .context("Failed to query Namada's last committed block")?;
+
+        Ok(BlockHeight(block.block.header.height.value()))
+    }
+
+    async fn raw_block(&self, height: BlockHeight) -> anyhow::Result<RawBlock> {
+        let (block, block_results) = futures::try_join!(
+            async {
+                self.block(height)
+                    .await
+                    .context("Failed to query CometBFT's last committed height")
+            },
+            async {
+                self.block_results(height)
+                    .await
+                    .context("Failed to query CometBFT's block results")
+            },
+        )?;
+
+        Ok(RawBlock {
+            block,
+            block_results,
+        })
+    }
+}
+
+/// Block source that records every block fetched from another source
+/// into a [`BlockArchive`].
+#[derive(Clone)]
+pub struct RecordingBlockSource<S> {
+    inner: S,
+    archive: Arc<BlockArchiveWriter>,
+}
+
+impl<S> RecordingBlockSource<S> {
+    /// Record the blocks fetched from `inner` into the archive at
+    /// `path`, which is created if it does not exist yet.
+    pub fn new(inner: S, path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            inner,
+            archive: Arc::new(BlockArchiveWriter::open(path)?),
+        })
+    }
+}
+
+impl<S: BlockSource> BlockSource for RecordingBlockSource<S> {
+    async fn latest_height(&self) -> anyhow::Result<BlockHeight> {
+        self.inner.latest_height().await
+    }
+
+    async fn raw_block(&self, height: BlockHeight) -> anyhow::Result<RawBlock> {
+        let raw_block = self.inner.raw_block(height).await?;
+        let archive = Arc::clone(&self.archive);
+
+        tokio::task::spawn_blocking(move || {
+            archive.append(height, &raw_block)?;
+            anyhow::Ok(raw_block)
+        })
+        .await
+        .context("Failed to join Tokio task")?
+    }
+
+    fn final_height(&self) -> Option<BlockHeight> {
+        self.inner.final_height()
+    }
+}
+
+/// Block source that serves the blocks stored in a [`BlockArchive`].
+#[derive(Clone)]
+pub struct ReplayBlockSource {
+    archive: Arc<BlockArchive>,
+}
+
+impl ReplayBlockSource {
+    /// Serve the blocks in the archive at `path`, starting from the
+    /// block after `last_height`.
+    ///
+    /// Fails if the archive does not contain every block from there
+    /// on, up to its last block.
+    pub fn open(
+        path: &Path,
+        last_height: Option<BlockHeight>,
+    ) -> anyhow::Result<Self> {
+        let archive = BlockArchive::open(path)?;
+
+        let first_height = last_height
+            .map_or(Some(BlockHeight(1)), |height| height.next())
+            .context("Block height overflow")?;
+        if let Some(missing) = archive.first_missing_height(first_height) {
+            anyhow::bail!(
+                "Block archive {} is missing the block at height {missing}",
+                path.display()
+            );
+        }
+
+        Ok(Self {
+            archive: Arc::new(archive),
+        })
+    }
+}
+
+impl BlockSource for ReplayBlockSource {
+    async fn latest_height(&self) -> anyhow::Result<BlockHeight> {
+        self.archive
+            .last_height()
+            .context("The block archive is empty")
+    }
+
+    async fn raw_block(&self, height: BlockHeight) -> anyhow::Result<RawBlock> {
+        let archive = Arc::clone(&self.archive);
+
+        tokio::task::spawn_blocking(move || archive.read(height))
+            .await
+            .context("Failed to join Tokio task")?
+    }
+
+    fn final_height(&self) -> Option<BlockHeight> {
+        Some(self.archive.last_height().unwrap_or_default())
+    }
+}
@@ -0,0 +1,445 @@
+//! Incremental merkle tree of MASP note commitments.
+//!
+//! Rather than keeping an [`IncrementalWitness`] per note, and updating
+//! all of them whenever a new leaf is appended, the [`BridgeTree`] keeps
+//! the leaves of the tree along with the roots of all of its complete
+//! subtrees. The witness of any position can then be produced on demand,
+//! by stitching together the frontier of the tree at the witnessed
+//! position and the subtrees to its right.
+//!
+//! The produced [`IncrementalWitness`] and [`MaspCommitmentTree`] values
+//! are byte-for-byte identical to the ones built by successive appends.
+
+use anyhow::Context;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use namada_sdk::masp_primitives::merkle_tree::{
+    CommitmentTree as MaspCommitmentTree, Hashable, IncrementalWitness,
+};
+use namada_sdk::masp_primitives::sapling::{
+    Node, SAPLING_COMMITMENT_TREE_DEPTH,
+};
+use rayon::prelude::*;
+
+/// A leaf of the tree, i.e. a MASP note commitment.
+pub type Leaf = Node;
+
+#[derive(Debug, Clone, Default)]
+pub struct BridgeTree {
+    /// Roots of the complete subtrees of the tree, indexed by level.
+    ///
+    /// `levels[0]` holds the leaves of the tree, and `levels[d][i]` holds
+    /// the root of the subtree of depth `d` spanning the leaves in the
+    /// range `i << d .. (i + 1) << d`.
+    levels: Vec<Vec<Node>>,
+    /// Tree size at the last checkpoint.
+    checkpoint: Option<usize>,
+}
+
+impl BridgeTree {
+    /// Build a tree from the given leaves.
+    ///
+    /// The resulting tree has no checkpoint.
+    pub fn from_leaves(leaves: Vec<Node>) -> anyhow::Result<Self> {
+        if leaves.len() > 1 << SAPLING_COMMITMENT_TREE_DEPTH {
+            anyhow::bail!("Note commitment tree is full");
+        }
+
+        let mut levels = vec![leaves];
+
+        for depth in 0..SAPLING_COMMITMENT_TREE_DEPTH {
+            let parents: Vec<_> = levels[depth]
+                .par_chunks_exact(2)
+                .map(|pair| Node::combine(depth, &pair[0], &pair[1]))
+                .collect();
+            if parents.is_empty() {
+                break;
+            }
+            levels.push(parents);
+        }
+
+        Ok(Self {
+            levels,
+            checkpoint: None,
+        })
+    }
+
+    /// Return the number of leaves in the tree.
+    pub fn size(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Return the leaves of the tree, starting at the given position.
+    pub fn leaves_from(&self, position: usize) -> &[Node] {
+        self.levels
+            .first()
+            .and_then(|leaves| leaves.get(position..))
+            .unwrap_or_default()
+    }
+
+    /// Append a new leaf to the tree.
+    pub fn append(&mut self, node: Node) -> anyhow::Result<()> {
+        if self.size() >= 1 << SAPLING_COMMITMENT_TREE_DEPTH {
+            anyhow::bail!("Note commitment tree is full");
+        }
+
+        let mut node = node;
+
+        for depth in 0..=SAPLING_COMMITMENT_TREE_DEPTH {
+            if self.levels.len() == depth {
+                self.levels.push(Vec::new());
+            }
+
+            let level = &mut self.levels[depth];
+            level.push(node);
+
+            if depth == SAPLING_COMMITMENT_TREE_DEPTH || level.len() % 2 != 0 {
+                break;
+            }
+
+            let len = level.len();
+            node = Node::combine(depth, &level[len - 2], &level[len - 1]);
+        }
+
+        Ok(())
+    }
+
+    /// Record the current state of the tree, so that it can be restored
+    /// by a call to [`BridgeTree::rewind`].
+    pub fn checkpoint(&mut self) {
+        self.checkpoint = Some(self.size());
+    }
+
+    /// Return the size of the tree at the last checkpoint.
+    pub fn last_checkpoint(&self) -> Option<usize> {
+        self.checkpoint
+    }
+
+    /// Discard all leaves appended since the last checkpoint.
+    pub fn rewind(&mut self) {
+        self.truncate(self.last_checkpoint().unwrap_or(0));
+    }
+
+    /// Discard all leaves at positions greater than or equal to `size`,
+    /// along with the checkpoint that referred to them.
+    pub fn truncate(&mut self, size: usize) {
+        for (depth, level) in self.levels.iter_mut().enumerate() {
+            level.truncate(size >> depth);
+        }
+        self.checkpoint =
+            self.checkpoint.filter(|&checkpoint| checkpoint <= size);
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> Node {
+        self.commitment_tree().root()
+    }
+
+    /// Return the frontier of the tree, as a [`MaspCommitmentTree`].
+    pub fn commitment_tree(&self) -> MaspCommitmentTree<Node> {
+        MaspCommitmentTree::try_from_slice(
+            &self.commitment_tree_bytes_at(self.size()),
+        )
+        .expect("Commitment tree frontier encoding should be valid")
+    }
+
+    /// Return the borsh encoded [`MaspCommitmentTree`] of the tree, as
+    /// of when it held `size` leaves.
+    pub fn commitment_tree_bytes_at(&self, size: usize) -> Vec<u8> {
+        assert!(size <= self.size(), "Tree size {size} is out of bounds");
+
+        let mut bytes = Vec::new();
+        self.write_subtree_frontier(0, size, &mut bytes);
+        bytes
+    }
+
+    /// Return the witness of the note at the given position.
+    pub fn witness(
+        &self,
+        position: usize,
+    ) -> anyhow::Result<IncrementalWitness<Node>> {
+        if position >= self.size() {
+            anyhow::bail!("Note position {position} is out of bounds");
+        }
+        IncrementalWitness::try_from_slice(&self.witness_bytes(position))
+            .context("Failed to decode note witness")
+    }
+
+    /// Return the borsh encoded witness of the note at the given
+    /// position.
+    pub fn witness_bytes(&self, position: usize) -> Vec<u8> {
+        self.witness_bytes_at(position, self.size())
+    }
+
+    /// Return the borsh encoded witness of the note at the given
+    /// position, as of when the tree held `size` leaves.
+    pub fn witness_bytes_at(&self, position: usize, size: usize) -> Vec<u8> {
+        assert!(size <= self.size(), "Tree size {size} is out of bounds");
+        assert!(position < size, "Note position {position} is out of bounds");
+
+        let mut bytes = Vec::new();
+        self.write_subtree_frontier(0, position + 1, &mut bytes);
+
+        let mut filled = Vec::new();
+        let mut cursor = None;
+
+        // NB: the slots left unfilled in the frontier of the witnessed
+        // position are the subtrees to the right of its path to the
+        // root, which get filled in ascending depth order
+        for depth in 0..SAPLING_COMMITMENT_TREE_DEPTH {
+            let index = position >> depth;
+
+            if index & 1 != 0 {
+                continue;
+            }
+
+            let start = (index + 1) << depth;
+            let end = (index + 2) << depth;
+
+            if end <= size {
+                filled.push(self.levels[depth][index + 1]);
+            } else {
+                if start < size {
+                    cursor = Some(start);
+                }
+                break;
+            }
+        }
+
+        write_compact_size(filled.len(), &mut bytes);
+        for node in filled {
+            write_node(&node, &mut bytes);
+        }
+
+        match cursor {
+            Some(start) => {
+                bytes.push(1);
+                self.write_subtree_frontier(start, size, &mut bytes);
+            }
+            None => bytes.push(0),
+        }
+
+        bytes
+    }
+
+    /// Write the encoding of the [`MaspCommitmentTree`] holding the
+    /// leaves in the range `start..end`, where `start` is aligned to a
+    /// subtree spanning the entire range.
+    fn write_subtree_frontier(
+        &self,
+        start: usize,
+        end: usize,
+        bytes: &mut Vec<u8>,
+    ) {
+        let leaves = &self.levels.first().map_or(&[][..], Vec::as_slice);
+
+        let Some(last) = (end - start).checked_sub(1) else {
+            // NB: left, right and an empty parents vector
+            bytes.extend_from_slice(&[0, 0, 0]);
+            return;
+        };
+
+        let (left, right) = if last & 1 == 0 {
+            (leaves[start + last], None)
+        } else {
+            (leaves[start + last - 1], Some(leaves[start + last]))
+        };
+
+        bytes.push(1);
+        write_node(&left, bytes);
+        match right {
+            Some(right) => {
+                bytes.push(1);
+                write_node(&right, bytes);
+            }
+            None => bytes.push(0),
+        }
+
+        let num_parents =
+            (usize::BITS - last.leading_zeros()).saturating_sub(1) as usize;
+        write_compact_size(num_parents, bytes);
+
+        for depth in 1..=num_parents {
+            if last & (1 << depth) == 0 {
+                bytes.push(0);
+            } else {
+                let index = ((start + last) >> depth) - 1;
+                bytes.push(1);
+                write_node(&self.levels[depth][index], bytes);
+            }
+        }
+    }
+}
+
+#[inline]
+fn write_node(node: &Node, bytes: &mut Vec<u8>) {
+    node.serialize(bytes)
+        .expect("Writing to a vector should not fail");
+}
+
+#[inline]
+fn write_compact_size(size: usize, bytes: &mut Vec<u8>) {
+    // NB: sizes are bounded by the depth of the tree, and
+    // thus always fit in a single byte
+    debug_assert!(size < 253);
+    bytes.push(size as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// Tree sizes at which the trees are compared: empty, one leaf,
+    /// powers of two, and their neighbours.
+    const SIZES: &[usize] = &[
+        0, 1, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17, 31, 32, 33, 63, 64, 65,
+    ];
+
+    fn random_leaves(count: usize) -> Vec<Node> {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        (0..count)
+            .map(|_| {
+                let mut bytes: [u8; 32] = rng.random();
+                // NB: keep the leaf a canonical field element
+                bytes[31] &= 0x3f;
+                Node::new(bytes)
+            })
+            .collect()
+    }
+
+    /// Append `leaves` one by one to a masp commitment tree and to the
+    /// witnesses of all the notes, returning the borsh encoding of both
+    /// at every tree size.
+    fn reference_encodings(leaves: &[Node]) -> Vec<(Vec<u8>, Vec<Vec<u8>>)> {
+        let mut tree = MaspCommitmentTree::<Node>::empty();
+        let mut witnesses: Vec<IncrementalWitness<Node>> = Vec::new();
+        let mut encodings =
+            vec![(namada_sdk::borsh::to_vec(&tree).unwrap(), vec![])];
+
+        for leaf in leaves {
+            for witness in witnesses.iter_mut() {
+                witness.append(*leaf).unwrap();
+            }
+            tree.append(*leaf).unwrap();
+            witnesses.push(IncrementalWitness::from_tree(&tree));
+
+            encodings.push((
+                namada_sdk::borsh::to_vec(&tree).unwrap(),
+                witnesses
+                    .iter()
+                    .map(|witness| namada_sdk::borsh::to_vec(witness).unwrap())
+                    .collect(),
+            ));
+        }
+
+        encodings
+    }
+
+    #[test]
+    fn matches_successive_appends() {
+        let leaves = random_leaves(*SIZES.last().unwrap());
+        let reference = reference_encodings(&leaves);
+
+        for &size in SIZES {
+            let (tree_bytes, witness_bytes) = &reference[size];
+
+            let mut appended = BridgeTree::default();
+            for leaf in &leaves[..size] {
+                appended.append(*leaf).unwrap();
+            }
+            let built =
+                BridgeTree::from_leaves(leaves[..size].to_vec()).unwrap();
+
+            for tree in [&appended, &built] {
+                assert_eq!(tree.size(), size);
+                assert_eq!(
+                    &namada_sdk::borsh::to_vec(&tree.commitment_tree())
+                        .unwrap(),
+                    tree_bytes,
+                    "commitment tree of size {size}"
+                );
+                for (position, witness) in witness_bytes.iter().enumerate() {
+                    assert_eq!(
+                        &tree.witness_bytes(position),
+                        witness,
+                        "witness of position {position} in a tree of size \
+                         {size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_successive_appends_at_smaller_sizes() {
+        let leaves = random_leaves(*SIZES.last().unwrap());
+        let reference = reference_encodings(&leaves);
+        let tree = BridgeTree::from_leaves(leaves).unwrap();
+
+        for &size in SIZES {
+            let (tree_bytes, witness_bytes) = &reference[size];
+
+            assert_eq!(
+                &tree.commitment_tree_bytes_at(size),
+                tree_bytes,
+                "commitment tree of size {size}"
+            );
+            for (position, witness) in witness_bytes.iter().enumerate() {
+                assert_eq!(
+                    &tree.witness_bytes_at(position, size),
+                    witness,
+                    "witness of position {position} in a tree of size {size}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rewind_restores_checkpoint() {
+        let leaves = random_leaves(40);
+        let reference = reference_encodings(&leaves);
+
+        let mut tree = BridgeTree::from_leaves(leaves[..17].to_vec()).unwrap();
+        tree.checkpoint();
+        for leaf in &leaves[17..] {
+            tree.append(*leaf).unwrap();
+        }
+        tree.rewind();
+
+        assert_eq!(tree.size(), 17);
+        assert_eq!(tree.last_checkpoint(), Some(17));
+        assert_eq!(
+            namada_sdk::borsh::to_vec(&tree.commitment_tree()).unwrap(),
+            reference[17].0
+        );
+
+        // NB: truncating below the checkpoint discards it
+        tree.truncate(9);
+        assert_eq!(tree.last_checkpoint(), None);
+        assert_eq!(
+            namada_sdk::borsh::to_vec(&tree.commitment_tree()).unwrap(),
+            reference[9].0
+        );
+        for (position, witness) in reference[9].1.iter().enumerate() {
+            assert_eq!(&tree.witness_bytes(position), witness);
+        }
+
+        tree.rewind();
+        assert_eq!(tree.size(), 0);
+        assert_eq!(
+            namada_sdk::borsh::to_vec(&tree.commitment_tree()).unwrap(),
+            reference[0].0
+        );
+    }
+
+    #[test]
+    fn witness_out_of_bounds() {
+        let tree = BridgeTree::from_leaves(random_leaves(3)).unwrap();
+
+        assert!(tree.witness(2).is_ok());
+        assert!(tree.witness(3).is_err());
+    }
+}
@@ -1,32 +1,290 @@
-use tendermint_rpc::HttpClient;
+//! CometBFT RPC client, spread over multiple endpoints.
+//!
+//! Requests are sent to the first available endpoint, in the order they
+//! were configured, failing over to the next one when an endpoint cannot
+//! be reached. Endpoints that fail are not queried again for some time,
+//! which grows with each consecutive failure.
+//!
+//! Queries for the data of some block height are only sent to endpoints
+//! that have not pruned it, according to the earliest block height they
+//! report in their `/status`. This allows pairing a pruned primary node
+//! with an archive node, to which historical queries are routed.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tendermint::block::Height;
+use tendermint_rpc::client::CompatMode;
+use tendermint_rpc::endpoint::{block, block_results};
+use tendermint_rpc::error::ErrorDetail;
+use tendermint_rpc::{Error, HttpClient, SimpleRequest};
+
+/// Time after which the earliest block height of an endpoint is queried
+/// again.
+const EARLIEST_HEIGHT_TTL: Duration = Duration::from_secs(60);
+
+/// Time during which an endpoint is not queried, after its first
+/// failure.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Maximum time during which a failing endpoint is not queried.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
 
 #[derive(Clone, Debug)]
 pub struct Client {
+    endpoints: Arc<[Endpoint]>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
     inner: HttpClient,
+    health: Mutex<Health>,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    unavailable_until: Option<Instant>,
+    earliest_height: Option<(Height, Instant)>,
 }
 
 impl Client {
-    pub fn new(ur: &str) -> Self {
-        let url = ur.parse().expect("Invalid URL");
-        let inner = reqwest::Client::builder()
-            .cookie_store(true)
-            .build()
-            .expect("Failed to create HTTP client");
-        let http_client = HttpClient::new_from_parts(
-            inner,
-            url,
-            tendermint_rpc::client::CompatMode::V0_37,
+    pub fn new<U: AsRef<str>>(urls: &[U]) -> Self {
+        assert!(!urls.is_empty(), "No CometBFT URL was provided");
+
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                let url = url.as_ref();
+                let inner = reqwest::Client::builder()
+                    .cookie_store(true)
+                    .build()
+                    .expect("Failed to create HTTP client");
+                let http_client = HttpClient::new_from_parts(
+                    inner,
+                    url.parse().expect("Invalid URL"),
+                    CompatMode::V0_37,
+                );
+
+                Endpoint {
+                    url: url.to_owned(),
+                    inner: http_client,
+                    health: Mutex::new(Health::default()),
+                }
+            })
+            .collect();
+
+        Client { endpoints }
+    }
+
+    /// Run `request` against the available endpoints, until one of
+    /// them succeeds.
+    ///
+    /// If `height` is given, endpoints that have pruned the block at
+    /// that height are skipped.
+    async fn perform_with_failover<T, F, Fut>(
+        &self,
+        height: Option<Height>,
+        request: F,
+    ) -> Result<T, Error>
+    where
+        F: Fn(HttpClient) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, Error>> + Send,
+    {
+        let mut last_error = None;
+
+        for endpoint in self.endpoints_by_preference() {
+            if let Some(height) = height {
+                match endpoint.earliest_height().await {
+                    Ok(earliest_height) if earliest_height > height => {
+                        tracing::debug!(
+                            url = endpoint.url,
+                            %height,
+                            %earliest_height,
+                            "Skipping CometBFT endpoint that pruned the \
+                             requested height"
+                        );
+                        continue;
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        last_error = Some(err);
+                        continue;
+                    }
+                }
+            }
+
+            match request(endpoint.inner.clone()).await {
+                Ok(response) => {
+                    endpoint.record_success();
+                    return Ok(response);
+                }
+                Err(err) => {
+                    if matches!(err.detail(), ErrorDetail::Response(_)) {
+                        // NB: the endpoint is reachable, but might have
+                        // pruned the data we requested since we last
+                        // checked its earliest height
+                        endpoint.forget_earliest_height();
+                    } else {
+                        endpoint.record_failure();
+                    }
+
+                    tracing::warn!(
+                        url = endpoint.url,
+                        %err,
+                        "CometBFT request failed"
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            Error::client_internal(format!(
+                "No CometBFT endpoint has the block at height {}",
+                height.unwrap_or_default()
+            ))
+        }))
+    }
+
+    /// Return the endpoints in the order they should be queried: the
+    /// available endpoints first, in configuration order, followed by
+    /// the unavailable ones, as a last resort.
+    fn endpoints_by_preference(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let (mut available, unavailable): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .partition(|endpoint| endpoint.is_available(now));
+
+        available.extend(unavailable);
+        available
+    }
+}
+
+impl Endpoint {
+    fn is_available(&self, now: Instant) -> bool {
+        self.health
+            .lock()
+            .unwrap()
+            .unavailable_until
+            .is_none_or(|until| until <= now)
+    }
+
+    fn record_success(&self) {
+        let mut health = self.health.lock().unwrap();
+
+        if health.consecutive_failures > 0 {
+            tracing::info!(url = self.url, "CometBFT endpoint has recovered");
+        }
+
+        health.consecutive_failures = 0;
+        health.unavailable_until = None;
+    }
+
+    fn record_failure(&self) {
+        let mut health = self.health.lock().unwrap();
+
+        let backoff = MIN_BACKOFF
+            .saturating_mul(
+                2u32.saturating_pow(health.consecutive_failures.min(16)),
+            )
+            .min(MAX_BACKOFF);
+
+        health.consecutive_failures =
+            health.consecutive_failures.saturating_add(1);
+        health.unavailable_until = Some(Instant::now() + backoff);
+
+        tracing::warn!(
+            url = self.url,
+            consecutive_failures = health.consecutive_failures,
+            ?backoff,
+            "CometBFT endpoint is unavailable"
         );
-        Client { inner: http_client }
     }
 
-    pub fn get(&self) -> HttpClient {
-        self.inner.clone()
+    fn forget_earliest_height(&self) {
+        self.health.lock().unwrap().earliest_height = None;
+    }
+
+    /// Return the earliest block height available from this endpoint,
+    /// querying its `/status` if the last known value is stale.
+    async fn earliest_height(&self) -> Result<Height, Error> {
+        let cached = self.health.lock().unwrap().earliest_height;
+
+        if let Some((earliest_height, queried_at)) = cached {
+            if queried_at.elapsed() < EARLIEST_HEIGHT_TTL {
+                return Ok(earliest_height);
+            }
+        }
+
+        let status = tendermint_rpc::Client::status(&self.inner)
+            .await
+            .inspect_err(|err| {
+                if !matches!(err.detail(), ErrorDetail::Response(_)) {
+                    self.record_failure();
+                }
+            })?;
+        let earliest_height = status.sync_info.earliest_block_height;
+
+        self.health.lock().unwrap().earliest_height =
+            Some((earliest_height, Instant::now()));
+
+        Ok(earliest_height)
     }
 }
 
-impl AsRef<HttpClient> for Client {
-    fn as_ref(&self) -> &HttpClient {
-        &self.inner
+#[async_trait]
+impl tendermint_rpc::Client for Client {
+    async fn block<H>(&self, height: H) -> Result<block::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+
+        self.perform_with_failover(Some(height), |client| async move {
+            tendermint_rpc::Client::block(&client, height).await
+        })
+        .await
+    }
+
+    async fn block_results<H>(
+        &self,
+        height: H,
+    ) -> Result<block_results::Response, Error>
+    where
+        H: Into<Height> + Send,
+    {
+        let height = height.into();
+
+        self.perform_with_failover(Some(height), |client| async move {
+            tendermint_rpc::Client::block_results(&client, height).await
+        })
+        .await
+    }
+
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        // NB: requests are not `Clone`, so we round trip them through
+        // their JSON encoding to send them to multiple endpoints
+        let request = serde_json::to_value(&request).map_err(Error::serde)?;
+
+        self.perform_with_failover(None, |client| {
+            let request = serde_json::from_value::<R>(request.clone());
+
+            async move {
+                tendermint_rpc::Client::perform(
+                    &client,
+                    request.map_err(Error::serde)?,
+                )
+                .await
+            }
+        })
+        .await
     }
 }
@@ -1,6 +1,7 @@
 use std::sync::LazyLock;
 
-use namada_sdk::borsh::BorshSerializeExt;
+use anyhow::Context;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
 use namada_sdk::masp_primitives::merkle_tree::CommitmentTree;
 use namada_sdk::masp_primitives::sapling::Node;
 
@@ -11,3 +12,10 @@ pub fn empty() -> Vec<u8> {
         LazyLock::new(|| CommitmentTree::<Node>::empty().serialize_to_vec());
     EMPTY_TREE.clone()
 }
+
+/// Return the number of notes in a serialized [`CommitmentTree`].
+pub fn size(tree: &[u8]) -> anyhow::Result<usize> {
+    CommitmentTree::<Node>::try_from_slice(tree)
+        .map(|tree| tree.size())
+        .context("Failed to deserialize commitment tree")
+}
@@ -1,7 +1,12 @@
 use std::sync::atomic::{self, AtomicBool};
 
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::Notify;
+
 static EXIT_HANDLE: AtomicBool = AtomicBool::new(false);
 
+static EXIT_NOTIFY: Notify = Notify::const_new();
+
 #[inline]
 pub fn must_exit() -> bool {
     EXIT_HANDLE.load(atomic::Ordering::Relaxed)
@@ -10,4 +15,41 @@ pub fn must_exit() -> bool {
 #[inline]
 pub fn exit() {
     EXIT_HANDLE.store(true, atomic::Ordering::Relaxed);
+    EXIT_NOTIFY.notify_waiters();
+}
+
+/// Wait until the process is requested to exit.
+pub async fn exited() {
+    let notified = EXIT_NOTIFY.notified();
+    tokio::pin!(notified);
+
+    // NB: register interest in the notification before checking the
+    // flag, lest we miss an exit request in between
+    notified.as_mut().enable();
+
+    if !must_exit() {
+        notified.await;
+    }
+}
+
+/// Request the process to exit upon receiving an INT, TERM or QUIT
+/// signal.
+pub fn spawn_signal_handler() {
+    let mut interrupt = signal(SignalKind::interrupt())
+        .expect("Failed to install INT signal handler");
+    let mut term = signal(SignalKind::terminate())
+        .expect("Failed to install TERM signal handler");
+    let mut quit = signal(SignalKind::quit())
+        .expect("Failed to install QUIT signal handler");
+
+    tokio::spawn(async move {
+        let signal_descriptor = tokio::select! {
+            _ = interrupt.recv() => "INT",
+            _ = term.recv() => "TERM",
+            _ = quit.recv() => "QUIT",
+        };
+        tracing::info!(which = signal_descriptor, "Signal received");
+
+        exit();
+    });
 }
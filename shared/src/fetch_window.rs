@@ -0,0 +1,131 @@
+//! Bounded look-ahead window of the block fetching pipeline.
+//!
+//! Blocks are fetched concurrently, and may arrive out of order, in
+//! which case they are buffered until the blocks preceding them have
+//! been processed. The window bounds how far ahead of the processing
+//! stage the fetcher may run, both in number of blocks and in bytes,
+//! such that a slow processing stage applies backpressure to the
+//! fetcher, rather than letting the buffered blocks grow without
+//! limit.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use crate::block::Block;
+use crate::height::BlockHeight;
+
+/// Name of the gauge tracking the number of blocks in the window.
+const BLOCKS_GAUGE: &str = "masp_indexer_fetch_window_blocks";
+
+/// Name of the gauge tracking the size of the blocks in the window.
+const BYTES_GAUGE: &str = "masp_indexer_fetch_window_bytes";
+
+/// Handle to the look-ahead window shared by the fetching and
+/// processing stages.
+#[derive(Clone, Debug)]
+pub struct FetchWindow {
+    max_blocks: usize,
+    max_bytes: usize,
+    occupancy: Arc<watch::Sender<Occupancy>>,
+}
+
+#[derive(Debug, Default)]
+struct Occupancy {
+    /// Number of blocks being fetched, or waiting to be processed.
+    blocks: usize,
+    /// Size of the fetched blocks waiting to be processed.
+    bytes: usize,
+    /// Size of each of the fetched blocks waiting to be processed.
+    sizes: HashMap<BlockHeight, usize>,
+}
+
+impl FetchWindow {
+    /// Create a window that holds at most `max_blocks` blocks, and
+    /// stops growing once its blocks exceed `max_bytes`.
+    pub fn new(max_blocks: usize, max_bytes: usize) -> Self {
+        Self {
+            max_blocks: max_blocks.max(1),
+            max_bytes,
+            occupancy: Arc::new(watch::Sender::new(Occupancy::default())),
+        }
+    }
+
+    /// Return the maximum number of blocks in the window.
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    /// Wait for there to be room in the window, and reserve a slot for
+    /// the next block to fetch.
+    ///
+    /// Slots must be reserved in increasing order of block heights, for
+    /// the block that must be processed next to always be part of the
+    /// window.
+    pub async fn reserve(&self) {
+        let mut occupancy = self.occupancy.subscribe();
+
+        loop {
+            if occupancy
+                .wait_for(|occupancy| self.has_room(occupancy))
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            let reserved = self.occupancy.send_if_modified(|occupancy| {
+                if self.has_room(occupancy) {
+                    occupancy.blocks += 1;
+                    true
+                } else {
+                    false
+                }
+            });
+
+            if reserved {
+                self.record_occupancy();
+                return;
+            }
+        }
+    }
+
+    /// Account for the size of a fetched block, until it is released.
+    pub fn insert(&self, block: &Block) {
+        let size = block.size_in_bytes();
+
+        self.occupancy.send_modify(|occupancy| {
+            occupancy.bytes += size;
+            occupancy.sizes.insert(block.header.height, size);
+        });
+        self.record_occupancy();
+    }
+
+    /// Free the slot of the block at the given height, once it has been
+    /// processed.
+    pub fn release(&self, height: BlockHeight) {
+        self.occupancy.send_modify(|occupancy| {
+            let size = occupancy.sizes.remove(&height).unwrap_or_default();
+
+            occupancy.blocks = occupancy.blocks.saturating_sub(1);
+            occupancy.bytes = occupancy.bytes.saturating_sub(size);
+        });
+        self.record_occupancy();
+    }
+
+    fn has_room(&self, occupancy: &Occupancy) -> bool {
+        // NB: an empty window always has room, lest a single block
+        // larger than the window stalls the pipeline
+        occupancy.blocks == 0
+            || occupancy.blocks < self.max_blocks
+                && occupancy.bytes < self.max_bytes
+    }
+
+    fn record_occupancy(&self) {
+        let occupancy = self.occupancy.borrow();
+
+        metrics::gauge!(BLOCKS_GAUGE).set(occupancy.blocks as f64);
+        metrics::gauge!(BYTES_GAUGE).set(occupancy.bytes as f64);
+    }
+}
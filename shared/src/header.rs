@@ -5,6 +5,7 @@ use crate::height::BlockHeight;
 
 #[derive(Debug, Clone, Default)]
 pub struct BlockHeader {
+    pub chain_id: String,
     pub height: BlockHeight,
     pub proposer_address: Id,
     pub timestamp: String,
@@ -14,6 +15,7 @@ pub struct BlockHeader {
 impl From<Header> for BlockHeader {
     fn from(value: Header) -> Self {
         Self {
+            chain_id: value.chain_id.to_string(),
             height: BlockHeight::from(value.height),
             proposer_address: Id::Account(
                 value.proposer_address.to_string().to_lowercase(),
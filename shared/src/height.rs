@@ -3,19 +3,19 @@ use std::fmt::Display;
 use std::ops::ControlFlow;
 use std::time::Duration;
 
-use anyhow::Context;
 use namada_core::chain::BlockHeight as NamadaBlockHeight;
 use tendermint::block::Height;
-use tendermint_rpc::{Client, HttpClient};
 
 use crate::block::Block;
-use crate::retry;
+use crate::block_source::BlockSource;
+use crate::subscription::NewBlockSubscription;
+use crate::{exit_handle, metrics, retry};
 
 #[derive(Debug)]
 pub struct UnprocessedBlocks {
     next_height: BlockHeight,
     buffer: BTreeMap<BlockHeight, Block>,
-    empty_block_to_commit: Option<BlockHeight>,
+    empty_block_to_commit: Option<Block>,
 }
 
 impl UnprocessedBlocks {
@@ -35,7 +35,7 @@ impl UnprocessedBlocks {
         block_to_commit: &Block,
     ) -> bool {
         if block_to_commit.transactions.is_empty() {
-            self.empty_block_to_commit = Some(block_to_commit.header.height);
+            self.empty_block_to_commit = Some(block_to_commit.clone());
             true
         } else {
             self.empty_block_to_commit = None;
@@ -43,8 +43,14 @@ impl UnprocessedBlocks {
         }
     }
 
+    /// Return the number of blocks received out of order, waiting for
+    /// the blocks preceding them.
+    pub fn num_buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
     pub fn finalize(self) -> Option<Block> {
-        self.empty_block_to_commit.map(Block::empty_block)
+        self.empty_block_to_commit
     }
 
     pub fn next_to_process(&mut self, incoming_block: Block) -> Option<Block> {
@@ -56,6 +62,12 @@ impl UnprocessedBlocks {
         self.buffer
             .insert(incoming_block.header.height, incoming_block);
 
+        self.next_buffered()
+    }
+
+    /// Pop the next block to process from the buffer of blocks that
+    /// were received out of order, if it is available.
+    pub fn next_buffered(&mut self) -> Option<Block> {
         let can_process_buffer_head =
             *self.buffer.first_entry()?.key() == self.next_height;
 
@@ -76,6 +88,7 @@ impl UnprocessedBlocks {
 pub struct FollowingHeights {
     iter_height: BlockHeight,
     last_committed_height: BlockHeight,
+    subscription: Option<NewBlockSubscription>,
 }
 
 impl FollowingHeights {
@@ -88,38 +101,83 @@ impl FollowingHeights {
         Self {
             iter_height: h,
             last_committed_height: h,
+            subscription: None,
         }
     }
 
-    pub async fn next_height(
+    /// Learn about new blocks from the given subscription, rather than
+    /// by polling the block source, whenever it is connected.
+    pub fn with_subscription(
+        mut self,
+        subscription: Option<NewBlockSubscription>,
+    ) -> Self {
+        self.subscription = subscription;
+        self
+    }
+
+    pub async fn next_height<S: BlockSource>(
         &mut self,
-        http_client: &HttpClient,
+        block_source: &S,
         fetch_retry_interval: Duration,
     ) -> Option<BlockHeight> {
         let next_height =
             self.iter_height.next().expect("Block height overflow");
         self.iter_height = next_height;
 
+        if block_source
+            .final_height()
+            .is_some_and(|final_height| next_height > final_height)
+        {
+            return None;
+        }
+
         // NB: the next height might not have been committed
         // yet, and we must block
         while next_height > self.last_committed_height {
+            if exit_handle::must_exit() {
+                return None;
+            }
+
+            if let Some(subscription) = &self.subscription {
+                let new_tip = tokio::time::timeout(
+                    fetch_retry_interval,
+                    subscription.wait_above(self.last_committed_height),
+                )
+                .await;
+
+                match new_tip {
+                    Ok(Some(tip)) => {
+                        metrics::record_tip_height(tip);
+                        self.last_committed_height = tip;
+                        continue;
+                    }
+                    // NB: no new block was announced in the meantime
+                    Err(_) => continue,
+                    // NB: the subscription was lost, fall back to polling
+                    Ok(None) => {}
+                }
+            }
+
             // NB: the compiler likes to complain like a little
-            // bitch if we don't clone the http client
-            let http_client = http_client.clone();
+            // bitch if we don't clone the block source
+            let block_source = block_source.clone();
 
-            let ControlFlow::Continue(block) =
+            let ControlFlow::Continue(latest_height) =
                 retry::every(fetch_retry_interval, async move || {
-                    http_client.latest_block().await.context(
-                        "Failed to query Namada's last committed block",
-                    )
+                    block_source.latest_height().await
                 })
                 .await
             else {
                 return None;
             };
 
-            self.last_committed_height =
-                BlockHeight(block.block.header.height.value());
+            metrics::record_tip_height(latest_height);
+
+            if latest_height <= self.last_committed_height {
+                tokio::time::sleep(fetch_retry_interval).await;
+            }
+
+            self.last_committed_height = latest_height;
         }
 
         debug_assert!(self.iter_height <= self.last_committed_height);
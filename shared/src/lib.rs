@@ -1,15 +1,23 @@
+pub mod appstate;
 pub mod block;
+pub mod block_archive;
 pub mod block_results;
+pub mod block_source;
+pub mod bridge_tree;
 pub mod client;
 pub mod commitment_tree;
 pub mod error;
 pub mod exit_handle;
 pub mod extracted_masp_tx;
+pub mod fetch_window;
 pub mod header;
 pub mod height;
 pub mod id;
 pub mod indexed_tx;
+pub mod metrics;
 pub mod retry;
+pub mod subscription;
 pub mod transaction;
 pub mod transactional;
 pub mod tx_index;
+pub mod witness_delta;
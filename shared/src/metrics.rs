@@ -0,0 +1,131 @@
+//! Metrics of the indexer, exposed to Prometheus.
+//!
+//! Metrics are recorded through the `metrics` facade, and are discarded
+//! unless [`install_prometheus_exporter`] is called, in which case they
+//! can be scraped from the `/metrics` endpoint of the exporter.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Context;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+use crate::height::BlockHeight;
+
+/// Name of the gauge tracking the last block height committed to the
+/// database.
+pub const COMMITTED_HEIGHT_GAUGE: &str = "masp_indexer_committed_height";
+
+/// Name of the gauge tracking the last block height committed by
+/// CometBFT.
+pub const TIP_HEIGHT_GAUGE: &str = "masp_indexer_tip_height";
+
+/// Name of the gauge tracking the number of blocks the index lags
+/// behind the tip of the chain.
+pub const LAG_GAUGE: &str = "masp_indexer_lag_blocks";
+
+/// Name of the gauge tracking the number of fetched blocks waiting to
+/// be processed.
+pub const QUEUED_BLOCKS_GAUGE: &str = "masp_indexer_queued_blocks";
+
+/// Name of the histogram tracking the time taken to fetch the MASP txs
+/// of a block.
+pub const FETCH_DURATION_HISTOGRAM: &str =
+    "masp_indexer_fetch_duration_seconds";
+
+/// Name of the histogram tracking the time taken to validate the MASP
+/// state against the anchors on chain.
+pub const VALIDATION_DURATION_HISTOGRAM: &str =
+    "masp_indexer_validation_duration_seconds";
+
+/// Name of the histogram tracking the time taken to commit a block to
+/// the database.
+pub const COMMIT_DURATION_HISTOGRAM: &str =
+    "masp_indexer_commit_duration_seconds";
+
+/// Name of the counter of failed attempts that were retried.
+pub const RETRIES_COUNTER: &str = "masp_indexer_retries_total";
+
+/// Name of the counter of committed MASP txs.
+pub const MASP_TXS_COUNTER: &str = "masp_indexer_masp_txs_total";
+
+/// Name of the counter of committed MASP notes.
+pub const MASP_NOTES_COUNTER: &str = "masp_indexer_masp_notes_total";
+
+/// Upper bounds, in seconds, of the buckets of the duration histograms.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Last known heights, from which the lag of the index is derived.
+static TIP_HEIGHT: AtomicU64 = AtomicU64::new(0);
+static COMMITTED_HEIGHT: AtomicU64 = AtomicU64::new(0);
+
+/// Serve the recorded metrics to Prometheus from the `/metrics`
+/// endpoint at `address`.
+pub fn install_prometheus_exporter(address: SocketAddr) -> anyhow::Result<()> {
+    PrometheusBuilder::new()
+        .with_http_listener(address)
+        .set_buckets_for_metric(
+            Matcher::Suffix("_seconds".to_owned()),
+            DURATION_BUCKETS,
+        )
+        .context("Invalid histogram buckets")?
+        .install()
+        .context("Failed to install the Prometheus exporter")?;
+
+    tracing::info!(%address, "Serving Prometheus metrics");
+
+    Ok(())
+}
+
+/// Record the last block height committed by CometBFT.
+pub fn record_tip_height(height: BlockHeight) {
+    let tip_height = TIP_HEIGHT
+        .fetch_max(height.0, Ordering::Relaxed)
+        .max(height.0);
+    metrics::gauge!(TIP_HEIGHT_GAUGE).set(tip_height as f64);
+    record_lag();
+}
+
+/// Record the commit of the MASP txs and notes of the block at the
+/// given height to the database.
+///
+/// Empty blocks should be recorded as soon as they are processed,
+/// although their commit is deferred, lest the index appears to lag
+/// behind a chain without MASP activity.
+pub fn record_committed_block(
+    height: BlockHeight,
+    num_masp_txs: usize,
+    num_masp_notes: usize,
+) {
+    metrics::counter!(MASP_TXS_COUNTER).increment(num_masp_txs as u64);
+    metrics::counter!(MASP_NOTES_COUNTER).increment(num_masp_notes as u64);
+
+    let committed_height = COMMITTED_HEIGHT
+        .fetch_max(height.0, Ordering::Relaxed)
+        .max(height.0);
+    metrics::gauge!(COMMITTED_HEIGHT_GAUGE).set(committed_height as f64);
+    record_lag();
+}
+
+/// Record the number of fetched blocks waiting to be processed.
+pub fn record_queued_blocks(num_blocks: usize) {
+    metrics::gauge!(QUEUED_BLOCKS_GAUGE).set(num_blocks as f64);
+}
+
+/// Record the time taken, in seconds, by some stage of the indexer, in
+/// the histogram with the given name.
+pub fn record_time_taken(histogram: &'static str, time_taken: f64) {
+    metrics::histogram!(histogram).record(time_taken);
+}
+
+fn record_lag() {
+    let tip = TIP_HEIGHT.load(Ordering::Relaxed);
+    let committed = COMMITTED_HEIGHT.load(Ordering::Relaxed);
+
+    // NB: the lag is unknown until we learn about the tip of the chain
+    if tip > 0 {
+        metrics::gauge!(LAG_GAUGE).set(tip.saturating_sub(committed) as f64);
+    }
+}
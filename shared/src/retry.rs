@@ -6,6 +6,7 @@ use std::ops::ControlFlow;
 use tokio::time::Duration;
 
 use crate::exit_handle;
+use crate::metrics::RETRIES_COUNTER;
 
 /// Retry a future generated by `future_generator`, if it fails.
 pub async fn every<F, T, E>(
@@ -24,6 +25,8 @@ where
                     return ControlFlow::Break(());
                 }
 
+                metrics::counter!(RETRIES_COUNTER).increment(1);
+
                 let jitter =
                     duration.mul_f64(rand::random_range(0.75f64..=1.25));
 
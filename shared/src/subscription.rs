@@ -0,0 +1,151 @@
+//! Subscription to the new blocks committed by CometBFT.
+//!
+//! The subscription is maintained by a background task, which listens
+//! to `NewBlock` events over CometBFT's WebSocket endpoint, and
+//! reconnects whenever the connection is lost. While disconnected, the
+//! tip of the chain is unknown, and callers are expected to fall back to
+//! polling CometBFT.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::exit_handle;
+use crate::height::BlockHeight;
+
+/// Query of the `NewBlock` events.
+const NEW_BLOCK_QUERY: &str = "tm.event='NewBlock'";
+
+/// Maximum time to wait for a message from CometBFT, before assuming the
+/// connection was lost.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Handle to a subscription to CometBFT's new blocks.
+#[derive(Clone, Debug)]
+pub struct NewBlockSubscription {
+    tip: watch::Receiver<Option<BlockHeight>>,
+}
+
+impl NewBlockSubscription {
+    /// Subscribe to the new blocks announced by the CometBFT WebSocket
+    /// endpoint at `url`, reconnecting after `retry_interval` if the
+    /// connection is lost.
+    pub fn spawn(url: String, retry_interval: Duration) -> Self {
+        let (tip_tx, tip) = watch::channel(None);
+
+        tokio::spawn(async move {
+            while !exit_handle::must_exit() {
+                if let Err(err) = listen(&url, &tip_tx).await {
+                    tracing::warn!(
+                        url,
+                        summary = %err,
+                        full = ?err,
+                        "Lost subscription to new blocks, falling back to \
+                         polling"
+                    );
+                }
+
+                tip_tx.send_replace(None);
+
+                if tip_tx.is_closed() {
+                    return;
+                }
+
+                tokio::time::sleep(retry_interval).await;
+            }
+        });
+
+        Self { tip }
+    }
+
+    /// Wait for a block above `height` to be committed, and return the
+    /// height of the new tip of the chain.
+    ///
+    /// Returns `None` if the tip of the chain is currently unknown,
+    /// e.g. because the subscription was lost.
+    pub async fn wait_above(&self, height: BlockHeight) -> Option<BlockHeight> {
+        let mut tip = self.tip.clone();
+
+        tip.wait_for(|tip| tip.is_none_or(|tip| tip > height))
+            .await
+            .ok()
+            .and_then(|tip| *tip)
+    }
+}
+
+/// Listen to the `NewBlock` events announced at `url`, until the
+/// connection is lost.
+async fn listen(
+    url: &str,
+    tip: &watch::Sender<Option<BlockHeight>>,
+) -> anyhow::Result<()> {
+    let (mut stream, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .context("Failed to connect to CometBFT's WebSocket endpoint")?;
+
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "subscribe",
+        "params": { "query": NEW_BLOCK_QUERY },
+    });
+    stream
+        .send(Message::Text(subscribe.to_string()))
+        .await
+        .context("Failed to subscribe to new blocks")?;
+
+    tracing::info!(url, "Subscribed to new blocks");
+
+    while !exit_handle::must_exit() {
+        let message = tokio::time::timeout(MESSAGE_TIMEOUT, stream.next())
+            .await
+            .context("Timed out waiting for new blocks")?
+            .context("CometBFT closed the WebSocket connection")?
+            .context("Failed to read from CometBFT's WebSocket endpoint")?;
+
+        let payload = match message {
+            Message::Text(text) => text.into_bytes(),
+            Message::Binary(bytes) => bytes,
+            Message::Close(_) => {
+                anyhow::bail!("CometBFT closed the WebSocket connection")
+            }
+            _ => continue,
+        };
+
+        let response: Value = serde_json::from_slice(&payload)
+            .context("Failed to parse message from CometBFT")?;
+
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("CometBFT returned an error: {error}");
+        }
+
+        // NB: the acknowledgement of the subscription carries no data
+        let Some(height) = response
+            .pointer("/result/data/value/block/header/height")
+            .and_then(Value::as_str)
+        else {
+            continue;
+        };
+        let height = height
+            .parse()
+            .map(BlockHeight)
+            .context("Invalid height in new block event")?;
+
+        tracing::debug!(%height, "New block committed");
+
+        tip.send_if_modified(|tip| {
+            if tip.is_none_or(|tip| tip < height) {
+                *tip = Some(height);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use std::borrow::Cow;
 
+use anyhow::Context;
+use namada_core::borsh::BorshDeserialize;
 use namada_core::hash::Hash;
+use namada_core::masp_primitives::ff::PrimeField;
+use namada_core::masp_primitives::sapling::Node;
 use namada_core::masp_primitives::transaction::Transaction as NamadaMaspTransaction;
 use namada_sdk::token::Transfer;
 use namada_tx::event::MaspTxRef;
@@ -44,6 +48,31 @@ impl Transaction {
     }
 }
 
+/// Return the number of notes appended to the commitment tree by the
+/// borsh encoded MASP tx in `tx_bytes`.
+pub fn num_masp_notes(tx_bytes: &[u8]) -> anyhow::Result<usize> {
+    let masp_tx = NamadaMaspTransaction::try_from_slice(tx_bytes)
+        .context("Failed to deserialize MASP tx")?;
+
+    Ok(masp_tx
+        .sapling_bundle()
+        .map_or(0, |bundle| bundle.shielded_outputs.len()))
+}
+
+/// Return the note commitments appended to the commitment tree by the
+/// borsh encoded MASP tx in `tx_bytes`.
+pub fn masp_note_commitments(tx_bytes: &[u8]) -> anyhow::Result<Vec<Node>> {
+    let masp_tx = NamadaMaspTransaction::try_from_slice(tx_bytes)
+        .context("Failed to deserialize MASP tx")?;
+
+    Ok(masp_tx
+        .sapling_bundle()
+        .map_or(&[][..], |bundle| &bundle.shielded_outputs)
+        .iter()
+        .map(|output| Node::new(output.cmu.to_repr()))
+        .collect())
+}
+
 fn get_masp_tx_from_ibc_data(
     transaction: &NamadaTx,
     data_sechash: &Hash,
@@ -0,0 +1,21 @@
+//! Delta encoding of the MASP witness map.
+//!
+//! Rather than the full witness map, each height stores the leaves appended
+//! to the commitment tree at that height. The witness map at any height can
+//! then be served from the leaves of the commitment tree up to that height.
+
+use anyhow::Context;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_sdk::masp_primitives::sapling::Node;
+
+/// Serialize the leaves appended to the commitment tree at some height.
+#[inline]
+pub fn serialize_leaves(leaves: &[Node]) -> Vec<u8> {
+    leaves.serialize_to_vec()
+}
+
+/// Deserialize the leaves appended to the commitment tree at some height.
+pub fn deserialize_leaves(bytes: &[u8]) -> anyhow::Result<Vec<Node>> {
+    Vec::<Node>::try_from_slice(bytes)
+        .context("Failed to deserialize commitment tree leaves")
+}
@@ -4,5 +4,6 @@ use vergen::EmitBuilder;
 
 fn main() -> Result<(), Box<dyn Error>> {
     EmitBuilder::builder().all_git().emit()?;
+    tonic_build::compile_protos("proto/masp_indexer.proto")?;
     Ok(())
 }
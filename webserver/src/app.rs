@@ -4,22 +4,36 @@ use std::time::Duration;
 
 use anyhow::Context;
 use axum::error_handling::HandleErrorLayer;
+use axum::http::header::VARY;
 use axum::http::{HeaderValue, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::get;
-use axum::{BoxError, Json, Router};
+use axum::{BoxError, Json, Router, middleware};
 use axum_trace_id::SetTraceIdLayer;
 use lazy_static::lazy_static;
 use serde_json::json;
+use shared::exit_handle;
 use tower::ServiceBuilder;
 use tower::buffer::BufferLayer;
 use tower::limit::RateLimitLayer;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{
+    DefaultPredicate, NotForContentType, Predicate,
+};
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 
 use crate::appstate::AppState;
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ServerConfig};
+use crate::dto::notes_index::NotesIndexQueryParams;
+use crate::dto::tree::TreeQueryParams;
+use crate::dto::txs::TxQueryParams;
+use crate::dto::witness::WitnessMapQueryParams;
+use crate::grpc::masp_indexer::MaspIndexerGrpc;
+use crate::grpc::proto::masp_indexer_server::MaspIndexerServer;
 use crate::handler;
+use crate::response::cache::etag;
 use crate::state::common::CommonState;
 
 lazy_static! {
@@ -31,75 +45,183 @@ pub struct ApplicationServer;
 
 impl ApplicationServer {
     pub async fn serve(config: Arc<AppConfig>) -> anyhow::Result<()> {
+        let app_state = AppState::new(config.database_url.clone()).await?;
+
+        Self::serve_with_state(&config.server, app_state).await
+    }
+
+    /// Serve the API from the db of `app_state`, until the process is
+    /// requested to exit.
+    pub async fn serve_with_state(
+        config: &ServerConfig,
+        app_state: AppState,
+    ) -> anyhow::Result<()> {
         let rps = config.rps.unwrap_or_else(|| *REQ_PER_SEC);
-        let db_url = config.database_url.clone();
 
-        let app_state = AppState::new(db_url).await?;
+        let common_state = CommonState::new(app_state.clone());
 
         let routes = {
-            let common_state = CommonState::new(app_state.clone());
-
             Router::new()
                 .route(
                     "/commitment-tree",
-                    get(handler::tree::get_commitment_tree),
+                    get(handler::tree::get_commitment_tree).layer(
+                        middleware::from_fn_with_state(
+                            common_state.clone(),
+                            etag::<CommonState, TreeQueryParams, _>,
+                        ),
+                    ),
                 )
                 .route(
                     "/witness-map",
-                    get(handler::witness_map::get_witness_map),
+                    get(handler::witness_map::get_witness_map).layer(
+                        middleware::from_fn_with_state(
+                            common_state.clone(),
+                            etag::<CommonState, WitnessMapQueryParams, _>,
+                        ),
+                    ),
                 )
                 .route(
                     "/notes-index",
-                    get(handler::notes_index::get_notes_index),
+                    get(handler::notes_index::get_notes_index).layer(
+                        middleware::from_fn_with_state(
+                            common_state.clone(),
+                            etag::<CommonState, NotesIndexQueryParams, _>,
+                        ),
+                    ),
+                )
+                .route(
+                    "/tx",
+                    get(handler::tx::get_tx).layer(
+                        middleware::from_fn_with_state(
+                            common_state.clone(),
+                            etag::<CommonState, TxQueryParams, _>,
+                        ),
+                    ),
+                )
+                .route("/tx/:tx_hash", get(handler::tx::get_tx_by_hash))
+                .route("/stream/tx", get(handler::tx::get_tx_stream))
+                .route(
+                    "/stream/witness-map",
+                    get(handler::witness_map::get_witness_map_stream),
                 )
-                .route("/tx", get(handler::tx::get_tx))
                 .route("/height", get(handler::namada_state::get_latest_height))
                 .route(
                     "/block-index",
                     get(handler::namada_state::get_block_index),
                 )
-                .with_state(common_state)
+                .route("/block", get(handler::block::get_first_block_from))
+                .route("/block/:height", get(handler::block::get_block))
+                .route(
+                    "/conversions",
+                    get(handler::conversion::get_conversions),
+                )
+                .route("/flows", get(handler::tx_flow::get_flows))
+                .route("/events", get(handler::notification::get_events))
+                .route("/asset-type", get(handler::asset::get_assets))
+                .route(
+                    "/asset-type/:asset_type",
+                    get(handler::asset::get_asset),
+                )
+                .route(
+                    "/network",
+                    get(handler::namada_state::get_network_identity),
+                )
+                .with_state(common_state.clone())
         };
 
-        let cors = CorsLayer::new()
-            .allow_origin("*".parse::<HeaderValue>().unwrap())
-            .allow_methods(Any)
-            .allow_headers(Any);
-
         let router = Router::new()
             .nest("/api/v1", routes)
             .merge(Router::new().route(
                 "/health",
                 get(|| async { json!({"commit": env!("VERGEN_GIT_SHA").to_string(), "version": env!("CARGO_PKG_VERSION") }).to_string() }),
             ))
-            .with_state(app_state)
-            .layer(
-                ServiceBuilder::new()
-                    .layer(TraceLayer::new_for_http())
-                    .layer(HandleErrorLayer::new(Self::handle_timeout_error))
-                    .timeout(Duration::from_secs(*HTTP_TIMEOUT))
-                    .layer(cors)
-                    .layer(BufferLayer::new(4096))
-                    .layer(RateLimitLayer::new(rps, Duration::from_secs(1)))
-                    .layer(SetTraceIdLayer::<String>::new()),
-            );
-
-        let router = router.fallback(Self::handle_404);
+            .with_state(app_state);
+
+        let router =
+            Self::with_middleware(router, rps).fallback(Self::handle_404);
 
         let port = config.port;
         let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
 
         tracing::info!("🚀 Server has launched on https://{addr}");
 
-        axum::Server::bind(&addr)
-            .serve(router.into_make_service())
-            .with_graceful_shutdown(Self::shutdown_signal())
-            .await
-            .context("The server shutdown unexpectedly")?;
+        let rest_server = async {
+            axum::Server::bind(&addr)
+                .serve(router.into_make_service())
+                .with_graceful_shutdown(Self::shutdown_signal())
+                .await
+                .context("The server shutdown unexpectedly")
+        };
+        let grpc_server = async {
+            match config.grpc_port {
+                Some(grpc_port) => {
+                    Self::serve_grpc(grpc_port, common_state).await
+                }
+                None => Ok(()),
+            }
+        };
+
+        tokio::try_join!(rest_server, grpc_server)?;
 
         Ok(())
     }
 
+    /// Wrap `router` in the middleware shared by all the routes of the
+    /// REST API.
+    fn with_middleware(router: Router, rps: u64) -> Router {
+        // NB: responses do not depend on the CORS request headers, and the
+        // `Vary` headers of the CORS layer would replace those of the
+        // responses, which caches rely on
+        let cors = CorsLayer::new()
+            .allow_origin("*".parse::<HeaderValue>().unwrap())
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .vary([]);
+
+        router.layer(
+            ServiceBuilder::new()
+                .layer(TraceLayer::new_for_http())
+                .layer(HandleErrorLayer::new(Self::handle_timeout_error))
+                .timeout(Duration::from_secs(*HTTP_TIMEOUT))
+                .layer(cors)
+                .layer(SetResponseHeaderLayer::appending(
+                    VARY,
+                    HeaderValue::from_static("accept-encoding"),
+                ))
+                // NB: compressing events would delay them until the
+                // compression buffers are flushed
+                .layer(
+                    CompressionLayer::new().compress_when(
+                        DefaultPredicate::new().and(
+                            NotForContentType::const_new("text/event-stream"),
+                        ),
+                    ),
+                )
+                .layer(BufferLayer::new(4096))
+                .layer(RateLimitLayer::new(rps, Duration::from_secs(1)))
+                .layer(SetTraceIdLayer::<String>::new()),
+        )
+    }
+
+    /// Serve the gRPC API on `port`, until the process is requested to
+    /// exit.
+    async fn serve_grpc(
+        port: u16,
+        common_state: CommonState,
+    ) -> anyhow::Result<()> {
+        let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+
+        tracing::info!("🚀 gRPC server has launched on http://{addr}");
+
+        tonic::transport::Server::builder()
+            .add_service(MaspIndexerServer::new(MaspIndexerGrpc::new(
+                common_state,
+            )))
+            .serve_with_shutdown(addr, Self::shutdown_signal())
+            .await
+            .context("The gRPC server shutdown unexpectedly")
+    }
+
     /// Adds a custom handler for tower's `TimeoutLayer`, see https://docs.rs/axum/latest/axum/middleware/index.html#commonly-used-middleware.
     async fn handle_timeout_error(
         err: BoxError,
@@ -125,13 +247,11 @@ impl ApplicationServer {
         }
     }
 
-    /// Tokio signal handler that will wait for a user to press CTRL+C.
+    /// Wait for the process to be requested to exit.
     /// We use this in our hyper `Server` method `with_graceful_shutdown`.
     async fn shutdown_signal() {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Unexpected tokio shutdown signal await error");
-        tracing::info!("Interrupt signal received, shutting down server");
+        exit_handle::exited().await;
+        tracing::info!("Exit requested, shutting down server");
     }
 
     async fn handle_404() -> impl IntoResponse {
@@ -147,3 +267,124 @@ impl ApplicationServer {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::{
+        ACCEPT, ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, ETAG,
+        IF_NONE_MATCH,
+    };
+    use axum::http::{HeaderName, Request};
+    use axum::response::Response;
+    use serde::Serialize;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::response::cache::IndexedTip;
+    use crate::response::encoding::{
+        COMPACT_JSON, Encoded, Encoding, serialize_bytes,
+    };
+
+    #[derive(Serialize)]
+    struct TreeResponse {
+        #[serde(serialize_with = "serialize_bytes")]
+        commitment_tree: Vec<u8>,
+    }
+
+    #[derive(Clone)]
+    struct Tip;
+
+    #[axum::async_trait]
+    impl IndexedTip for Tip {
+        async fn indexed_tip(&self) -> anyhow::Result<Option<u64>> {
+            Ok(Some(1))
+        }
+    }
+
+    async fn get_tree(encoding: Encoding) -> Encoded<TreeResponse> {
+        Encoded(
+            encoding,
+            TreeResponse {
+                commitment_tree: vec![7; 1024],
+            },
+        )
+    }
+
+    async fn request(headers: &[(HeaderName, &str)]) -> Response {
+        let router = ApplicationServer::with_middleware(
+            Router::new().route(
+                "/commitment-tree",
+                get(get_tree).layer(middleware::from_fn_with_state(
+                    Tip,
+                    etag::<Tip, TreeQueryParams, _>,
+                )),
+            ),
+            u64::MAX,
+        );
+
+        let mut request = Request::get("/commitment-tree?height=1");
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+
+        router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn vary(response: &Response) -> Vec<&str> {
+        response
+            .headers()
+            .get_all(VARY)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn revalidates_compressed_responses() {
+        let response =
+            request(&[(ACCEPT, COMPACT_JSON), (ACCEPT_ENCODING, "gzip")]).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+        assert_eq!(vary(&response), ["accept", "accept-encoding"]);
+        assert!(
+            response.headers()[CACHE_CONTROL]
+                .to_str()
+                .unwrap()
+                .contains("must-revalidate")
+        );
+        let etag = response.headers()[ETAG].to_str().unwrap().to_string();
+        assert!(etag.starts_with("W/"));
+
+        // NB: the tag of the gzip coding also matches the other codings
+        for accept_encoding in ["br", "identity"] {
+            let response = request(&[
+                (ACCEPT, COMPACT_JSON),
+                (ACCEPT_ENCODING, accept_encoding),
+                (IF_NONE_MATCH, &etag),
+            ])
+            .await;
+
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(response.headers()[ETAG], etag.as_str());
+            assert_eq!(vary(&response), ["accept", "accept-encoding"]);
+            assert!(!response.headers().contains_key(CONTENT_ENCODING));
+        }
+
+        // NB: other media types are tagged differently
+        let response = request(&[
+            (ACCEPT, "application/json"),
+            (ACCEPT_ENCODING, "gzip"),
+            (IF_NONE_MATCH, &etag),
+        ])
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[ETAG], etag.as_str());
+        assert_eq!(vary(&response), ["accept", "accept-encoding"]);
+    }
+}
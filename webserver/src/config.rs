@@ -1,11 +1,23 @@
 #[derive(clap::Parser)]
 pub struct AppConfig {
+    #[clap(long, env)]
+    pub database_url: String,
+
+    #[command(flatten)]
+    pub server: ServerConfig,
+}
+
+/// Configuration of the API server, independent of the db it reads
+/// from.
+#[derive(clap::Args)]
+pub struct ServerConfig {
     #[clap(long, env, default_value = "5000")]
     pub port: u16,
 
     #[clap(long, env)]
-    pub database_url: String,
+    pub rps: Option<u64>,
 
+    /// Port to serve the gRPC API on, alongside the REST API.
     #[clap(long, env)]
-    pub rps: Option<u64>,
+    pub grpc_port: Option<u16>,
 }
@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct ConversionQueryParams {
+    pub epoch: Option<u64>,
+}
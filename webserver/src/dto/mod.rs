@@ -1,4 +1,8 @@
+pub mod asset;
+pub mod block;
+pub mod conversion;
 pub mod notes_index;
 pub mod tree;
+pub mod tx_flow;
 pub mod txs;
 pub mod witness;
@@ -1,8 +1,22 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::response::cache::AsOfHeight;
+
 #[derive(Clone, Serialize, Deserialize, Validate)]
 pub struct NotesIndexQueryParams {
     #[validate(range(min = 1))]
     pub height: u64,
+    /// First block height of the range, defaulting to the first block.
+    pub from: Option<u64>,
+    /// Note position after which to start the page.
+    pub after: Option<u64>,
+    #[validate(range(min = 1, max = 10000))]
+    pub limit: Option<u64>,
+}
+
+impl AsOfHeight for NotesIndexQueryParams {
+    fn as_of_height(&self) -> u64 {
+        self.height
+    }
 }
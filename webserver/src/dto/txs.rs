@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::response::cache::AsOfHeight;
+
 #[derive(Clone, Serialize, Deserialize, Validate)]
 pub struct TxQueryParams {
     #[validate(range(min = 1))]
@@ -8,3 +10,17 @@ pub struct TxQueryParams {
     #[validate(range(min = 0, max = 30))]
     pub height_offset: u64,
 }
+
+impl AsOfHeight for TxQueryParams {
+    fn as_of_height(&self) -> u64 {
+        self.height.saturating_add(self.height_offset)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Validate)]
+pub struct TxStreamQueryParams {
+    pub from_height: u64,
+    /// Last block height of the range, defaulting to the last indexed
+    /// block.
+    pub to_height: Option<u64>,
+}
@@ -1,8 +1,16 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::response::cache::AsOfHeight;
+
 #[derive(Clone, Serialize, Deserialize, Validate)]
 pub struct WitnessMapQueryParams {
     #[validate(range(min = 1))]
     pub height: u64,
 }
+
+impl AsOfHeight for WitnessMapQueryParams {
+    fn as_of_height(&self) -> u64 {
+        self.height
+    }
+}
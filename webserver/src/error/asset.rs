@@ -0,0 +1,24 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::response::api::ApiErrorResponse;
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("Asset type {0} not found")]
+    AssetTypeNotFound(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for AssetError {
+    fn into_response(self) -> Response {
+        let status_code = match self {
+            AssetError::AssetTypeNotFound(_) => StatusCode::NOT_FOUND,
+            AssetError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+    }
+}
@@ -0,0 +1,30 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::response::api::ApiErrorResponse;
+
+#[derive(Error, Debug)]
+pub enum BlockError {
+    #[error("Block {0} not found")]
+    BlockNotFound(u64),
+    #[error("No block found from {0}")]
+    NoBlockFrom(String),
+    #[error("Blocks are not indexed as far back as {0}")]
+    BlocksNotIndexed(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for BlockError {
+    fn into_response(self) -> Response {
+        let status_code = match self {
+            BlockError::BlockNotFound(_)
+            | BlockError::NoBlockFrom(_)
+            | BlockError::BlocksNotIndexed(_) => StatusCode::NOT_FOUND,
+            BlockError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+    }
+}
@@ -0,0 +1,28 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::response::api::ApiErrorResponse;
+
+#[derive(Error, Debug)]
+pub enum ConversionError {
+    #[error("No conversions found at or below MASP epoch {0}")]
+    EpochNotFound(u64),
+    #[error("No conversions have been indexed yet")]
+    NotIndexed,
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for ConversionError {
+    fn into_response(self) -> Response {
+        let status_code = match self {
+            ConversionError::EpochNotFound(_) | ConversionError::NotIndexed => {
+                StatusCode::NOT_FOUND
+            }
+            ConversionError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+    }
+}
@@ -1,6 +1,10 @@
 pub mod api;
+pub mod asset;
+pub mod block;
+pub mod conversion;
 pub mod namada_state;
 pub mod notes_index;
 pub mod tree;
 pub mod tx;
+pub mod tx_flow;
 pub mod witness_map;
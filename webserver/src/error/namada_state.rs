@@ -1,6 +1,7 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
+use tonic::Status;
 
 use crate::response::api::ApiErrorResponse;
 
@@ -8,6 +9,8 @@ use crate::response::api::ApiErrorResponse;
 pub enum NamadaStateError {
     #[error("Block index not found")]
     BlockIndexNotFound,
+    #[error("Network identity not recorded yet")]
+    NetworkIdentityNotFound,
     #[error("Database error: {0}")]
     Database(String),
 }
@@ -15,10 +18,25 @@ pub enum NamadaStateError {
 impl IntoResponse for NamadaStateError {
     fn into_response(self) -> Response {
         let status_code = match self {
-            NamadaStateError::BlockIndexNotFound => StatusCode::NOT_FOUND,
+            NamadaStateError::BlockIndexNotFound
+            | NamadaStateError::NetworkIdentityNotFound => {
+                StatusCode::NOT_FOUND
+            }
             NamadaStateError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
         ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
     }
 }
+
+impl From<NamadaStateError> for Status {
+    fn from(err: NamadaStateError) -> Self {
+        match &err {
+            NamadaStateError::BlockIndexNotFound
+            | NamadaStateError::NetworkIdentityNotFound => {
+                Status::not_found(err.to_string())
+            }
+            NamadaStateError::Database(_) => Status::internal(err.to_string()),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
+use tonic::Status;
 
 use crate::response::api::ApiErrorResponse;
 
@@ -8,6 +9,8 @@ use crate::response::api::ApiErrorResponse;
 pub enum NotesIndexError {
     #[error("NotesIndex not found")]
     NotFound,
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
     #[error("Database error: {0}")]
     Database(String),
 }
@@ -16,9 +19,22 @@ impl IntoResponse for NotesIndexError {
     fn into_response(self) -> Response {
         let status_code = match self {
             NotesIndexError::NotFound => StatusCode::NOT_FOUND,
+            NotesIndexError::InvalidRange(_) => StatusCode::BAD_REQUEST,
             NotesIndexError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
         ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
     }
 }
+
+impl From<NotesIndexError> for Status {
+    fn from(err: NotesIndexError) -> Self {
+        match &err {
+            NotesIndexError::NotFound => Status::not_found(err.to_string()),
+            NotesIndexError::InvalidRange(_) => {
+                Status::invalid_argument(err.to_string())
+            }
+            NotesIndexError::Database(_) => Status::internal(err.to_string()),
+        }
+    }
+}
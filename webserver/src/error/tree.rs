@@ -1,6 +1,7 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
+use tonic::Status;
 
 use crate::response::api::ApiErrorResponse;
 
@@ -19,3 +20,11 @@ impl IntoResponse for TreeError {
         ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
     }
 }
+
+impl From<TreeError> for Status {
+    fn from(err: TreeError) -> Self {
+        match &err {
+            TreeError::Database(_) => Status::internal(err.to_string()),
+        }
+    }
+}
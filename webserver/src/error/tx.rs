@@ -1,11 +1,18 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
+use tonic::Status;
 
 use crate::response::api::ApiErrorResponse;
 
 #[derive(Error, Debug)]
 pub enum TxError {
+    #[error("No MASP transactions found with hash {0}")]
+    NotFound(String),
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+    #[error("No blocks have been indexed yet")]
+    NotIndexed,
     #[error("Database error: {0}")]
     Database(String),
 }
@@ -13,8 +20,24 @@ pub enum TxError {
 impl IntoResponse for TxError {
     fn into_response(self) -> Response {
         let status_code = match &self {
+            TxError::NotFound(_) | TxError::NotIndexed => StatusCode::NOT_FOUND,
+            TxError::InvalidRange(_) => StatusCode::BAD_REQUEST,
             TxError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
     }
 }
+
+impl From<TxError> for Status {
+    fn from(err: TxError) -> Self {
+        match &err {
+            TxError::NotFound(_) | TxError::NotIndexed => {
+                Status::not_found(err.to_string())
+            }
+            TxError::InvalidRange(_) => {
+                Status::invalid_argument(err.to_string())
+            }
+            TxError::Database(_) => Status::internal(err.to_string()),
+        }
+    }
+}
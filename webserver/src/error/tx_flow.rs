@@ -0,0 +1,33 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::response::api::ApiErrorResponse;
+
+#[derive(Error, Debug)]
+pub enum TxFlowError {
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+    #[error("No block found from {0}")]
+    NoBlockFrom(String),
+    #[error("Blocks are not indexed as far back as {0}")]
+    BlocksNotIndexed(String),
+    #[error("No blocks have been indexed yet")]
+    NotIndexed,
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl IntoResponse for TxFlowError {
+    fn into_response(self) -> Response {
+        let status_code = match self {
+            TxFlowError::InvalidRange(_) => StatusCode::BAD_REQUEST,
+            TxFlowError::NoBlockFrom(_)
+            | TxFlowError::BlocksNotIndexed(_)
+            | TxFlowError::NotIndexed => StatusCode::NOT_FOUND,
+            TxFlowError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+    }
+}
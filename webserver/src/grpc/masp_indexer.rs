@@ -0,0 +1,300 @@
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use shared::error::InspectWrap;
+use shared::height::BlockHeight;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status};
+
+use crate::error::namada_state::NamadaStateError;
+use crate::error::notes_index::NotesIndexError;
+use crate::error::tree::TreeError;
+use crate::error::tx::TxError;
+use crate::error::witness_map::WitnessMapError;
+use crate::grpc::proto::masp_indexer_server::MaspIndexer;
+use crate::grpc::proto::{
+    BlockIndexRequest, BlockIndexResponse, CommitmentTreeRequest,
+    CommitmentTreeResponse, LatestHeightRequest, LatestHeightResponse, Note,
+    NotesIndexRequest, Tx, TxSlot, TxsRequest, Witness, WitnessMapRequest,
+};
+use crate::state::common::CommonState;
+
+/// Number of notes index entries fetched from the db at a time.
+const NOTES_INDEX_PAGE_SIZE: u64 = 10_000;
+
+/// Metadata holding the block height of a streamed witness map.
+const BLOCK_HEIGHT_METADATA: &str = "x-block-height";
+
+/// The gRPC counterpart of the REST handlers, sharing their services.
+pub struct MaspIndexerGrpc {
+    state: CommonState,
+}
+
+impl MaspIndexerGrpc {
+    pub fn new(state: CommonState) -> Self {
+        Self { state }
+    }
+
+    /// Resolve the last block height of a range, defaulting to the last
+    /// indexed block.
+    async fn to_block_height(
+        &self,
+        from_block_height: u64,
+        to_block_height: Option<u64>,
+    ) -> Result<u64, Status> {
+        let latest_height = self
+            .state
+            .namada_state_service
+            .get_latest_height()
+            .await
+            .inspect_wrap("get_latest_height", |err| {
+                TxError::Database(err.to_string())
+            })?
+            .ok_or(TxError::NotIndexed)?
+            .0;
+
+        let to_block_height = to_block_height.unwrap_or(latest_height);
+
+        if from_block_height > to_block_height {
+            return Err(TxError::InvalidRange(format!(
+                "from_height {from_block_height} is above to_height \
+                 {to_block_height}"
+            ))
+            .into());
+        }
+        if to_block_height > latest_height {
+            return Err(TxError::InvalidRange(format!(
+                "to_height {to_block_height} exceeds the latest block height \
+                 {latest_height}"
+            ))
+            .into());
+        }
+
+        Ok(to_block_height)
+    }
+}
+
+#[tonic::async_trait]
+impl MaspIndexer for MaspIndexerGrpc {
+    type StreamNotesIndexStream = BoxStream<'static, Result<Note, Status>>;
+    type StreamTxsStream = BoxStream<'static, Result<Tx, Status>>;
+    type StreamWitnessMapStream = BoxStream<'static, Result<Witness, Status>>;
+
+    async fn get_latest_height(
+        &self,
+        _request: Request<LatestHeightRequest>,
+    ) -> Result<Response<LatestHeightResponse>, Status> {
+        let maybe_height = self
+            .state
+            .namada_state_service
+            .get_latest_height()
+            .await
+            .inspect_wrap("get_latest_height", |err| {
+                NamadaStateError::Database(err.to_string())
+            })?;
+
+        Ok(Response::new(LatestHeightResponse {
+            block_height: maybe_height.map(|h| h.0).unwrap_or_default(),
+        }))
+    }
+
+    async fn get_block_index(
+        &self,
+        _request: Request<BlockIndexRequest>,
+    ) -> Result<Response<BlockIndexResponse>, Status> {
+        let (height, index) = self
+            .state
+            .namada_state_service
+            .get_block_index()
+            .await
+            .inspect_wrap("get_block_index", |err| {
+                NamadaStateError::Database(err.to_string())
+            })?
+            .ok_or(NamadaStateError::BlockIndexNotFound)?;
+
+        let index = bincode::serialize(&index).map_err(|err| {
+            Status::internal(format!("Failed to encode the block index: {err}"))
+        })?;
+
+        Ok(Response::new(BlockIndexResponse {
+            block_height: height.0,
+            index,
+        }))
+    }
+
+    async fn stream_txs(
+        &self,
+        request: Request<TxsRequest>,
+    ) -> Result<Response<Self::StreamTxsStream>, Status> {
+        let TxsRequest {
+            from_height: from_block_height,
+            to_height,
+        } = request.into_inner();
+        let to_block_height =
+            self.to_block_height(from_block_height, to_height).await?;
+
+        let txs = self
+            .state
+            .tx_service
+            .stream_txs(from_block_height, to_block_height)
+            .await
+            .inspect_wrap("stream_txs", |err| {
+                TxError::Database(err.to_string())
+            })?;
+
+        Ok(Response::new(
+            txs.map(|txs| {
+                let (batch, block_height, block_index) = txs
+                    .inspect_wrap("stream_txs", |err| {
+                        TxError::Database(err.to_string())
+                    })?;
+
+                Ok(Tx {
+                    block_height,
+                    block_index,
+                    batch: batch
+                        .into_iter()
+                        .map(|(masp_tx_index, is_masp_fee_payment, bytes)| {
+                            TxSlot {
+                                masp_tx_index,
+                                is_masp_fee_payment,
+                                bytes,
+                            }
+                        })
+                        .collect(),
+                })
+            })
+            .boxed(),
+        ))
+    }
+
+    async fn get_commitment_tree(
+        &self,
+        request: Request<CommitmentTreeRequest>,
+    ) -> Result<Response<CommitmentTreeResponse>, Status> {
+        let height = request.into_inner().height;
+
+        let maybe_commitment_tree = self
+            .state
+            .tree_service
+            .get_at_height(height)
+            .await
+            .inspect_wrap("get_commitment_tree", |err| {
+                TreeError::Database(err.to_string())
+            })?;
+
+        let (commitment_tree, block_height) = maybe_commitment_tree
+            .unwrap_or_else(|| (shared::commitment_tree::empty(), height));
+
+        Ok(Response::new(CommitmentTreeResponse {
+            commitment_tree,
+            block_height,
+        }))
+    }
+
+    async fn stream_witness_map(
+        &self,
+        request: Request<WitnessMapRequest>,
+    ) -> Result<Response<Self::StreamWitnessMapStream>, Status> {
+        let height = request.into_inner().height;
+
+        let witnesses_and_height = self
+            .state
+            .witness_map_service
+            .stream_witnesses(BlockHeight(height))
+            .await
+            .inspect_wrap("stream_witness_map", |err| {
+                WitnessMapError::Database(err.to_string())
+            })?;
+
+        let (witnesses, block_height) = witnesses_and_height
+            .unwrap_or_else(|| (futures::stream::empty().boxed(), height));
+
+        let mut response = Response::new(
+            witnesses
+                .map(|(bytes, index)| Ok(Witness { bytes, index }))
+                .boxed(),
+        );
+        response
+            .metadata_mut()
+            .insert(BLOCK_HEIGHT_METADATA, MetadataValue::from(block_height));
+
+        Ok(response)
+    }
+
+    async fn stream_notes_index(
+        &self,
+        request: Request<NotesIndexRequest>,
+    ) -> Result<Response<Self::StreamNotesIndexStream>, Status> {
+        let NotesIndexRequest {
+            from_height: from_block_height,
+            to_height,
+        } = request.into_inner();
+        let to_block_height =
+            self.to_block_height(from_block_height, to_height).await?;
+
+        let notes_index_service = self.state.notes_index_service.clone();
+
+        // NB: the notes index is fetched a page at a time, each starting
+        // after the last note position of the previous one
+        let pages = futures::stream::try_unfold(
+            Some(None),
+            move |after: Option<Option<u64>>| {
+                let notes_index_service = notes_index_service.clone();
+
+                async move {
+                    let Some(after) = after else {
+                        return Ok(None);
+                    };
+
+                    let notes_index = notes_index_service
+                        .get_notes_index(
+                            from_block_height,
+                            to_block_height,
+                            after,
+                            Some(NOTES_INDEX_PAGE_SIZE),
+                        )
+                        .await
+                        .inspect_wrap("stream_notes_index", |err| {
+                            NotesIndexError::Database(err.to_string())
+                        })?;
+
+                    // NB: a full page might be followed by more entries
+                    let next = (notes_index.len() as u64
+                        == NOTES_INDEX_PAGE_SIZE)
+                        .then(|| notes_index.last())
+                        .flatten()
+                        .map(|(_, _, _, note_position, _)| {
+                            Some(*note_position)
+                        });
+
+                    Ok::<_, Status>(Some((notes_index, next)))
+                }
+            },
+        );
+
+        Ok(Response::new(
+            pages
+                .map_ok(|notes_index| {
+                    futures::stream::iter(notes_index.into_iter().map(
+                        |(
+                            block_height,
+                            block_index,
+                            masp_tx_index,
+                            note_position,
+                            is_masp_fee_payment,
+                        )| {
+                            Ok(Note {
+                                block_height,
+                                block_index,
+                                masp_tx_index,
+                                note_position,
+                                is_masp_fee_payment,
+                            })
+                        },
+                    ))
+                })
+                .try_flatten()
+                .boxed(),
+        ))
+    }
+}
@@ -0,0 +1,6 @@
+pub mod masp_indexer;
+
+/// Messages and services generated from `proto/masp_indexer.proto`.
+pub mod proto {
+    tonic::include_proto!("masp_indexer.v1");
+}
@@ -0,0 +1,51 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::asset::AssetQueryParams;
+use crate::error::asset::AssetError;
+use crate::response::asset::{AssetResponse, AssetsResponse};
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_asset(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Path(asset_type): Path<String>,
+) -> Result<Json<AssetResponse>, AssetError> {
+    // NB: asset types are stored in lowercase hex
+    let asset_type = asset_type.to_lowercase();
+
+    let maybe_asset = state
+        .asset_service
+        .get_asset(asset_type.clone())
+        .await
+        .inspect_wrap("get_asset", |err| {
+        AssetError::Database(err.to_string())
+    })?;
+
+    if let Some(asset) = maybe_asset {
+        Ok(Json(AssetResponse::new(asset)))
+    } else {
+        Err(AssetError::AssetTypeNotFound(asset_type))
+    }
+}
+
+#[debug_handler]
+pub async fn get_assets(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<AssetQueryParams>,
+) -> Result<Json<AssetsResponse>, AssetError> {
+    let assets = state
+        .asset_service
+        .get_assets(query_params.token)
+        .await
+        .inspect_wrap("get_assets", |err| {
+            AssetError::Database(err.to_string())
+        })?;
+
+    Ok(Json(AssetsResponse::new(assets)))
+}
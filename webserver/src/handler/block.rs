@@ -0,0 +1,59 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::block::BlockQueryParams;
+use crate::error::block::BlockError;
+use crate::response::block::BlockResponse;
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_block(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Path(block_height): Path<u64>,
+) -> Result<Json<BlockResponse>, BlockError> {
+    let maybe_block = state
+        .block_service
+        .get_block(block_height)
+        .await
+        .inspect_wrap("get_block", |err| {
+            BlockError::Database(err.to_string())
+        })?;
+
+    if let Some(block) = maybe_block {
+        Ok(Json(BlockResponse::new(block)))
+    } else {
+        Err(BlockError::BlockNotFound(block_height))
+    }
+}
+
+#[debug_handler]
+pub async fn get_first_block_from(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<BlockQueryParams>,
+) -> Result<Json<BlockResponse>, BlockError> {
+    let timestamp = query_params.timestamp;
+
+    let maybe_block = state
+        .block_service
+        .get_first_block_from(timestamp.naive_utc())
+        .await
+        .inspect_wrap("get_first_block_from", |err| {
+            BlockError::Database(err.to_string())
+        })?;
+
+    match maybe_block {
+        Some((block, true)) => Ok(Json(BlockResponse::new(block))),
+        // NB: MASP txs preceding the returned block might have been
+        // indexed without their block, and thus could be more recent
+        // than the requested timestamp
+        Some((_, false)) => {
+            Err(BlockError::BlocksNotIndexed(timestamp.to_rfc3339()))
+        }
+        None => Err(BlockError::NoBlockFrom(timestamp.to_rfc3339())),
+    }
+}
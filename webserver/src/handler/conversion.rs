@@ -0,0 +1,37 @@
+use axum::extract::{Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use shared::error::InspectWrap;
+
+use crate::dto::conversion::ConversionQueryParams;
+use crate::error::conversion::ConversionError;
+use crate::response::conversion::ConversionsResponse;
+use crate::response::encoding::{Encoded, Encoding};
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_conversions(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Query(query_params): Query<ConversionQueryParams>,
+) -> Result<Encoded<ConversionsResponse>, ConversionError> {
+    let maybe_conversions = state
+        .conversion_service
+        .get_conversions(query_params.epoch)
+        .await
+        .inspect_wrap("get_conversions", |err| {
+            ConversionError::Database(err.to_string())
+        })?;
+
+    match (maybe_conversions, query_params.epoch) {
+        (Some((masp_epoch, conversions)), _) => Ok(Encoded(
+            encoding,
+            ConversionsResponse::new(masp_epoch, conversions),
+        )),
+        (None, Some(masp_epoch)) => {
+            Err(ConversionError::EpochNotFound(masp_epoch))
+        }
+        (None, None) => Err(ConversionError::NotIndexed),
+    }
+}
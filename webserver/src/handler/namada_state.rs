@@ -5,7 +5,10 @@ use axum_trace_id::TraceId;
 use shared::error::InspectWrap;
 
 use crate::error::namada_state::NamadaStateError;
-use crate::response::namada_state::{BlockIndexResponse, LatestHeightResponse};
+use crate::response::encoding::{Encoded, Encoding};
+use crate::response::namada_state::{
+    BlockIndexResponse, LatestHeightResponse, NetworkIdentityResponse,
+};
 use crate::state::common::CommonState;
 
 #[debug_handler]
@@ -30,7 +33,8 @@ pub async fn get_latest_height(
 pub async fn get_block_index(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
-) -> Result<Json<BlockIndexResponse>, NamadaStateError> {
+    encoding: Encoding,
+) -> Result<Encoded<BlockIndexResponse>, NamadaStateError> {
     let maybe_block_index = state
         .namada_state_service
         .get_block_index()
@@ -40,11 +44,40 @@ pub async fn get_block_index(
         })?;
 
     if let Some((height, index)) = maybe_block_index {
-        Ok(Json(BlockIndexResponse {
-            block_height: height.0,
-            index,
-        }))
+        Ok(Encoded(
+            encoding,
+            BlockIndexResponse {
+                block_height: height.0,
+                index,
+            },
+        ))
     } else {
         Err(NamadaStateError::BlockIndexNotFound)
     }
 }
+
+#[debug_handler]
+pub async fn get_network_identity(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+) -> Result<Json<NetworkIdentityResponse>, NamadaStateError> {
+    let maybe_network = state
+        .namada_state_service
+        .get_network_identity()
+        .await
+        .inspect_wrap("get_network_identity", |err| {
+            NamadaStateError::Database(err.to_string())
+        })?;
+
+    if let Some((chain_id, first_block_height, first_block_hash)) =
+        maybe_network
+    {
+        Ok(Json(NetworkIdentityResponse {
+            chain_id,
+            first_block_height: first_block_height.0,
+            first_block_hash,
+        }))
+    } else {
+        Err(NamadaStateError::NetworkIdentityNotFound)
+    }
+}
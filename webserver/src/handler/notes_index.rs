@@ -9,21 +9,50 @@ use crate::error::notes_index::NotesIndexError;
 use crate::response::notes_index::NotesIndexResponse;
 use crate::state::common::CommonState;
 
+/// Maximum number of notes index entries returned in a page.
+const MAX_LIMIT: u64 = 10_000;
+
 #[debug_handler]
 pub async fn get_notes_index(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
     Query(query_params): Query<NotesIndexQueryParams>,
 ) -> Result<Json<NotesIndexResponse>, NotesIndexError> {
-    let from_block_height = query_params.height;
+    let to_block_height = query_params.height;
+    let from_block_height = query_params.from.unwrap_or_default();
+
+    if from_block_height > to_block_height {
+        return Err(NotesIndexError::InvalidRange(format!(
+            "from {from_block_height} is above height {to_block_height}"
+        )));
+    }
+    if let Some(limit) = query_params.limit {
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(NotesIndexError::InvalidRange(format!(
+                "limit must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+    }
 
     let notes_index = state
         .notes_index_service
-        .get_notes_index(from_block_height)
+        .get_notes_index(
+            from_block_height,
+            to_block_height,
+            query_params.after,
+            query_params.limit,
+        )
         .await
         .inspect_wrap("get_notes_index", |err| {
             NotesIndexError::Database(err.to_string())
         })?;
 
-    Ok(Json(NotesIndexResponse::new(notes_index)))
+    // NB: a full page might be followed by more entries
+    let next = query_params
+        .limit
+        .filter(|limit| notes_index.len() as u64 == *limit)
+        .and_then(|_| notes_index.last())
+        .map(|(_, _, _, note_position, _)| *note_position);
+
+    Ok(Json(NotesIndexResponse::new(notes_index, next)))
 }
@@ -0,0 +1,55 @@
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use futures::Stream;
+use orm::notification::Notification;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::response::notification::{BlockIndexEvent, HeightEvent};
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_events(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let notifications = state.notification_service.subscribe();
+
+    let events =
+        futures::stream::unfold(notifications, |mut notifications| async {
+            loop {
+                let event = match notifications.recv().await {
+                    Ok(Notification::Height {
+                        block_height,
+                        num_masp_txs,
+                        commitment_tree_size,
+                    }) => Event::default().event("height").json_data(
+                        HeightEvent {
+                            block_height,
+                            num_masp_txs,
+                            commitment_tree_size,
+                        },
+                    ),
+                    Ok(Notification::BlockIndex { block_height }) => {
+                        Event::default()
+                            .event("block-index")
+                            .json_data(BlockIndexEvent { block_height })
+                    }
+                    // NB: later events supersede the skipped ones
+                    Err(RecvError::Lagged(num_skipped)) => {
+                        tracing::debug!(
+                            num_skipped,
+                            "Skipped events of a slow subscriber"
+                        );
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+
+                return Some((event.map_err(axum::Error::new), notifications));
+            }
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -1,4 +1,3 @@
-use axum::Json;
 use axum::extract::{Query, State};
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
@@ -7,6 +6,7 @@ use shared::error::InspectWrap;
 
 use crate::dto::tree::TreeQueryParams;
 use crate::error::tree::TreeError;
+use crate::response::encoding::{Encoded, Encoding};
 use crate::response::tree::TreeResponse;
 use crate::state::common::CommonState;
 
@@ -14,8 +14,9 @@ use crate::state::common::CommonState;
 pub async fn get_commitment_tree(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
+    encoding: Encoding,
     Query(query_params): Query<TreeQueryParams>,
-) -> Result<Json<TreeResponse>, TreeError> {
+) -> Result<Encoded<TreeResponse>, TreeError> {
     let maybe_commitment_tree = state
         .tree_service
         .get_at_height(query_params.height)
@@ -27,8 +28,11 @@ pub async fn get_commitment_tree(
     let (commitment_tree, block_height) = maybe_commitment_tree
         .unwrap_or_else(|| (empty_tree(), query_params.height));
 
-    Ok(Json(TreeResponse {
-        commitment_tree,
-        block_height,
-    }))
+    Ok(Encoded(
+        encoding,
+        TreeResponse {
+            commitment_tree,
+            block_height,
+        },
+    ))
 }
@@ -1,20 +1,24 @@
-use axum::Json;
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
+use futures::StreamExt;
 use shared::error::InspectWrap;
 
-use crate::dto::txs::TxQueryParams;
+use crate::dto::txs::{TxQueryParams, TxStreamQueryParams};
 use crate::error::tx::TxError;
-use crate::response::tx::TxResponse;
+use crate::response::encoding::{Encoded, Encoding};
+use crate::response::stream::Streamed;
+use crate::response::tx::{Tx, TxByHashResponse, TxResponse};
 use crate::state::common::CommonState;
 
 #[debug_handler]
 pub async fn get_tx(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
+    encoding: Encoding,
     Query(query_params): Query<TxQueryParams>,
-) -> Result<Json<TxResponse>, TxError> {
+) -> Result<Encoded<TxResponse>, TxError> {
     let from_block_height = query_params.height;
     let to_block_height = from_block_height + query_params.height_offset;
 
@@ -24,5 +28,81 @@ pub async fn get_tx(
         .await
         .inspect_wrap("get_tx", |err| TxError::Database(err.to_string()))?;
 
-    Ok(Json(TxResponse::new(txs)))
+    Ok(Encoded(encoding, TxResponse::new(txs)))
+}
+
+#[debug_handler]
+pub async fn get_tx_by_hash(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Path(tx_hash): Path<String>,
+) -> Result<Encoded<TxByHashResponse>, TxError> {
+    // NB: tx hashes are stored in lowercase hex
+    let tx_hash = tx_hash.to_lowercase();
+
+    let txs = state
+        .tx_service
+        .get_txs_by_hash(tx_hash.clone())
+        .await
+        .inspect_wrap("get_tx_by_hash", |err| {
+            TxError::Database(err.to_string())
+        })?;
+
+    if txs.is_empty() {
+        return Err(TxError::NotFound(tx_hash));
+    }
+
+    Ok(Encoded(encoding, TxByHashResponse::new(tx_hash, txs)))
+}
+
+#[debug_handler]
+pub async fn get_tx_stream(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Query(query_params): Query<TxStreamQueryParams>,
+) -> Result<impl IntoResponse, TxError> {
+    let latest_height = state
+        .namada_state_service
+        .get_latest_height()
+        .await
+        .inspect_wrap("get_latest_height", |err| {
+            TxError::Database(err.to_string())
+        })?
+        .ok_or(TxError::NotIndexed)?
+        .0;
+
+    let from_block_height = query_params.from_height;
+    let to_block_height = query_params.to_height.unwrap_or(latest_height);
+
+    if from_block_height > to_block_height {
+        return Err(TxError::InvalidRange(format!(
+            "from_height {from_block_height} is above to_height \
+             {to_block_height}"
+        )));
+    }
+    if to_block_height > latest_height {
+        return Err(TxError::InvalidRange(format!(
+            "to_height {to_block_height} exceeds the latest block height \
+             {latest_height}"
+        )));
+    }
+
+    let txs = state
+        .tx_service
+        .stream_txs(from_block_height, to_block_height)
+        .await
+        .inspect_wrap("get_tx_stream", |err| {
+            TxError::Database(err.to_string())
+        })?;
+
+    Ok(Streamed(
+        encoding,
+        txs.map(|txs| {
+            txs.map(|(batch, block_height, block_index)| {
+                Tx::new(batch, block_height, block_index)
+            })
+        }),
+    ))
 }
@@ -0,0 +1,101 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use axum_macros::debug_handler;
+use axum_trace_id::TraceId;
+use chrono::{DateTime, Utc};
+use shared::error::InspectWrap;
+
+use crate::dto::tx_flow::TxFlowQueryParams;
+use crate::error::tx_flow::TxFlowError;
+use crate::response::tx_flow::TxFlowsResponse;
+use crate::state::common::CommonState;
+
+#[debug_handler]
+pub async fn get_flows(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    Query(query_params): Query<TxFlowQueryParams>,
+) -> Result<Json<TxFlowsResponse>, TxFlowError> {
+    let from_height =
+        match (query_params.from_height, query_params.from_timestamp) {
+            (Some(_), Some(_)) => {
+                return Err(TxFlowError::InvalidRange(
+                    "from_height and from_timestamp are mutually exclusive"
+                        .to_string(),
+                ));
+            }
+            (Some(from_height), None) => from_height,
+            (None, Some(from_timestamp)) => {
+                first_height_from(&state, from_timestamp)
+                    .await?
+                    .ok_or_else(|| {
+                        TxFlowError::NoBlockFrom(from_timestamp.to_rfc3339())
+                    })?
+            }
+            (None, None) => 0,
+        };
+
+    let to_height = match (query_params.to_height, query_params.to_timestamp) {
+        (Some(_), Some(_)) => {
+            return Err(TxFlowError::InvalidRange(
+                "to_height and to_timestamp are mutually exclusive".to_string(),
+            ));
+        }
+        (Some(to_height), None) => to_height,
+        // NB: exclude the blocks from `to_timestamp` onwards
+        (None, Some(to_timestamp)) => {
+            match first_height_from(&state, to_timestamp).await? {
+                Some(height) => height.saturating_sub(1),
+                None => latest_height(&state).await?,
+            }
+        }
+        (None, None) => latest_height(&state).await?,
+    };
+
+    let flows = state
+        .tx_flow_service
+        .get_aggregated_flows(from_height, to_height, query_params.asset_type)
+        .await
+        .inspect_wrap("get_flows", |err| {
+            TxFlowError::Database(err.to_string())
+        })?;
+
+    Ok(Json(TxFlowsResponse::new(from_height, to_height, flows)))
+}
+
+/// Get the height of the first block at or after `timestamp`.
+async fn first_height_from(
+    state: &CommonState,
+    timestamp: DateTime<Utc>,
+) -> Result<Option<u64>, TxFlowError> {
+    let maybe_block = state
+        .block_service
+        .get_first_block_from(timestamp.naive_utc())
+        .await
+        .inspect_wrap("get_first_block_from", |err| {
+            TxFlowError::Database(err.to_string())
+        })?;
+
+    match maybe_block {
+        Some(((block_height, ..), true)) => Ok(Some(block_height.0)),
+        // NB: MASP txs preceding the block might have been indexed
+        // without their block, and thus could be more recent than
+        // `timestamp`
+        Some((_, false)) => {
+            Err(TxFlowError::BlocksNotIndexed(timestamp.to_rfc3339()))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn latest_height(state: &CommonState) -> Result<u64, TxFlowError> {
+    state
+        .namada_state_service
+        .get_latest_height()
+        .await
+        .inspect_wrap("get_latest_height", |err| {
+            TxFlowError::Database(err.to_string())
+        })?
+        .map(|height| height.0)
+        .ok_or(TxFlowError::NotIndexed)
+}
@@ -1,21 +1,30 @@
-use axum::Json;
 use axum::extract::{Query, State};
+use axum::http::{HeaderName, HeaderValue};
+use axum::response::IntoResponse;
 use axum_macros::debug_handler;
 use axum_trace_id::TraceId;
+use futures::StreamExt;
 use shared::error::InspectWrap;
 use shared::height::BlockHeight;
 
 use crate::dto::witness::WitnessMapQueryParams;
 use crate::error::witness_map::WitnessMapError;
-use crate::response::witness_map::WitnessMapResponse;
+use crate::response::encoding::{Encoded, Encoding};
+use crate::response::stream::Streamed;
+use crate::response::witness_map::{Witness, WitnessMapResponse};
 use crate::state::common::CommonState;
 
+/// Header holding the block height of a streamed witness map.
+const BLOCK_HEIGHT_HEADER: HeaderName =
+    HeaderName::from_static("x-block-height");
+
 #[debug_handler]
 pub async fn get_witness_map(
     _trace_id: TraceId<String>,
     State(state): State<CommonState>,
+    encoding: Encoding,
     Query(query_params): Query<WitnessMapQueryParams>,
-) -> Result<Json<WitnessMapResponse>, WitnessMapError> {
+) -> Result<Encoded<WitnessMapResponse>, WitnessMapError> {
     let witnesses_and_height = state
         .witness_map_service
         .get_witnesses(BlockHeight(query_params.height))
@@ -27,8 +36,39 @@ pub async fn get_witness_map(
     let (witnesses, block_height) =
         witnesses_and_height.unwrap_or((Vec::new(), query_params.height));
 
-    Ok(Json(WitnessMapResponse::new(
-        BlockHeight(block_height),
-        witnesses,
-    )))
+    Ok(Encoded(
+        encoding,
+        WitnessMapResponse::new(BlockHeight(block_height), witnesses),
+    ))
+}
+
+#[debug_handler]
+pub async fn get_witness_map_stream(
+    _trace_id: TraceId<String>,
+    State(state): State<CommonState>,
+    encoding: Encoding,
+    Query(query_params): Query<WitnessMapQueryParams>,
+) -> Result<impl IntoResponse, WitnessMapError> {
+    let witnesses_and_height = state
+        .witness_map_service
+        .stream_witnesses(BlockHeight(query_params.height))
+        .await
+        .inspect_wrap("get_witness_map_stream", |err| {
+            WitnessMapError::Database(err.to_string())
+        })?;
+
+    let (witnesses, block_height) = witnesses_and_height.unwrap_or_else(|| {
+        (futures::stream::empty().boxed(), query_params.height)
+    });
+
+    // NB: witnesses are serialized as they are computed, such that the
+    // witness map is never held in memory as a whole
+    Ok((
+        [(BLOCK_HEIGHT_HEADER, HeaderValue::from(block_height))],
+        Streamed(
+            encoding,
+            witnesses
+                .map(|(bytes, index)| anyhow::Ok(Witness { bytes, index })),
+        ),
+    ))
 }
@@ -0,0 +1,14 @@
+#![allow(async_fn_in_trait)]
+
+pub mod app;
+pub mod appstate;
+pub mod config;
+pub mod dto;
+pub mod error;
+pub mod grpc;
+pub mod handler;
+pub mod repository;
+pub mod response;
+pub mod service;
+pub mod state;
+pub mod utils;
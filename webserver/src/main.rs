@@ -1,23 +1,9 @@
-#![allow(async_fn_in_trait)]
-
-pub mod app;
-pub mod appstate;
-pub mod config;
-pub mod dto;
-pub mod error;
-pub mod handler;
-pub mod repository;
-pub mod response;
-pub mod service;
-pub mod state;
-pub mod utils;
-
 use std::sync::Arc;
 
 use clap::Parser;
-
-use crate::app::ApplicationServer;
-use crate::config::AppConfig;
+use shared::exit_handle;
+use webserver::app::ApplicationServer;
+use webserver::config::AppConfig;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -27,5 +13,7 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(tracing::Level::INFO)
         .init();
 
+    exit_handle::spawn_signal_handler();
+
     ApplicationServer::serve(config).await
 }
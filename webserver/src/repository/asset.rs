@@ -0,0 +1,94 @@
+use anyhow::Context;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgSortExpressionMethods, QueryDsl,
+    RunQueryDsl, SelectableHelper,
+};
+use orm::asset::AssetDb;
+use orm::schema::asset;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct AssetRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait AssetRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    async fn get_asset(
+        &self,
+        asset_type: String,
+    ) -> anyhow::Result<Option<AssetDb>>;
+
+    /// Get the registered assets, optionally restricted to those of
+    /// the given token.
+    async fn get_assets(
+        &self,
+        token: Option<String>,
+    ) -> anyhow::Result<Vec<AssetDb>>;
+}
+
+impl AssetRepositoryTrait for AssetRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_asset(
+        &self,
+        asset_type: String,
+    ) -> anyhow::Result<Option<AssetDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            asset::table
+                .filter(asset::dsl::asset_type.eq(&asset_type))
+                .select(AssetDb::as_select())
+                .first(conn)
+                .optional()
+                .with_context(|| {
+                    format!(
+                        "Failed to get the asset type {asset_type} from the \
+                         database"
+                    )
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_assets(
+        &self,
+        token: Option<String>,
+    ) -> anyhow::Result<Vec<AssetDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            let mut query = asset::table
+                .order_by((
+                    asset::dsl::token.asc(),
+                    asset::dsl::epoch.asc().nulls_first(),
+                    asset::dsl::digit_pos.asc(),
+                ))
+                .select(AssetDb::as_select())
+                .into_boxed();
+
+            if let Some(token) = token {
+                query = query.filter(asset::dsl::token.eq(token));
+            }
+
+            query
+                .load(conn)
+                .context("Failed to get the asset types from the database")
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
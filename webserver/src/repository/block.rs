@@ -0,0 +1,119 @@
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::{
+    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+use orm::block::BlockDb;
+use orm::schema::{block, tx};
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct BlockRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait BlockRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    async fn get_block(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockDb>>;
+
+    /// Get the first block with a timestamp at or after `timestamp`,
+    /// along with whether the blocks of every preceding MASP tx are
+    /// stored.
+    async fn get_first_block_from(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<(BlockDb, bool)>>;
+}
+
+impl BlockRepositoryTrait for BlockRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_block(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Option<BlockDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            block::table
+                .filter(block::dsl::block_height.eq(block_height))
+                .select(BlockDb::as_select())
+                .first(conn)
+                .optional()
+                .with_context(|| {
+                    format!(
+                        "Failed to get the block at height {block_height} \
+                         from the database"
+                    )
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_first_block_from(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<Option<(BlockDb, bool)>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            conn.build_transaction().read_only().run(move |conn| {
+                let Some(first_block) = block::table
+                    .filter(block::dsl::timestamp.ge(timestamp))
+                    .order_by(block::dsl::timestamp.asc())
+                    .select(BlockDb::as_select())
+                    .first(conn)
+                    .optional()
+                    .with_context(|| {
+                        format!(
+                            "Failed to get the first block from {timestamp} \
+                             from the database"
+                        )
+                    })?
+                else {
+                    return Ok(None);
+                };
+
+                // NB: blocks are stored from the first block committed
+                // after the table was introduced, thus an earlier block
+                // being stored means the ones that follow are too
+                let (has_earlier_block, has_earlier_tx) = diesel::select((
+                    exists(block::table.filter(
+                        block::dsl::block_height.lt(first_block.block_height),
+                    )),
+                    exists(tx::table.filter(
+                        tx::dsl::block_height.lt(first_block.block_height),
+                    )),
+                ))
+                .get_result::<(bool, bool)>(conn)
+                .context(
+                    "Failed to check the blocks preceding the first block",
+                )?;
+
+                anyhow::Ok(Some((
+                    first_block,
+                    has_earlier_block || !has_earlier_tx,
+                )))
+            })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
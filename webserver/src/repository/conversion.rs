@@ -0,0 +1,86 @@
+use anyhow::Context;
+use diesel::dsl::max;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use orm::conversion::ConversionDb;
+use orm::schema::conversion;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct ConversionRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait ConversionRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Get the conversions of the last indexed MASP epoch at or below the
+    /// given one, or of the last indexed one if unspecified, along with
+    /// the MASP epoch.
+    async fn get_conversions(
+        &self,
+        masp_epoch: Option<i32>,
+    ) -> anyhow::Result<Option<(i32, Vec<ConversionDb>)>>;
+}
+
+impl ConversionRepositoryTrait for ConversionRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_conversions(
+        &self,
+        masp_epoch: Option<i32>,
+    ) -> anyhow::Result<Option<(i32, Vec<ConversionDb>)>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            conn.build_transaction().read_only().run(move |conn| {
+                let mut query = conversion::table
+                    .select(max(conversion::dsl::masp_epoch))
+                    .into_boxed();
+
+                // NB: the conversion state is only indexed at the MASP
+                // epochs observed by the crawler, so the closest one below
+                // is served in place of a missed epoch
+                if let Some(masp_epoch) = masp_epoch {
+                    query = query
+                        .filter(conversion::dsl::masp_epoch.le(masp_epoch));
+                }
+
+                let last_masp_epoch =
+                    query.first::<Option<i32>>(conn).context(
+                        "Failed to get the last MASP epoch of the conversions \
+                         from the database",
+                    )?;
+
+                let Some(masp_epoch) = last_masp_epoch else {
+                    return Ok(None);
+                };
+
+                let conversions = conversion::table
+                    .filter(conversion::dsl::masp_epoch.eq(masp_epoch))
+                    .order_by(conversion::dsl::asset_type.asc())
+                    .select(ConversionDb::as_select())
+                    .load(conn)
+                    .with_context(|| {
+                        format!(
+                            "Failed to get the conversions of MASP epoch \
+                             {masp_epoch} from the database"
+                        )
+                    })?;
+
+                anyhow::Ok(
+                    (!conversions.is_empty())
+                        .then_some((masp_epoch, conversions)),
+                )
+            })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Context;
 use diesel::dsl::max;
 use diesel::{OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use orm::chain_state::NetworkIdentityDb;
 use shared::error::ContextDbInteractError;
 use shared::height::BlockHeight;
 use xorf::BinaryFuse16;
@@ -20,6 +21,10 @@ pub trait NamadaStateRepositoryTrait {
     async fn get_block_index(
         &self,
     ) -> anyhow::Result<Option<(i32, BinaryFuse16)>>;
+
+    async fn get_network_identity(
+        &self,
+    ) -> anyhow::Result<Option<NetworkIdentityDb>>;
 }
 
 impl NamadaStateRepositoryTrait for NamadaStateRepository {
@@ -93,4 +98,25 @@ impl NamadaStateRepositoryTrait for NamadaStateRepository {
                 .transpose()
         })
     }
+
+    async fn get_network_identity(
+        &self,
+    ) -> anyhow::Result<Option<NetworkIdentityDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            use orm::schema::chain_state;
+
+            chain_state::table
+                .select(NetworkIdentityDb::as_select())
+                .first(conn)
+                .optional()
+        })
+        .await
+        .context_db_interact_error()?
+        .context("Failed to get network identity from db")
+    }
 }
@@ -13,7 +13,19 @@ pub struct NotesIndexRepository {
 
 pub trait NotesIndexRepositoryTrait {
     fn new(app_state: AppState) -> Self;
+    /// Get the notes index entries from `from_block_height` up to
+    /// `to_block_height`, ordered by note position. Only the entries
+    /// after note position `after` are returned, up to `limit` of them.
     async fn get_notes_index(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        after: Option<i32>,
+        limit: Option<i64>,
+    ) -> anyhow::Result<Vec<NotesIndexDb>>;
+    /// Get the notes index entries at `block_height`, preceded by the
+    /// last entry of the previous blocks, ordered by note position.
+    async fn get_block_notes_index(
         &self,
         block_height: i32,
     ) -> anyhow::Result<Vec<NotesIndexDb>>;
@@ -26,7 +38,10 @@ impl NotesIndexRepositoryTrait for NotesIndexRepository {
 
     async fn get_notes_index(
         &self,
-        block_height: i32,
+        from_block_height: i32,
+        to_block_height: i32,
+        after: Option<i32>,
+        limit: Option<i64>,
     ) -> anyhow::Result<Vec<NotesIndexDb>> {
         let conn = self.app_state.get_db_connection().await.context(
             "Failed to retrieve connection from the pool of database \
@@ -34,16 +49,68 @@ impl NotesIndexRepositoryTrait for NotesIndexRepository {
         )?;
 
         conn.interact(move |conn| {
-            notes_index::table
-                .filter(notes_index::dsl::block_height.le(block_height))
+            let mut query = notes_index::table
+                .filter(notes_index::dsl::block_height.ge(from_block_height))
+                .filter(notes_index::dsl::block_height.le(to_block_height))
+                .order_by(notes_index::dsl::note_position.asc())
                 .select(NotesIndexDb::as_select())
-                .get_results(conn)
-                .with_context(|| {
-                    format!(
-                        "Failed to retrieve the notes map up to block height \
-                         {block_height}"
-                    )
-                })
+                .into_boxed();
+            if let Some(after) = after {
+                query = query.filter(notes_index::dsl::note_position.gt(after));
+            }
+            if let Some(limit) = limit {
+                query = query.limit(limit);
+            }
+
+            query.get_results(conn).with_context(|| {
+                format!(
+                    "Failed to retrieve the notes map from block height \
+                     {from_block_height} to {to_block_height}"
+                )
+            })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn get_block_notes_index(
+        &self,
+        block_height: i32,
+    ) -> anyhow::Result<Vec<NotesIndexDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            conn.build_transaction().read_only().run(move |conn| {
+                let mut notes = notes_index::table
+                    .filter(notes_index::dsl::block_height.lt(block_height))
+                    .order_by(notes_index::dsl::note_position.desc())
+                    .limit(1)
+                    .select(NotesIndexDb::as_select())
+                    .get_results(conn)
+                    .with_context(|| {
+                        format!(
+                            "Failed to retrieve the last notes map entry \
+                             before block height {block_height}"
+                        )
+                    })?;
+                notes.extend(
+                    notes_index::table
+                        .filter(notes_index::dsl::block_height.eq(block_height))
+                        .order_by(notes_index::dsl::note_position.asc())
+                        .select(NotesIndexDb::as_select())
+                        .get_results(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to retrieve the notes map at block \
+                                 height {block_height}"
+                            )
+                        })?,
+                );
+                anyhow::Ok(notes)
+            })
         })
         .await
         .context_db_interact_error()?
@@ -0,0 +1,199 @@
+use std::ffi::{CStr, CString};
+use std::os::fd::{AsRawFd, RawFd};
+
+use anyhow::Context;
+use orm::notification::{CHANNEL, Notification};
+use pq_sys::{
+    ConnStatusType, ExecStatusType, PGconn, PQclear, PQconnectdb,
+    PQconsumeInput, PQerrorMessage, PQexec, PQfinish, PQfreemem, PQnotifies,
+    PQresultStatus, PQsocket, PQstatus,
+};
+use tokio::io::unix::AsyncFd;
+
+use crate::appstate::AppState;
+
+#[derive(Clone)]
+pub struct NotificationRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait NotificationRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+    /// Open a dedicated db connection, listening to the notifications
+    /// of the indexer.
+    async fn listen(&self) -> anyhow::Result<Listener>;
+}
+
+impl NotificationRepositoryTrait for NotificationRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn listen(&self) -> anyhow::Result<Listener> {
+        let db_url = self.app_state.db_url().to_owned();
+
+        let conn = tokio::task::spawn_blocking(move || {
+            let mut conn = PgConn::connect(&db_url)?;
+            conn.execute(&format!("LISTEN \"{CHANNEL}\""))?;
+            anyhow::Ok(conn)
+        })
+        .await
+        .context("Failed to join the db listener task")??;
+
+        Ok(Listener {
+            conn: AsyncFd::new(conn)
+                .context("Failed to poll the socket of the db listener")?,
+        })
+    }
+}
+
+/// A db connection listening to the notifications of the indexer.
+pub struct Listener {
+    conn: AsyncFd<PgConn>,
+}
+
+impl Listener {
+    /// Wait for the next notifications sent to the connection.
+    pub async fn recv(&mut self) -> anyhow::Result<Vec<Notification>> {
+        loop {
+            let mut guard = self
+                .conn
+                .readable_mut()
+                .await
+                .context("Failed to poll the socket of the db listener")?;
+            let payloads = guard.get_inner_mut().notifications()?;
+
+            // NB: the socket might not be drained by a single read, so
+            // we only wait for it to be readable again once a read did
+            // not yield any notification
+            if payloads.is_empty() {
+                guard.clear_ready();
+                continue;
+            }
+
+            return Ok(payloads
+                .into_iter()
+                .filter_map(|payload| {
+                    serde_json::from_str(&payload)
+                        .inspect_err(|err| {
+                            tracing::warn!(
+                                %payload,
+                                %err,
+                                "Ignoring unknown db notification"
+                            )
+                        })
+                        .ok()
+                })
+                .collect());
+        }
+    }
+}
+
+/// A raw libpq connection, since diesel does not expose the
+/// notifications of its connections.
+struct PgConn(*mut PGconn);
+
+// SAFETY: libpq connections can be moved between threads, as long as
+// they are not used concurrently
+unsafe impl Send for PgConn {}
+
+impl PgConn {
+    fn connect(db_url: &str) -> anyhow::Result<Self> {
+        let conninfo =
+            CString::new(db_url).context("The db url contains a nul byte")?;
+        // SAFETY: `conninfo` is a valid C string
+        let conn = Self(unsafe { PQconnectdb(conninfo.as_ptr()) });
+
+        if conn.0.is_null() {
+            anyhow::bail!("Failed to allocate a db connection");
+        }
+        // SAFETY: `conn` is a valid connection
+        if unsafe { PQstatus(conn.0) } != ConnStatusType::CONNECTION_OK {
+            anyhow::bail!(
+                "Failed to connect to the db: {}",
+                conn.error_message()
+            );
+        }
+
+        Ok(conn)
+    }
+
+    fn execute(&mut self, query: &str) -> anyhow::Result<()> {
+        let query =
+            CString::new(query).context("The query contains a nul byte")?;
+
+        // SAFETY: `self` is a valid connection and `query` a valid C
+        // string. The result is freed right after reading its status.
+        let status = unsafe {
+            let result = PQexec(self.0, query.as_ptr());
+            let status = PQresultStatus(result);
+            PQclear(result);
+            status
+        };
+
+        if status != ExecStatusType::PGRES_COMMAND_OK {
+            anyhow::bail!(
+                "Failed to execute {query:?} on the db: {}",
+                self.error_message()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Read the input available on the socket of the connection, and
+    /// return the payloads of the notifications received so far.
+    fn notifications(&mut self) -> anyhow::Result<Vec<String>> {
+        // SAFETY: `self` is a valid connection
+        if unsafe { PQconsumeInput(self.0) } == 0 {
+            anyhow::bail!(
+                "Failed to read from the db connection: {}",
+                self.error_message()
+            );
+        }
+
+        let mut payloads = Vec::new();
+        loop {
+            // SAFETY: `self` is a valid connection, and notifications
+            // are freed right after copying their payload
+            let payload = unsafe {
+                let notify = PQnotifies(self.0);
+                if notify.is_null() {
+                    break;
+                }
+                let payload = CStr::from_ptr((*notify).extra)
+                    .to_string_lossy()
+                    .into_owned();
+                PQfreemem(notify.cast());
+                payload
+            };
+            payloads.push(payload);
+        }
+
+        Ok(payloads)
+    }
+
+    fn error_message(&self) -> String {
+        // SAFETY: `self` is a valid connection, whose error message is
+        // a valid C string owned by the connection
+        unsafe { CStr::from_ptr(PQerrorMessage(self.0)) }
+            .to_string_lossy()
+            .trim()
+            .to_owned()
+    }
+}
+
+impl AsRawFd for PgConn {
+    fn as_raw_fd(&self) -> RawFd {
+        // SAFETY: `self` is a valid connection
+        unsafe { PQsocket(self.0) }
+    }
+}
+
+impl Drop for PgConn {
+    fn drop(&mut self) {
+        // SAFETY: `self` is a valid connection, which is not used after
+        // being closed
+        unsafe { PQfinish(self.0) }
+    }
+}
@@ -1,14 +1,21 @@
 use anyhow::Context;
+use diesel::pg::PgRowByRowLoadingMode;
 use diesel::{
     BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl,
     RunQueryDsl, SelectableHelper,
 };
+use futures::StreamExt;
+use futures::stream::BoxStream;
 use orm::schema::{chain_state, tx};
 use orm::tx::TxDb;
 use shared::error::ContextDbInteractError;
+use tokio::sync::mpsc;
 
 use crate::appstate::AppState;
 
+/// Number of rows fetched ahead of the client, when streaming txs.
+const STREAM_BUFFER_SIZE: usize = 64;
+
 #[derive(Clone)]
 pub struct TxRepository {
     pub(crate) app_state: AppState,
@@ -21,6 +28,18 @@ pub trait TxRepositoryTrait {
         from_block_height: i32,
         to_block_height: i32,
     ) -> anyhow::Result<Vec<TxDb>>;
+    async fn get_txs_by_hash(
+        &self,
+        tx_hash: String,
+    ) -> anyhow::Result<Vec<TxDb>>;
+    /// Stream the txs from `from_block_height` up to `to_block_height`,
+    /// ordered by index. Rows are fetched one by one from the db, no
+    /// faster than the returned stream is consumed.
+    async fn stream_txs(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<TxDb>>>;
 }
 
 impl TxRepositoryTrait for TxRepository {
@@ -80,4 +99,102 @@ impl TxRepositoryTrait for TxRepository {
         .await
         .context_db_interact_error()?
     }
+
+    async fn get_txs_by_hash(
+        &self,
+        tx_hash: String,
+    ) -> anyhow::Result<Vec<TxDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            tx::table
+                .filter(tx::dsl::tx_hash.eq(&tx_hash))
+                .order_by((
+                    tx::dsl::block_height.asc(),
+                    tx::dsl::is_masp_fee_payment.desc(),
+                    tx::dsl::block_index.asc(),
+                    tx::dsl::masp_tx_index.asc(),
+                ))
+                .select(TxDb::as_select())
+                .get_results(conn)
+                .with_context(|| {
+                    format!(
+                        "Failed to get the transactions with hash {tx_hash} \
+                         from the database"
+                    )
+                })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+
+    async fn stream_txs(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<TxDb>>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        // NB: the channel is bounded, such that rows are only fetched
+        // from the db as fast as the client receives them
+        let (sender, mut receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            let error_sender = sender.clone();
+            let result = conn
+                .interact(move |conn| {
+                    let rows = tx::table
+                        .filter(
+                            tx::dsl::block_height
+                                .ge(from_block_height)
+                                .and(tx::dsl::block_height.le(to_block_height)),
+                        )
+                        .order_by((
+                            tx::dsl::block_height.asc(),
+                            tx::dsl::block_index.asc(),
+                            tx::dsl::masp_tx_index.asc(),
+                        ))
+                        .select(TxDb::as_select())
+                        .load_iter::<TxDb, PgRowByRowLoadingMode>(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to stream transactions from the \
+                                 database in the range \
+                                 {from_block_height}-{to_block_height}"
+                            )
+                        })?;
+
+                    for row in rows {
+                        let row = row.context(
+                            "Failed to read a streamed transaction from the \
+                             database",
+                        );
+                        let failed = row.is_err();
+                        // NB: stop fetching rows once the client is gone
+                        if sender.blocking_send(row).is_err() || failed {
+                            break;
+                        }
+                    }
+
+                    anyhow::Ok(())
+                })
+                .await
+                .context_db_interact_error()
+                .and_then(|result| result);
+
+            // NB: errors are passed on to the client, such that it does
+            // not mistake a truncated stream for a complete one
+            if let Err(err) = result {
+                _ = error_sender.send(Err(err)).await;
+            }
+        });
+
+        Ok(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)).boxed())
+    }
 }
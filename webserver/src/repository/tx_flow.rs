@@ -0,0 +1,86 @@
+use anyhow::Context;
+use diesel::dsl::{count_star, sum};
+use diesel::pg::data_types::PgNumeric;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use orm::schema::tx_flow;
+use shared::error::ContextDbInteractError;
+
+use crate::appstate::AppState;
+
+/// Flows of an asset type summed over some MASP txs, along with the
+/// number of MASP txs.
+pub type AggregatedFlowsDb = (
+    String,
+    Option<PgNumeric>,
+    Option<PgNumeric>,
+    Option<PgNumeric>,
+    i64,
+);
+
+#[derive(Clone)]
+pub struct TxFlowRepository {
+    pub(crate) app_state: AppState,
+}
+
+pub trait TxFlowRepositoryTrait {
+    fn new(app_state: AppState) -> Self;
+
+    /// Sum the flows of the MASP txs between `from_block_height` and
+    /// `to_block_height` (inclusive), per asset type.
+    async fn get_aggregated_flows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        asset_type: Option<String>,
+    ) -> anyhow::Result<Vec<AggregatedFlowsDb>>;
+}
+
+impl TxFlowRepositoryTrait for TxFlowRepository {
+    fn new(app_state: AppState) -> Self {
+        Self { app_state }
+    }
+
+    async fn get_aggregated_flows(
+        &self,
+        from_block_height: i32,
+        to_block_height: i32,
+        asset_type: Option<String>,
+    ) -> anyhow::Result<Vec<AggregatedFlowsDb>> {
+        let conn = self.app_state.get_db_connection().await.context(
+            "Failed to retrieve connection from the pool of database \
+             connections",
+        )?;
+
+        conn.interact(move |conn| {
+            let mut query = tx_flow::table
+                .filter(
+                    tx_flow::dsl::block_height
+                        .between(from_block_height, to_block_height),
+                )
+                .group_by(tx_flow::dsl::asset_type)
+                .select((
+                    tx_flow::dsl::asset_type,
+                    sum(tx_flow::dsl::transparent_in),
+                    sum(tx_flow::dsl::transparent_out),
+                    sum(tx_flow::dsl::value_balance),
+                    count_star(),
+                ))
+                .order_by(tx_flow::dsl::asset_type.asc())
+                .into_boxed();
+
+            if let Some(asset_type) = asset_type {
+                query = query.filter(tx_flow::dsl::asset_type.eq(asset_type));
+            }
+
+            query.load(conn).with_context(|| {
+                format!(
+                    "Failed to get the flows between heights \
+                     {from_block_height} and {to_block_height} from the \
+                     database"
+                )
+            })
+        })
+        .await
+        .context_db_interact_error()?
+    }
+}
@@ -1,14 +1,27 @@
 use anyhow::Context;
+use diesel::pg::PgRowByRowLoadingMode;
 use diesel::{
-    ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
-    SelectableHelper,
+    BoolExpressionMethods, ExpressionMethods, JoinOnDsl,
+    NullableExpressionMethods, QueryDsl, RunQueryDsl,
 };
-use orm::schema::witness;
-use orm::witness::WitnessDb;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use orm::schema::{tx, witness_delta};
 use shared::error::ContextDbInteractError;
+use tokio::sync::mpsc;
 
 use crate::appstate::AppState;
-use crate::utils::sql::abs;
+
+/// Number of rows fetched ahead of the reader, when streaming leaves.
+const STREAM_BUFFER_SIZE: usize = 64;
+
+/// The block height of a witness map delta, along with the borsh
+/// encoded leaves it appended to the commitment tree.
+///
+/// The leaves of legacy deltas were not recorded, so each of the MASP
+/// txs of their height is returned instead, in the order its notes were
+/// appended to the tree.
+pub type LeavesRow = (i32, Option<Vec<u8>>, Option<Vec<u8>>);
 
 #[derive(Clone)]
 pub struct WitnessMapRepository {
@@ -17,10 +30,14 @@ pub struct WitnessMapRepository {
 
 pub trait WitnessMapRepositoryTrait {
     fn new(app_state: AppState) -> Self;
-    async fn get_witnesses(
+    /// Stream the leaves of the witness map deltas above
+    /// `from_block_height` (if any), up to and including
+    /// `to_block_height`, in ascending height order.
+    async fn stream_leaves(
         &self,
-        block_height: i32,
-    ) -> anyhow::Result<(Vec<WitnessDb>, i32)>;
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<LeavesRow>>>;
 }
 
 impl WitnessMapRepositoryTrait for WitnessMapRepository {
@@ -28,47 +45,83 @@ impl WitnessMapRepositoryTrait for WitnessMapRepository {
         Self { app_state }
     }
 
-    async fn get_witnesses(
+    async fn stream_leaves(
         &self,
-        block_height: i32,
-    ) -> anyhow::Result<(Vec<WitnessDb>, i32)> {
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<LeavesRow>>> {
         let conn = self.app_state.get_db_connection().await.context(
             "Failed to retrieve connection from the pool of database \
              connections",
         )?;
 
-        conn.interact(move |conn| {
-            let Some(closest_height) = witness::table
-                .order(abs(witness::dsl::block_height - block_height).asc())
-                .filter(witness::dsl::block_height.le(block_height))
-                .select(witness::dsl::block_height)
-                .first(conn)
-                .optional()
-                .with_context(|| {
-                    format!(
-                        "Failed to fetch height from the db closest to the \
-                         provided height {block_height}"
-                    )
-                })?
-            else {
-                return anyhow::Ok((vec![], block_height));
-            };
+        // NB: the channel is bounded, such that rows are only fetched
+        // from the db as fast as they are read
+        let (sender, mut receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::spawn(async move {
+            let error_sender = sender.clone();
+            let result = conn
+                .interact(move |conn| {
+                    let rows = witness_delta::table
+                        .left_join(
+                            tx::table.on(tx::dsl::block_height
+                                .eq(witness_delta::dsl::block_height)
+                                .and(witness_delta::dsl::leaves.is_null())),
+                        )
+                        .filter(
+                            witness_delta::dsl::block_height
+                                .gt(from_block_height.unwrap_or(-1))
+                                .and(
+                                    witness_delta::dsl::block_height
+                                        .le(to_block_height),
+                                ),
+                        )
+                        // NB: follow the ordering of `MaspIndexedTx`
+                        .order_by((
+                            witness_delta::dsl::block_height.asc(),
+                            tx::dsl::is_masp_fee_payment.desc(),
+                            tx::dsl::block_index.asc(),
+                            tx::dsl::masp_tx_index.asc(),
+                        ))
+                        .select((
+                            witness_delta::dsl::block_height,
+                            witness_delta::dsl::leaves,
+                            tx::dsl::tx_bytes.nullable(),
+                        ))
+                        .load_iter::<LeavesRow, PgRowByRowLoadingMode>(conn)
+                        .with_context(|| {
+                            format!(
+                                "Failed to stream commitment tree leaves from \
+                                 the database up to height {to_block_height}"
+                            )
+                        })?;
+
+                    for row in rows {
+                        let row = row.context(
+                            "Failed to read streamed commitment tree leaves \
+                             from the database",
+                        );
+                        let failed = row.is_err();
+                        // NB: stop fetching rows once the reader is gone
+                        if sender.blocking_send(row).is_err() || failed {
+                            break;
+                        }
+                    }
+
+                    anyhow::Ok(())
+                })
+                .await
+                .context_db_interact_error()
+                .and_then(|result| result);
 
-            let witnesses = witness::table
-                .filter(witness::dsl::block_height.eq(closest_height))
-                .select(WitnessDb::as_select())
-                .get_results::<WitnessDb>(conn)
-                .with_context(|| {
-                    format!(
-                        "Failed to fetch witnesses from the db at height \
-                         {closest_height} (the closest to the provided height \
-                         {block_height})"
-                    )
-                })?;
+            // NB: a truncated tree would go unnoticed by the reader, so
+            // errors are passed on to it
+            if let Err(err) = result {
+                _ = error_sender.send(Err(err)).await;
+            }
+        });
 
-            anyhow::Ok((witnesses, closest_height))
-        })
-        .await
-        .context_db_interact_error()?
+        Ok(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)).boxed())
     }
 }
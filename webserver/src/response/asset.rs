@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct AssetsResponse {
+    pub assets: Vec<AssetResponse>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct AssetResponse {
+    /// Hex encoded MASP asset type.
+    pub asset_type: String,
+    pub token: String,
+    pub denom: u8,
+    pub digit_pos: u8,
+    /// MASP epoch of the asset type, if dated.
+    pub epoch: Option<u64>,
+}
+
+impl AssetResponse {
+    pub fn new(
+        (asset_type, token, denom, digit_pos, epoch): (
+            String,
+            String,
+            u8,
+            u8,
+            Option<u64>,
+        ),
+    ) -> Self {
+        Self {
+            asset_type,
+            token,
+            denom,
+            digit_pos,
+            epoch,
+        }
+    }
+}
+
+impl AssetsResponse {
+    pub fn new(
+        assets: impl IntoIterator<Item = (String, String, u8, u8, Option<u64>)>,
+    ) -> Self {
+        Self {
+            assets: assets.into_iter().map(AssetResponse::new).collect(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use shared::height::BlockHeight;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlockResponse {
+    pub block_height: u64,
+    pub hash: String,
+    /// RFC 3339 timestamp of the block, in UTC.
+    pub timestamp: String,
+    pub proposer_address: String,
+    pub app_hash: String,
+}
+
+impl BlockResponse {
+    pub fn new(
+        (block_height, hash, timestamp, proposer_address, app_hash): (
+            BlockHeight,
+            String,
+            NaiveDateTime,
+            String,
+            String,
+        ),
+    ) -> Self {
+        Self {
+            block_height: block_height.0,
+            hash,
+            timestamp: timestamp.and_utc().to_rfc3339(),
+            proposer_address,
+            app_hash,
+        }
+    }
+}
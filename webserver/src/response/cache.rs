@@ -0,0 +1,311 @@
+use axum::async_trait;
+use axum::extract::{Query, State};
+use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH, VARY};
+use axum::http::{HeaderMap, HeaderValue, Request, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use crate::response::api::ApiErrorResponse;
+use crate::response::encoding::Encoding;
+use crate::state::common::CommonState;
+
+/// Cache control of responses as of an indexed height. These only
+/// change if the db is rolled back or rebuilt, so they are cached for a
+/// bounded time, after which they are revalidated.
+const SETTLED: &str = "public, max-age=600, must-revalidate";
+
+/// Cache control of responses which might still change, as of heights
+/// that have not been indexed yet.
+const REVALIDATE: &str = "no-cache";
+
+/// Query of a response as of a block height.
+pub trait AsOfHeight {
+    /// Last block height of the indexed data the response depends on.
+    fn as_of_height(&self) -> u64;
+}
+
+/// Source of the last indexed block height.
+#[async_trait]
+pub trait IndexedTip {
+    async fn indexed_tip(&self) -> anyhow::Result<Option<u64>>;
+}
+
+#[async_trait]
+impl IndexedTip for CommonState {
+    async fn indexed_tip(&self) -> anyhow::Result<Option<u64>> {
+        self.namada_state_service
+            .get_latest_height()
+            .await
+            .map(|height| height.map(|height| height.0))
+    }
+}
+
+/// Middleware setting the cache control of responses as of a height,
+/// and tagging them with a weak `ETag` once their height is indexed. The
+/// tag is derived from the request, such that a request already holding
+/// it in `If-None-Match` is answered `304 Not Modified` without running
+/// the handler.
+///
+/// NB: the tag is weak, since it is shared by all the content codings of
+/// the response.
+pub async fn etag<S, Q, B>(
+    State(state): State<S>,
+    Query(query): Query<Q>,
+    encoding: Encoding,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response
+where
+    S: IndexedTip,
+    Q: AsOfHeight,
+{
+    let settled = match state.indexed_tip().await {
+        Ok(indexed_tip) => indexed_tip
+            .is_some_and(|indexed_tip| query.as_of_height() <= indexed_tip),
+        Err(err) => {
+            tracing::error!(%err, "Failed to read the indexed tip");
+            return ApiErrorResponse::send(
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                Some(err.to_string()),
+            );
+        }
+    };
+
+    if !settled {
+        let mut response = next.run(request).await;
+        if response.status().is_success() {
+            response
+                .headers_mut()
+                .insert(CACHE_CONTROL, HeaderValue::from_static(REVALIDATE));
+        }
+        return response;
+    }
+
+    let opaque_tag = opaque_tag(request.uri(), encoding);
+    // NB: `If-None-Match` uses the weak comparison of tags
+    let if_none_match: Vec<String> = request
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|tags| tags.to_str().ok())
+        .map(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/").to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if if_none_match.contains(&opaque_tag) {
+        return not_modified(&opaque_tag);
+    }
+
+    let mut response = next.run(request).await;
+
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    // NB: `*` matches any current response, which only exists once the
+    // handler has succeeded
+    if if_none_match.iter().any(|tag| tag == "*") {
+        return not_modified(&opaque_tag);
+    }
+
+    insert_settled_headers(response.headers_mut(), &opaque_tag);
+    response
+}
+
+/// Opaque tag of the settled response to a request of `uri`, in
+/// `encoding`. Settled responses only depend on their request, and on
+/// the version of the server which renders them.
+fn opaque_tag(uri: &Uri, encoding: Encoding) -> String {
+    let digest = Sha256::digest(format!(
+        "{}\n{uri}\n{encoding:?}",
+        env!("CARGO_PKG_VERSION")
+    ));
+    format!("\"{digest:x}\"")
+}
+
+fn not_modified(opaque_tag: &str) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    insert_settled_headers(response.headers_mut(), opaque_tag);
+    response
+}
+
+fn insert_settled_headers(headers: &mut HeaderMap, opaque_tag: &str) {
+    headers.insert(
+        ETAG,
+        HeaderValue::from_str(&format!("W/{opaque_tag}"))
+            .expect("Hex encoded hashes should be valid header values"),
+    );
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(SETTLED));
+    // NB: the tag depends on the negotiated encoding
+    if !headers.contains_key(VARY) {
+        headers.insert(VARY, HeaderValue::from_static("accept"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::body::{Body, HttpBody};
+    use axum::routing::get;
+    use axum::{Router, middleware};
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const BODY: &str = "witness map";
+
+    const INDEXED_TIP: u64 = 5;
+
+    #[derive(Clone)]
+    struct Tip;
+
+    #[async_trait]
+    impl IndexedTip for Tip {
+        async fn indexed_tip(&self) -> anyhow::Result<Option<u64>> {
+            Ok(Some(INDEXED_TIP))
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct HeightQuery {
+        height: u64,
+    }
+
+    impl AsOfHeight for HeightQuery {
+        fn as_of_height(&self) -> u64 {
+            self.height
+        }
+    }
+
+    /// Send a request of `uri`, and return its response along with the
+    /// number of times the handler ran.
+    async fn get_with(
+        uri: &str,
+        if_none_match: Option<&str>,
+    ) -> (Response, usize) {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let handler_runs = handled.clone();
+        let router = Router::new()
+            .route(
+                "/",
+                get(move || async move {
+                    handler_runs.fetch_add(1, Ordering::SeqCst);
+                    BODY
+                }),
+            )
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .layer(middleware::from_fn_with_state(
+                Tip,
+                etag::<Tip, HeightQuery, _>,
+            ));
+
+        let mut request = Request::get(uri);
+        if let Some(tags) = if_none_match {
+            request = request.header(IF_NONE_MATCH, tags);
+        }
+
+        let response = router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        (response, handled.load(Ordering::SeqCst))
+    }
+
+    async fn body_bytes(response: Response) -> Vec<u8> {
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        bytes
+    }
+
+    async fn weak_tag(uri: &str) -> String {
+        let (response, _) = get_with(uri, None).await;
+        response.headers()[ETAG].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn tags_settled_responses_with_weak_etags() {
+        let (response, handled) = get_with("/?height=5", None).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(handled, 1);
+        assert!(response.headers()[ETAG].to_str().unwrap().starts_with("W/"));
+        assert_eq!(response.headers()[CACHE_CONTROL], SETTLED);
+        assert_eq!(response.headers()[VARY], "accept");
+        assert_eq!(body_bytes(response).await, BODY.as_bytes());
+
+        // NB: tags are derived from the requests
+        assert_eq!(weak_tag("/?height=5").await, weak_tag("/?height=5").await);
+        assert_ne!(weak_tag("/?height=4").await, weak_tag("/?height=5").await);
+    }
+
+    #[tokio::test]
+    async fn answers_matching_tags_without_running_the_handler() {
+        let weak_tag = weak_tag("/?height=1").await;
+        let opaque_tag = weak_tag.trim_start_matches("W/");
+
+        for tags in [
+            weak_tag.clone(),
+            // NB: `If-None-Match` uses the weak comparison of tags
+            opaque_tag.to_string(),
+            format!("\"other\", {weak_tag}"),
+            format!("W/\"other\" ,{opaque_tag}"),
+        ] {
+            let (response, handled) = get_with("/?height=1", Some(&tags)).await;
+
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{tags}");
+            assert_eq!(handled, 0, "{tags}");
+            assert_eq!(response.headers()[ETAG], weak_tag.as_str());
+            assert_eq!(response.headers()[CACHE_CONTROL], SETTLED);
+            assert!(body_bytes(response).await.is_empty());
+        }
+
+        let (response, handled) = get_with("/?height=1", Some("*")).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(handled, 1);
+        assert_eq!(response.headers()[ETAG], weak_tag.as_str());
+    }
+
+    #[tokio::test]
+    async fn serves_mismatching_tags() {
+        for tags in ["\"other\"", "W/\"other\"", "\"other\", W/\"another\""] {
+            let (response, handled) = get_with("/?height=1", Some(tags)).await;
+
+            assert_eq!(response.status(), StatusCode::OK, "{tags}");
+            assert_eq!(handled, 1, "{tags}");
+            assert_eq!(body_bytes(response).await, BODY.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn revalidates_unsettled_responses() {
+        let tags = weak_tag("/?height=5").await;
+
+        for tags in [tags.as_str(), "*"] {
+            let (response, handled) = get_with("/?height=6", Some(tags)).await;
+
+            assert_eq!(response.status(), StatusCode::OK, "{tags}");
+            assert_eq!(handled, 1, "{tags}");
+            assert!(!response.headers().contains_key(ETAG));
+            assert_eq!(response.headers()[CACHE_CONTROL], REVALIDATE);
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_unsuccessful_responses() {
+        for uri in ["/missing?height=1", "/missing?height=6"] {
+            let (response, _) = get_with(uri, Some("*")).await;
+
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+            assert!(!response.headers().contains_key(ETAG));
+            assert!(!response.headers().contains_key(CACHE_CONTROL));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::response::encoding::serialize_bytes;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct ConversionsResponse {
+    pub masp_epoch: u64,
+    pub conversions: Vec<Conversion>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct Conversion {
+    /// Hex encoded MASP asset type.
+    pub asset_type: String,
+    pub token: String,
+    pub denom: u8,
+    pub digit_pos: u8,
+    /// MASP epoch of the asset type.
+    pub epoch: u64,
+    /// Borsh encoded conversion of the asset type.
+    #[serde(serialize_with = "serialize_bytes")]
+    pub conversion: Vec<u8>,
+}
+
+impl ConversionsResponse {
+    pub fn new(
+        masp_epoch: u64,
+        conversions: impl IntoIterator<
+            Item = (String, String, u8, u8, u64, Vec<u8>),
+        >,
+    ) -> Self {
+        Self {
+            masp_epoch,
+            conversions: conversions
+                .into_iter()
+                .map(
+                    |(
+                        asset_type,
+                        token,
+                        denom,
+                        digit_pos,
+                        epoch,
+                        conversion,
+                    )| {
+                        Conversion {
+                            asset_type,
+                            token,
+                            denom,
+                            digit_pos,
+                            epoch,
+                            conversion,
+                        }
+                    },
+                )
+                .collect(),
+        }
+    }
+}
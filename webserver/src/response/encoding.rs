@@ -0,0 +1,312 @@
+use std::convert::Infallible;
+use std::io;
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Serialize, Serializer};
+
+use crate::response::api::ApiErrorResponse;
+
+/// Media type of bincode encoded responses.
+pub const BINARY: &str = "application/octet-stream";
+
+/// Media type of JSON responses with base64 encoded bytes.
+pub const COMPACT_JSON: &str = "application/vnd.masp-indexer.compact+json";
+
+/// Wire format of a response, negotiated from the `Accept` header of
+/// the request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// JSON, with bytes rendered as arrays of numbers.
+    #[default]
+    Json,
+    /// JSON, with bytes rendered as base64 strings.
+    CompactJson,
+    /// The bincode encoding of the JSON response structs.
+    Binary,
+}
+
+impl Encoding {
+    /// Pick the preferred encoding among the media ranges of an
+    /// `Accept` header, falling back to JSON.
+    pub fn from_accept(accept: &str) -> Self {
+        let mut best = (Self::Json, 0.0);
+
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';').map(str::trim);
+            let encoding = match params.next() {
+                Some(BINARY) => Self::Binary,
+                Some(COMPACT_JSON) => Self::CompactJson,
+                Some("application/json") => Self::Json,
+                _ => continue,
+            };
+            let quality = params
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|quality| quality.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            if quality > best.1 {
+                best = (encoding, quality);
+            }
+        }
+
+        best.0
+    }
+
+    /// Serialize `value` in this encoding.
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|err| {
+                format!("Failed to serialize the JSON response: {err}")
+            }),
+            Self::CompactJson => {
+                let mut body = Vec::new();
+                let mut serializer = serde_json::Serializer::with_formatter(
+                    &mut body,
+                    Base64Formatter,
+                );
+                value
+                    .serialize(&mut serializer)
+                    .map(|()| body)
+                    .map_err(|err| {
+                        format!(
+                            "Failed to serialize the compact JSON response: \
+                             {err}"
+                        )
+                    })
+            }
+            Self::Binary => bincode::serialize(value).map_err(|err| {
+                format!("Failed to serialize the binary response: {err}")
+            }),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::CompactJson => COMPACT_JSON,
+            Self::Binary => BINARY,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Encoding
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(Self::from_accept)
+            .unwrap_or_default())
+    }
+}
+
+/// A response serialized in the negotiated [`Encoding`].
+pub struct Encoded<T>(pub Encoding, pub T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Self(encoding, value) = self;
+
+        match encoding.encode(&value) {
+            Ok(body) => (
+                [
+                    (
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static(encoding.content_type()),
+                    ),
+                    (header::VARY, HeaderValue::from_static("accept")),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(err) => {
+                tracing::error!(%err, "Failed to encode response");
+                ApiErrorResponse::send(
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    Some(err),
+                )
+            }
+        }
+    }
+}
+
+/// Serialize the bytes of a response field as bytes, rather than as a
+/// sequence, such that each [`Encoding`] can render them its own way.
+/// The JSON and bincode renderings are left unchanged.
+pub fn serialize_bytes<S>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(bytes)
+}
+
+/// JSON formatter rendering bytes as base64 strings.
+struct Base64Formatter;
+
+impl serde_json::ser::Formatter for Base64Formatter {
+    fn write_byte_array<W>(
+        &mut self,
+        writer: &mut W,
+        value: &[u8],
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        write!(writer, "\"{}\"", BASE64.encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Response {
+        #[serde(serialize_with = "serialize_bytes")]
+        bytes: Vec<u8>,
+        #[serde(serialize_with = "serialize_bytes")]
+        empty: Vec<u8>,
+        numbers: Vec<u8>,
+        block_height: u64,
+    }
+
+    fn response() -> Response {
+        Response {
+            bytes: vec![0, 1, 2, 255],
+            empty: vec![],
+            numbers: vec![3, 4],
+            block_height: 42,
+        }
+    }
+
+    #[test]
+    fn from_accept_picks_the_highest_quality() {
+        assert_eq!(Encoding::from_accept(BINARY), Encoding::Binary);
+        assert_eq!(Encoding::from_accept(COMPACT_JSON), Encoding::CompactJson);
+        assert_eq!(Encoding::from_accept("application/json"), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept(
+                "application/json;q=0.5, application/octet-stream;q=0.9"
+            ),
+            Encoding::Binary
+        );
+        assert_eq!(
+            Encoding::from_accept(
+                "application/octet-stream; q=0.4, \
+                 application/vnd.masp-indexer.compact+json; q=0.8, \
+                 application/json; q=0.6"
+            ),
+            Encoding::CompactJson
+        );
+        // NB: media ranges without a quality value default to 1
+        assert_eq!(
+            Encoding::from_accept(
+                "application/json;q=0.9, application/octet-stream"
+            ),
+            Encoding::Binary
+        );
+        // NB: the first of equally preferred media ranges wins
+        assert_eq!(
+            Encoding::from_accept("application/octet-stream, application/json"),
+            Encoding::Binary
+        );
+    }
+
+    #[test]
+    fn from_accept_skips_refused_encodings() {
+        assert_eq!(
+            Encoding::from_accept("application/octet-stream;q=0"),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(
+                "application/octet-stream;q=0, \
+                 application/vnd.masp-indexer.compact+json;q=0.1"
+            ),
+            Encoding::CompactJson
+        );
+    }
+
+    #[test]
+    fn from_accept_skips_unknown_media_types() {
+        assert_eq!(Encoding::from_accept(""), Encoding::Json);
+        assert_eq!(Encoding::from_accept("*/*"), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept("text/html, application/*;q=0.8"),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(
+                "application/xml, application/octet-stream;q=0.1"
+            ),
+            Encoding::Binary
+        );
+    }
+
+    #[test]
+    fn compact_json_renders_bytes_as_base64() {
+        let body = Encoding::CompactJson.encode(&response()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            r#"{"bytes":"AAEC/w==","empty":"","numbers":[3,4],"block_height":42}"#
+        );
+    }
+
+    #[test]
+    fn json_renders_bytes_as_numbers() {
+        let body = Encoding::Json.encode(&response()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            r#"{"bytes":[0,1,2,255],"empty":[],"numbers":[3,4],"block_height":42}"#
+        );
+    }
+
+    #[test]
+    fn binary_encodes_bytes_as_sequences() {
+        #[derive(Serialize)]
+        struct PlainResponse {
+            bytes: Vec<u8>,
+            empty: Vec<u8>,
+            numbers: Vec<u8>,
+            block_height: u64,
+        }
+
+        let Response {
+            bytes,
+            empty,
+            numbers,
+            block_height,
+        } = response();
+
+        assert_eq!(
+            Encoding::Binary.encode(&response()).unwrap(),
+            bincode::serialize(&PlainResponse {
+                bytes,
+                empty,
+                numbers,
+                block_height,
+            })
+            .unwrap()
+        );
+    }
+}
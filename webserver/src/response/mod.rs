@@ -1,6 +1,14 @@
 pub mod api;
+pub mod asset;
+pub mod block;
+pub mod cache;
+pub mod conversion;
+pub mod encoding;
 pub mod namada_state;
 pub mod notes_index;
+pub mod notification;
+pub mod stream;
 pub mod tree;
 pub mod tx;
+pub mod tx_flow;
 pub mod witness_map;
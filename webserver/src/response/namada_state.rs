@@ -11,3 +11,10 @@ pub struct BlockIndexResponse {
     pub block_height: u64,
     pub index: BinaryFuse16,
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NetworkIdentityResponse {
+    pub chain_id: String,
+    pub first_block_height: u64,
+    pub first_block_hash: String,
+}
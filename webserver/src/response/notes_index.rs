@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct NotesIndexResponse {
     pub notes_index: Vec<Note>,
+    /// Note position to pass as `after` to fetch the next page, if the
+    /// page was full.
+    pub next: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
@@ -15,7 +18,10 @@ pub struct Note {
 }
 
 impl NotesIndexResponse {
-    pub fn new(notes_index: Vec<(u64, u64, u64, u64, bool)>) -> Self {
+    pub fn new(
+        notes_index: Vec<(u64, u64, u64, u64, bool)>,
+        next: Option<u64>,
+    ) -> Self {
         Self {
             notes_index: notes_index
                 .into_iter()
@@ -37,6 +43,7 @@ impl NotesIndexResponse {
                     },
                 )
                 .collect(),
+            next,
         }
     }
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// A block was indexed.
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct HeightEvent {
+    pub block_height: u64,
+    /// Number of MASP txs of the block.
+    pub num_masp_txs: u64,
+    /// Number of notes in the commitment tree, as of the block.
+    pub commitment_tree_size: u64,
+}
+
+/// A new block index is available.
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct BlockIndexEvent {
+    pub block_height: u64,
+}
@@ -0,0 +1,65 @@
+use axum::body::StreamBody;
+use axum::http::{HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::response::encoding::{BINARY, Encoding};
+
+/// Media type of newline delimited JSON streams.
+pub const NDJSON: &str = "application/x-ndjson";
+
+/// A stream of items, each serialized in the negotiated [`Encoding`]
+/// as soon as the client is ready to receive it.
+///
+/// JSON items are separated by newlines, whereas binary items are each
+/// preceded by their length, as a little endian u64. An error ends the
+/// stream abruptly, such that clients can tell it from a complete one.
+pub struct Streamed<S>(pub Encoding, pub S);
+
+impl<S, T> IntoResponse for Streamed<S>
+where
+    S: Stream<Item = anyhow::Result<T>> + Send + 'static,
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let Self(encoding, items) = self;
+
+        let body = items.map(move |item| {
+            let item = item.map_err(|err| err.to_string())?;
+            let bytes = encoding.encode(&item)?;
+
+            Ok::<_, String>(match encoding {
+                Encoding::Json | Encoding::CompactJson => {
+                    let mut line = bytes;
+                    line.push(b'\n');
+                    line
+                }
+                Encoding::Binary => {
+                    let mut frame = (bytes.len() as u64).to_le_bytes().to_vec();
+                    frame.extend(bytes);
+                    frame
+                }
+            })
+        });
+        let body = body.inspect(|item| {
+            if let Err(err) = item {
+                tracing::error!(%err, "Failed to stream response");
+            }
+        });
+
+        let content_type = match encoding {
+            Encoding::Json | Encoding::CompactJson => NDJSON,
+            Encoding::Binary => BINARY,
+        };
+
+        (
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+                (header::VARY, HeaderValue::from_static("accept")),
+            ],
+            StreamBody::new(body),
+        )
+            .into_response()
+    }
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+use crate::response::encoding::serialize_bytes;
+
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct TreeResponse {
+    #[serde(serialize_with = "serialize_bytes")]
     pub commitment_tree: Vec<u8>,
     pub block_height: u64,
 }
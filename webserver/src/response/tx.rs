@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::response::encoding::serialize_bytes;
+
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct TxResponse {
     pub txs: Vec<Tx>,
@@ -16,7 +18,25 @@ pub struct Tx {
 pub struct TxSlot {
     pub masp_tx_index: u64,
     pub is_masp_fee_payment: bool,
+    #[serde(serialize_with = "serialize_bytes")]
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct TxByHashResponse {
+    pub tx_hash: String,
+    pub txs: Vec<IndexedTx>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct IndexedTx {
+    pub block_height: u64,
+    pub block_index: u64,
+    pub masp_tx_index: u64,
+    pub is_masp_fee_payment: bool,
+    #[serde(serialize_with = "serialize_bytes")]
     pub bytes: Vec<u8>,
+    pub note_positions: Vec<u64>,
 }
 
 impl TxResponse {
@@ -26,21 +46,62 @@ impl TxResponse {
         Self {
             txs: txs
                 .into_iter()
-                .map(|(batch, block_height, block_index)| Tx {
-                    batch: batch
-                        .into_iter()
-                        .map(|(masp_tx_index, is_masp_fee_payment, bytes)| {
-                            TxSlot {
-                                masp_tx_index,
-                                is_masp_fee_payment,
-                                bytes,
-                            }
-                        })
-                        .collect(),
-                    block_height,
-                    block_index,
+                .map(|(batch, block_height, block_index)| {
+                    Tx::new(batch, block_height, block_index)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Tx {
+    pub fn new(
+        batch: Vec<(u64, bool, Vec<u8>)>,
+        block_height: u64,
+        block_index: u64,
+    ) -> Self {
+        Self {
+            batch: batch
+                .into_iter()
+                .map(|(masp_tx_index, is_masp_fee_payment, bytes)| TxSlot {
+                    masp_tx_index,
+                    is_masp_fee_payment,
+                    bytes,
                 })
                 .collect(),
+            block_height,
+            block_index,
+        }
+    }
+}
+
+impl TxByHashResponse {
+    pub fn new(
+        tx_hash: String,
+        txs: impl IntoIterator<Item = (u64, u64, u64, bool, Vec<u8>, Vec<u64>)>,
+    ) -> Self {
+        Self {
+            tx_hash,
+            txs: txs
+                .into_iter()
+                .map(
+                    |(
+                        block_height,
+                        block_index,
+                        masp_tx_index,
+                        is_masp_fee_payment,
+                        bytes,
+                        note_positions,
+                    )| IndexedTx {
+                        block_height,
+                        block_index,
+                        masp_tx_index,
+                        is_masp_fee_payment,
+                        bytes,
+                        note_positions,
+                    },
+                )
+                .collect(),
         }
     }
 }
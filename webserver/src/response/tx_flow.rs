@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct TxFlowsResponse {
+    pub from_height: u64,
+    pub to_height: u64,
+    pub flows: Vec<AssetFlows>,
+}
+
+/// Value moved in and out of the shielded pool in some asset type.
+/// Amounts are decimal strings, since they might not fit in a JSON
+/// number.
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct AssetFlows {
+    /// Hex encoded MASP asset type.
+    pub asset_type: String,
+    /// Value of the transparent inputs of the MASP txs.
+    pub shielded: String,
+    /// Value of the transparent outputs of the MASP txs.
+    pub unshielded: String,
+    /// Value shielded minus value unshielded.
+    pub net_flow: String,
+    /// Sum of the value balances of the sapling bundles of the MASP
+    /// txs, i.e. the value leaving the shielded pool.
+    pub value_balance: String,
+    pub num_txs: u64,
+}
+
+impl TxFlowsResponse {
+    pub fn new(
+        from_height: u64,
+        to_height: u64,
+        flows: impl IntoIterator<Item = (String, i128, i128, i128, u64)>,
+    ) -> Self {
+        Self {
+            from_height,
+            to_height,
+            flows: flows
+                .into_iter()
+                .map(
+                    |(
+                        asset_type,
+                        shielded,
+                        unshielded,
+                        value_balance,
+                        num_txs,
+                    )| {
+                        AssetFlows {
+                            asset_type,
+                            shielded: shielded.to_string(),
+                            unshielded: unshielded.to_string(),
+                            net_flow: (shielded - unshielded).to_string(),
+                            value_balance: value_balance.to_string(),
+                            num_txs,
+                        }
+                    },
+                )
+                .collect(),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use shared::height::BlockHeight;
 
+use crate::response::encoding::serialize_bytes;
+
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct WitnessMapResponse {
     pub witnesses: Vec<Witness>,
@@ -9,6 +11,7 @@ pub struct WitnessMapResponse {
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct Witness {
+    #[serde(serialize_with = "serialize_bytes")]
     pub bytes: Vec<u8>,
     pub index: u64,
 }
@@ -0,0 +1,47 @@
+use orm::asset::AssetDb;
+
+use crate::appstate::AppState;
+use crate::repository::asset::{AssetRepository, AssetRepositoryTrait};
+
+#[derive(Clone)]
+pub struct AssetService {
+    asset_repo: AssetRepository,
+}
+
+impl AssetService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            asset_repo: AssetRepository::new(app_state),
+        }
+    }
+
+    pub async fn get_asset(
+        &self,
+        asset_type: String,
+    ) -> anyhow::Result<Option<(String, String, u8, u8, Option<u64>)>> {
+        self.asset_repo
+            .get_asset(asset_type)
+            .await
+            .map(|asset| asset.map(into_tuple))
+    }
+
+    pub async fn get_assets(
+        &self,
+        token: Option<String>,
+    ) -> anyhow::Result<Vec<(String, String, u8, u8, Option<u64>)>> {
+        self.asset_repo
+            .get_assets(token)
+            .await
+            .map(|assets| assets.into_iter().map(into_tuple).collect())
+    }
+}
+
+fn into_tuple(asset: AssetDb) -> (String, String, u8, u8, Option<u64>) {
+    (
+        asset.asset_type,
+        asset.token,
+        asset.denom as u8,
+        asset.digit_pos as u8,
+        asset.epoch.map(|epoch| epoch as u64),
+    )
+}
@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use orm::block::BlockDb;
+use shared::height::BlockHeight;
+
+use crate::appstate::AppState;
+use crate::repository::block::{BlockRepository, BlockRepositoryTrait};
+
+#[derive(Clone)]
+pub struct BlockService {
+    block_repo: BlockRepository,
+}
+
+impl BlockService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            block_repo: BlockRepository::new(app_state),
+        }
+    }
+
+    pub async fn get_block(
+        &self,
+        block_height: u64,
+    ) -> anyhow::Result<
+        Option<(BlockHeight, String, NaiveDateTime, String, String)>,
+    > {
+        self.block_repo
+            .get_block(block_height as i32)
+            .await
+            .map(|block| block.map(into_tuple))
+    }
+
+    pub async fn get_first_block_from(
+        &self,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<
+        Option<((BlockHeight, String, NaiveDateTime, String, String), bool)>,
+    > {
+        self.block_repo.get_first_block_from(timestamp).await.map(
+            |maybe_block| {
+                maybe_block.map(|(block, is_complete)| {
+                    (into_tuple(block), is_complete)
+                })
+            },
+        )
+    }
+}
+
+fn into_tuple(
+    block: BlockDb,
+) -> (BlockHeight, String, NaiveDateTime, String, String) {
+    (
+        BlockHeight::from(block.block_height),
+        block.hash,
+        block.timestamp,
+        block.proposer_address,
+        block.app_hash,
+    )
+}
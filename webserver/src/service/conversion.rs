@@ -0,0 +1,51 @@
+use orm::conversion::ConversionDb;
+
+use crate::appstate::AppState;
+use crate::repository::conversion::{
+    ConversionRepository, ConversionRepositoryTrait,
+};
+
+#[derive(Clone)]
+pub struct ConversionService {
+    conversion_repo: ConversionRepository,
+}
+
+impl ConversionService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            conversion_repo: ConversionRepository::new(app_state),
+        }
+    }
+
+    pub async fn get_conversions(
+        &self,
+        masp_epoch: Option<u64>,
+    ) -> anyhow::Result<
+        Option<(u64, Vec<(String, String, u8, u8, u64, Vec<u8>)>)>,
+    > {
+        self.conversion_repo
+            .get_conversions(masp_epoch.map(|masp_epoch| masp_epoch as i32))
+            .await
+            .map(|maybe_conversions| {
+                maybe_conversions.map(|(masp_epoch, conversions)| {
+                    (
+                        masp_epoch as u64,
+                        conversions.into_iter().map(into_tuple).collect(),
+                    )
+                })
+            })
+    }
+}
+
+fn into_tuple(
+    conversion: ConversionDb,
+) -> (String, String, u8, u8, u64, Vec<u8>) {
+    (
+        conversion.asset_type,
+        conversion.token,
+        conversion.denom as u8,
+        conversion.digit_pos as u8,
+        conversion.asset_epoch as u64,
+        conversion.conversion_bytes,
+    )
+}
@@ -1,3 +1,4 @@
+use orm::chain_state::NetworkIdentityDb;
 use shared::height::BlockHeight;
 
 use crate::appstate::AppState;
@@ -34,4 +35,25 @@ impl NamadaStateService {
                     .map(|(height, filter)| (BlockHeight(height as _), filter))
             })
     }
+
+    pub async fn get_network_identity(
+        &self,
+    ) -> anyhow::Result<Option<(String, BlockHeight, String)>> {
+        self.namada_state_repo
+            .get_network_identity()
+            .await
+            .map(|network| {
+                let NetworkIdentityDb {
+                    chain_id,
+                    first_block_height,
+                    first_block_hash,
+                } = network?;
+
+                Some((
+                    chain_id?,
+                    BlockHeight(first_block_height? as _),
+                    first_block_hash?,
+                ))
+            })
+    }
 }
@@ -18,10 +18,18 @@ impl NotesIndexService {
     pub async fn get_notes_index(
         &self,
         from_block_height: u64,
+        to_block_height: u64,
+        after: Option<u64>,
+        limit: Option<u64>,
     ) -> anyhow::Result<Vec<(u64, u64, u64, u64, bool)>> {
         Ok(self
             .notes_index_repo
-            .get_notes_index(from_block_height as i32)
+            .get_notes_index(
+                from_block_height as i32,
+                to_block_height as i32,
+                after.map(|after| after as i32),
+                limit.map(|limit| limit as i64),
+            )
             .await?
             .into_iter()
             .map(|notes_index_entry| {
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use orm::notification::Notification;
+use tokio::sync::broadcast;
+
+use crate::appstate::AppState;
+use crate::repository::notification::{
+    NotificationRepository, NotificationRepositoryTrait,
+};
+
+/// Number of notifications buffered for slow subscribers.
+const NOTIFICATION_BUFFER_SIZE: usize = 128;
+
+/// Delay before listening to the db again, after losing its connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct NotificationService {
+    sender: broadcast::Sender<Notification>,
+}
+
+impl NotificationService {
+    /// Create the service, along with a background task relaying the
+    /// notifications of the db to its subscribers.
+    pub fn new(app_state: AppState) -> Self {
+        let (sender, _) = broadcast::channel(NOTIFICATION_BUFFER_SIZE);
+
+        tokio::spawn(relay_notifications(
+            NotificationRepository::new(app_state),
+            sender.clone(),
+        ));
+
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.sender.subscribe()
+    }
+}
+
+async fn relay_notifications(
+    notification_repo: NotificationRepository,
+    sender: broadcast::Sender<Notification>,
+) {
+    loop {
+        match notification_repo.listen().await {
+            Ok(mut listener) => {
+                tracing::info!("Listening to db notifications");
+
+                loop {
+                    match listener.recv().await {
+                        Ok(notifications) => {
+                            for notification in notifications {
+                                // NB: sending only fails without subscribers
+                                _ = sender.send(notification);
+                            }
+                        }
+                        Err(err) => {
+                            tracing::warn!(
+                                ?err,
+                                "Stopped listening to db notifications"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!(?err, "Failed to listen to db notifications");
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
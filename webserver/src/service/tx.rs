@@ -1,17 +1,25 @@
+use anyhow::Context;
+use futures::{Stream, StreamExt};
 use itertools::Itertools;
+use orm::tx::TxDb;
 
 use crate::appstate::AppState;
+use crate::repository::notes_index::{
+    NotesIndexRepository, NotesIndexRepositoryTrait,
+};
 use crate::repository::tx::{TxRepository, TxRepositoryTrait};
 
 #[derive(Clone)]
 pub struct TxService {
     tx_repo: TxRepository,
+    notes_index_repo: NotesIndexRepository,
 }
 
 impl TxService {
     pub fn new(app_state: AppState) -> Self {
         Self {
-            tx_repo: TxRepository::new(app_state),
+            tx_repo: TxRepository::new(app_state.clone()),
+            notes_index_repo: NotesIndexRepository::new(app_state),
         }
     }
 
@@ -49,4 +57,131 @@ impl TxService {
             })
             .collect::<Vec<_>>())
     }
+
+    #[allow(clippy::type_complexity)]
+    pub async fn get_txs_by_hash(
+        &self,
+        tx_hash: String,
+    ) -> anyhow::Result<Vec<(u64, u64, u64, bool, Vec<u8>, Vec<u64>)>> {
+        let txs = self.tx_repo.get_txs_by_hash(tx_hash).await?;
+        let mut indexed_txs = Vec::with_capacity(txs.len());
+
+        for tx in txs {
+            let notes = self
+                .notes_index_repo
+                .get_block_notes_index(tx.block_height)
+                .await?;
+
+            // NB: notes are appended to the commitment tree in the same
+            // order as the txs are sorted here. A tx without notes
+            // shadows the notes index entry of the tx that follows it,
+            // which starts at the same note position, therefore we look
+            // for the last entry up to and including this tx.
+            let tx_key = (
+                tx.block_height,
+                !tx.is_masp_fee_payment,
+                tx.block_index,
+                tx.masp_tx_index,
+            );
+            let first_note_position = notes
+                .iter()
+                .rev()
+                .find(|note| {
+                    (
+                        note.block_height,
+                        !note.is_masp_fee_payment,
+                        note.block_index,
+                        note.masp_tx_index,
+                    ) <= tx_key
+                })
+                .with_context(|| {
+                    format!(
+                        "Missing notes index entry of the transaction at \
+                         block height {}, index {}",
+                        tx.block_height, tx.block_index
+                    )
+                })?
+                .note_position as u64;
+            let num_notes =
+                shared::transaction::num_masp_notes(&tx.tx_bytes)? as u64;
+
+            indexed_txs.push((
+                tx.block_height as u64,
+                tx.block_index as u64,
+                tx.masp_tx_index as u64,
+                tx.is_masp_fee_payment,
+                tx.tx_bytes,
+                (first_note_position..first_note_position + num_notes)
+                    .collect(),
+            ));
+        }
+
+        Ok(indexed_txs)
+    }
+
+    /// Stream the txs from `from_block_height` up to `to_block_height`,
+    /// batched by their slot in a block, like [`Self::get_txs`].
+    pub async fn stream_txs(
+        &self,
+        from_block_height: u64,
+        to_block_height: u64,
+    ) -> anyhow::Result<
+        impl Stream<Item = anyhow::Result<(Vec<(u64, bool, Vec<u8>)>, u64, u64)>>
+        + use<>,
+    > {
+        let txs = self
+            .tx_repo
+            .stream_txs(from_block_height as i32, to_block_height as i32)
+            .await?;
+
+        // NB: the streamed txs are sorted by their slot in a block, thus
+        // each batch ends at the first tx of the next slot
+        Ok(futures::stream::unfold(
+            (txs, None),
+            |(mut txs, next_tx): (_, Option<TxDb>)| async move {
+                let first_tx = match next_tx {
+                    Some(tx) => tx,
+                    None => match txs.next().await? {
+                        Ok(tx) => tx,
+                        Err(err) => return Some((Err(err), (txs, None))),
+                    },
+                };
+                let (block_height, block_index) =
+                    (first_tx.block_height, first_tx.block_index);
+
+                let mut tx_batch = vec![first_tx];
+                let mut next_tx = None;
+                while let Some(tx) = txs.next().await {
+                    match tx {
+                        Ok(tx)
+                            if (tx.block_height, tx.block_index)
+                                == (block_height, block_index) =>
+                        {
+                            tx_batch.push(tx)
+                        }
+                        Ok(tx) => {
+                            next_tx = Some(tx);
+                            break;
+                        }
+                        Err(err) => return Some((Err(err), (txs, None))),
+                    }
+                }
+
+                let tx_batch = tx_batch
+                    .into_iter()
+                    .map(|tx| {
+                        (
+                            tx.masp_tx_index as u64,
+                            tx.is_masp_fee_payment,
+                            tx.tx_bytes,
+                        )
+                    })
+                    .collect();
+                Some((
+                    Ok((tx_batch, block_height as u64, block_index as u64)),
+                    (txs, next_tx),
+                ))
+            },
+        ))
+    }
 }
@@ -0,0 +1,65 @@
+use anyhow::Context;
+use diesel::pg::data_types::PgNumeric;
+use orm::numeric::numeric_to_i128;
+
+use crate::appstate::AppState;
+use crate::repository::tx_flow::{TxFlowRepository, TxFlowRepositoryTrait};
+
+#[derive(Clone)]
+pub struct TxFlowService {
+    tx_flow_repo: TxFlowRepository,
+}
+
+impl TxFlowService {
+    pub fn new(app_state: AppState) -> Self {
+        Self {
+            tx_flow_repo: TxFlowRepository::new(app_state),
+        }
+    }
+
+    /// Get the value shielded and unshielded in each asset type, and the
+    /// value balance of the shielded pool, between `from_block_height`
+    /// and `to_block_height` (inclusive), along with the number of MASP
+    /// txs moving value of the asset type.
+    pub async fn get_aggregated_flows(
+        &self,
+        from_block_height: u64,
+        to_block_height: u64,
+        asset_type: Option<String>,
+    ) -> anyhow::Result<Vec<(String, i128, i128, i128, u64)>> {
+        let flows = self
+            .tx_flow_repo
+            .get_aggregated_flows(
+                i32::try_from(from_block_height).unwrap_or(i32::MAX),
+                i32::try_from(to_block_height).unwrap_or(i32::MAX),
+                asset_type,
+            )
+            .await?;
+
+        flows
+            .into_iter()
+            .map(
+                |(
+                    asset_type,
+                    transparent_in,
+                    transparent_out,
+                    value_balance,
+                    num_txs,
+                )| {
+                    Ok((
+                        asset_type,
+                        sum_to_i128(transparent_in)?,
+                        sum_to_i128(transparent_out)?,
+                        sum_to_i128(value_balance)?,
+                        num_txs as u64,
+                    ))
+                },
+            )
+            .collect()
+    }
+}
+
+fn sum_to_i128(sum: Option<PgNumeric>) -> anyhow::Result<i128> {
+    sum.map_or(Some(0), |sum| numeric_to_i128(&sum))
+        .context("Summed flows do not fit in an i128")
+}
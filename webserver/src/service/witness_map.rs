@@ -1,35 +1,264 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use shared::bridge_tree::BridgeTree;
 use shared::height::BlockHeight;
+use shared::witness_delta::deserialize_leaves;
+use tokio::sync::{Mutex, mpsc};
 
 use crate::appstate::AppState;
+use crate::repository::tree::{TreeRepository, TreeRepositoryTrait};
 use crate::repository::witness_map::{
     WitnessMapRepository, WitnessMapRepositoryTrait,
 };
 
+/// Number of witnesses computed ahead of the reader, when streaming
+/// witness maps.
+const STREAM_BUFFER_SIZE: usize = 64;
+
+/// The commitment tree as of the last witness map delta read from the
+/// db, from which the witness maps of all heights up to its own are
+/// served.
+#[derive(Clone)]
+struct CachedTree {
+    tree: Arc<BridgeTree>,
+    block_height: i32,
+}
+
 #[derive(Clone)]
 pub struct WitnessMapService {
     witness_map_repo: WitnessMapRepository,
+    tree_repo: TreeRepository,
+    /// NB: only locked to read or replace the cached tree, never while
+    /// it is being rebuilt
+    cached_tree: Arc<std::sync::Mutex<Option<CachedTree>>>,
+    /// Held while the cached tree is being rebuilt, such that concurrent
+    /// requests do not rebuild it more than once.
+    rebuilding_tree: Arc<Mutex<()>>,
 }
 
 impl WitnessMapService {
     pub fn new(app_state: AppState) -> Self {
         Self {
-            witness_map_repo: WitnessMapRepository::new(app_state),
+            witness_map_repo: WitnessMapRepository::new(app_state.clone()),
+            tree_repo: TreeRepository::new(app_state),
+            cached_tree: Arc::new(std::sync::Mutex::new(None)),
+            rebuilding_tree: Arc::new(Mutex::new(())),
         }
     }
 
+    /// Return the witnesses of the notes in the commitment tree as of
+    /// the closest height at or below `block_height`, along with that
+    /// height.
     pub async fn get_witnesses(
         &self,
         block_height: BlockHeight,
     ) -> anyhow::Result<Option<(Vec<(Vec<u8>, u64)>, u64)>> {
-        let (witnesses, closest_height) = self
+        let Some((witnesses, block_height)) =
+            self.witnesses_at(block_height).await?
+        else {
+            return Ok(None);
+        };
+
+        let witnesses =
+            tokio::task::spawn_blocking(move || witnesses.collect())
+                .await
+                .context("Failed to join the witness map computation")?;
+
+        Ok(Some((witnesses, block_height)))
+    }
+
+    /// Stream the witnesses of [`Self::get_witnesses`], computing them
+    /// on a blocking thread as fast as they are read.
+    pub async fn stream_witnesses(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<Option<(BoxStream<'static, (Vec<u8>, u64)>, u64)>> {
+        let Some((witnesses, block_height)) =
+            self.witnesses_at(block_height).await?
+        else {
+            return Ok(None);
+        };
+
+        // NB: the channel is bounded, such that witnesses are only
+        // computed as fast as they are read
+        let (sender, mut receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+
+        tokio::task::spawn_blocking(move || {
+            for witness in witnesses {
+                // NB: stop computing witnesses once the reader is gone
+                if sender.blocking_send(witness).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Some((
+            futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)).boxed(),
+            block_height,
+        )))
+    }
+
+    /// Return the witnesses of the notes in the commitment tree as of
+    /// the closest height at or below `block_height`, along with that
+    /// height.
+    ///
+    /// Witnesses are only computed as they are iterated.
+    async fn witnesses_at(
+        &self,
+        block_height: BlockHeight,
+    ) -> anyhow::Result<
+        Option<(impl Iterator<Item = (Vec<u8>, u64)> + Send + use<>, u64)>,
+    > {
+        let Some(stored_tree) =
+            self.tree_repo.get_at_height(block_height.0 as i32).await?
+        else {
+            return Ok(None);
+        };
+        let size = shared::commitment_tree::size(&stored_tree.tree)?;
+
+        if size == 0 {
+            return Ok(None);
+        }
+
+        let tree = self
+            .tree_up_to(stored_tree.block_height, &stored_tree.tree, size)
+            .await?;
+
+        Ok(Some((
+            (0..size).map(move |note_pos| {
+                (tree.witness_bytes_at(note_pos, size), note_pos as u64)
+            }),
+            stored_tree.block_height as u64,
+        )))
+    }
+
+    /// Return a commitment tree holding at least the `size` leaves of
+    /// `stored_tree`, the commitment tree stored at `block_height`.
+    async fn tree_up_to(
+        &self,
+        block_height: i32,
+        stored_tree: &[u8],
+        size: usize,
+    ) -> anyhow::Result<Arc<BridgeTree>> {
+        let holds_stored_tree = |tree: &BridgeTree| {
+            tree.size() >= size
+                && tree.commitment_tree_bytes_at(size) == stored_tree
+        };
+
+        if let Some(cached) = self.cached_tree() {
+            if holds_stored_tree(&cached.tree) {
+                return Ok(cached.tree);
+            }
+        }
+
+        let _rebuilding = self.rebuilding_tree.lock().await;
+
+        // NB: the tree might have been rebuilt while waiting for the lock
+        if let Some(cached) = self.cached_tree() {
+            if holds_stored_tree(&cached.tree) {
+                return Ok(cached.tree);
+            }
+
+            if cached.block_height < block_height {
+                let leaves = self
+                    .read_leaves(Some(cached.block_height), block_height)
+                    .await?;
+                let mut tree = cached.tree;
+                // NB: the tree is copied, since witnesses might still be
+                // served from the cached one
+                let tree_mut = Arc::make_mut(&mut tree);
+                tokio::task::block_in_place(|| {
+                    leaves
+                        .into_iter()
+                        .try_for_each(|leaf| tree_mut.append(leaf))
+                })?;
+
+                if holds_stored_tree(&tree) {
+                    self.cache_tree(tree.clone(), block_height);
+                    return Ok(tree);
+                }
+            }
+
+            // NB: the db might have been rolled back and recrawled since
+            // the tree was cached, in which case it is rebuilt
+            tracing::info!(
+                block_height,
+                "Discarding cached commitment tree, which does not match the \
+                 db"
+            );
+        }
+
+        let leaves = self.read_leaves(None, block_height).await?;
+        let tree =
+            tokio::task::block_in_place(|| BridgeTree::from_leaves(leaves))?;
+
+        if !holds_stored_tree(&tree) || tree.size() != size {
+            anyhow::bail!(
+                "The leaves of the witness map deltas up to height \
+                 {block_height} do not match the stored commitment tree"
+            );
+        }
+
+        let tree = Arc::new(tree);
+        self.cache_tree(tree.clone(), block_height);
+
+        Ok(tree)
+    }
+
+    fn cached_tree(&self) -> Option<CachedTree> {
+        self.cached_tree
+            .lock()
+            .expect("Cached commitment tree mutex has been poisoned")
+            .clone()
+    }
+
+    fn cache_tree(&self, tree: Arc<BridgeTree>, block_height: i32) {
+        *self
+            .cached_tree
+            .lock()
+            .expect("Cached commitment tree mutex has been poisoned") =
+            Some(CachedTree { tree, block_height });
+    }
+
+    /// Read the leaves appended to the commitment tree above
+    /// `from_block_height` (if any), up to and including
+    /// `to_block_height`.
+    async fn read_leaves(
+        &self,
+        from_block_height: Option<i32>,
+        to_block_height: i32,
+    ) -> anyhow::Result<Vec<shared::bridge_tree::Leaf>> {
+        let mut rows = self
             .witness_map_repo
-            .get_witnesses(block_height.0 as i32)
+            .stream_leaves(from_block_height, to_block_height)
             .await?;
-        let witnesses = witnesses
-            .into_iter()
-            .map(|witness| (witness.witness_bytes, witness.witness_idx as u64))
-            .collect::<Vec<_>>();
-        let non_empty_witnesses = !witnesses.is_empty();
-        Ok(non_empty_witnesses.then_some((witnesses, closest_height as u64)))
+        let mut leaves = Vec::new();
+
+        while let Some(row) = rows.next().await {
+            let (block_height, delta_leaves, tx_bytes) = row?;
+
+            match (delta_leaves, tx_bytes) {
+                (Some(delta_leaves), _) => {
+                    leaves.extend(deserialize_leaves(&delta_leaves)?)
+                }
+                // NB: the leaves of legacy deltas are recovered from the
+                // txs of their height
+                (None, Some(tx_bytes)) => leaves.extend(
+                    shared::transaction::masp_note_commitments(&tx_bytes)
+                        .with_context(|| {
+                            format!(
+                                "Failed to read the leaves of the legacy \
+                                 witness map delta at height {block_height}"
+                            )
+                        })?,
+                ),
+                (None, None) => {}
+            }
+        }
+
+        Ok(leaves)
     }
 }
@@ -1,8 +1,13 @@
 use crate::appstate::AppState;
+use crate::service::asset::AssetService;
+use crate::service::block::BlockService;
+use crate::service::conversion::ConversionService;
 use crate::service::namada_state::NamadaStateService;
 use crate::service::notes_index::NotesIndexService;
+use crate::service::notification::NotificationService;
 use crate::service::tree::TreeService;
 use crate::service::tx::TxService;
+use crate::service::tx_flow::TxFlowService;
 use crate::service::witness_map::WitnessMapService;
 
 #[derive(Clone)]
@@ -12,6 +17,11 @@ pub struct CommonState {
     pub notes_index_service: NotesIndexService,
     pub tx_service: TxService,
     pub namada_state_service: NamadaStateService,
+    pub block_service: BlockService,
+    pub conversion_service: ConversionService,
+    pub asset_service: AssetService,
+    pub tx_flow_service: TxFlowService,
+    pub notification_service: NotificationService,
 }
 
 impl CommonState {
@@ -21,7 +31,12 @@ impl CommonState {
             witness_map_service: WitnessMapService::new(data.clone()),
             notes_index_service: NotesIndexService::new(data.clone()),
             tx_service: TxService::new(data.clone()),
-            namada_state_service: NamadaStateService::new(data),
+            namada_state_service: NamadaStateService::new(data.clone()),
+            block_service: BlockService::new(data.clone()),
+            conversion_service: ConversionService::new(data.clone()),
+            asset_service: AssetService::new(data.clone()),
+            tx_flow_service: TxFlowService::new(data.clone()),
+            notification_service: NotificationService::new(data),
         }
     }
 }